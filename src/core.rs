@@ -24,10 +24,27 @@ mod uniform;
 #[doc(inline)]
 pub use uniform::*;
 
+mod uniform_block;
+#[doc(inline)]
+pub use uniform_block::*;
+
 mod program;
 #[doc(inline)]
 pub use program::*;
 
+#[cfg(not(target_arch = "wasm32"))]
+mod shader_hot_reload;
+mod shader_include;
+mod state_cache;
+
+mod timer_query;
+#[doc(inline)]
+pub use timer_query::*;
+
+mod profiler;
+#[doc(inline)]
+pub use profiler::{ProfileFrame, ProfileScope, ProfileScopeGuard};
+
 mod scissor_box;
 #[doc(inline)]
 pub use scissor_box::*;
@@ -59,12 +76,24 @@ pub enum CoreError {
     ShaderCompilation(String, String, String),
     #[error("failed to link shader program: {0}")]
     ShaderLink(String),
+    #[error("the shader chunk {0} is included but has not been registered")]
+    MissingShaderChunk(String),
+    #[error("failed to read the shader chunk {0} from {1}: {2}")]
+    ShaderChunkRead(String, String, String),
+    #[error("failed parsing compressed texture: {0}")]
+    CompressedTextureParsing(String),
+    #[error("the compressed texture format {0} is not supported by the graphics driver and cannot be decompressed")]
+    UnsupportedCompressedTextureFormat(String),
+    #[error("the layout of the uniform block {0} does not match the std140 layout: {1}")]
+    UniformBlockLayout(String, String),
 }
 
 ///
 /// Applies a 2D/screen space effect to the given viewport. Can for example be used for adding an effect on top of a rendered image.
 /// The fragment shader get the uv coordinates of the viewport (specified by `in vec2 uvs;`),
 /// where uv coordinates of `(0, 0)` corresponds to the bottom left corner of the viewport and `(1, 1)` to the top right corner.
+/// If parallel shader compilation is enabled (see [Context::set_parallel_shader_compile]), the effect is skipped until the program is compiled,
+/// so use [Context::with_blocking_shader_compile] if the effect is only applied once.
 ///
 pub fn apply_effect(
     context: &Context,
@@ -90,7 +119,7 @@ pub fn apply_effect(
             |program| {
                 use_uniforms(program);
                 program.use_vertex_attribute("position", &position_buffer);
-                program.draw_arrays(render_states, viewport, 3);
+                program.draw_arrays(render_states, viewport, PrimitiveTopology::Triangles, 3);
             },
         )
         .expect("Failed compiling shader");
@@ -99,6 +128,7 @@ pub fn apply_effect(
 ///
 /// Applies a 2D/screen space effect to the given viewport of the given side of a cube map.
 /// The fragment shader get the 3D position (specified by `in vec3 pos;`) of the fragment on the cube with minimum position `(-1, -1, -1)` and maximum position `(1, 1, 1)`.
+/// As for [apply_effect], the effect is skipped until the program is compiled if parallel shader compilation is enabled.
 ///
 pub fn apply_cube_effect(
     context: &Context,
@@ -130,7 +160,7 @@ pub fn apply_cube_effect(
                 program.use_uniform("direction", side.direction());
                 program.use_uniform("up", side.up());
                 program.use_vertex_attribute("position", &position_buffer);
-                program.draw_arrays(render_states, viewport, 3);
+                program.draw_arrays(render_states, viewport, PrimitiveTopology::Triangles, 3);
             },
         )
         .expect("Failed compiling shader");
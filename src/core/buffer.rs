@@ -17,6 +17,10 @@ mod uniform_buffer;
 #[doc(inline)]
 pub use uniform_buffer::*;
 
+mod uniform_block_buffer;
+#[doc(inline)]
+pub use uniform_block_buffer::*;
+
 use crate::core::*;
 use data_type::*;
 
@@ -43,12 +47,37 @@ impl BufferDataType for Quat {}
 
 impl<T: BufferDataType + ?Sized> BufferDataType for &T {}
 
+///
+/// A hint to the graphics driver on how often the data in a buffer is changed, which allows the driver to place the data in the most appropriate memory.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BufferUsage {
+    /// The data is set once and used many times.
+    Static,
+    /// The data is changed repeatedly and used many times.
+    Dynamic,
+    /// The data is changed every time before it is used, for example every frame.
+    /// When filling a buffer with this usage, the old data storage is orphaned so that the upload does not have to wait for the GPU to finish using the old data.
+    Stream,
+}
+
+impl BufferUsage {
+    fn gl_usage(&self) -> u32 {
+        match self {
+            BufferUsage::Static => crate::context::STATIC_DRAW,
+            BufferUsage::Dynamic => crate::context::DYNAMIC_DRAW,
+            BufferUsage::Stream => crate::context::STREAM_DRAW,
+        }
+    }
+}
+
 struct Buffer {
     context: Context,
     id: crate::context::Buffer,
     attribute_count: u32,
     data_type: u32,
     data_size: u32,
+    usage: Option<BufferUsage>,
 }
 
 impl Buffer {
@@ -59,6 +88,7 @@ impl Buffer {
             attribute_count: 0,
             data_type: 0,
             data_size: 0,
+            usage: None,
         }
     }
 
@@ -72,16 +102,27 @@ impl Buffer {
 
     pub fn fill<T: BufferDataType>(&mut self, data: &[T]) {
         self.bind();
+        let usage = self.usage();
         unsafe {
-            self.context.buffer_data_u8_slice(
-                crate::context::ARRAY_BUFFER,
-                to_byte_slice(data),
-                if self.attribute_count > 0 {
-                    crate::context::DYNAMIC_DRAW
-                } else {
-                    crate::context::STATIC_DRAW
-                },
-            );
+            if usage == BufferUsage::Stream {
+                // Orphan the old data storage
+                self.context.buffer_data_size(
+                    crate::context::ARRAY_BUFFER,
+                    std::mem::size_of_val(data) as i32,
+                    usage.gl_usage(),
+                );
+                self.context.buffer_sub_data_u8_slice(
+                    crate::context::ARRAY_BUFFER,
+                    0,
+                    to_byte_slice(data),
+                );
+            } else {
+                self.context.buffer_data_u8_slice(
+                    crate::context::ARRAY_BUFFER,
+                    to_byte_slice(data),
+                    usage.gl_usage(),
+                );
+            }
             self.context.bind_buffer(crate::context::ARRAY_BUFFER, None);
         }
         self.attribute_count = data.len() as u32;
@@ -89,6 +130,41 @@ impl Buffer {
         self.data_size = T::size();
     }
 
+    pub fn fill_subset<T: BufferDataType>(&mut self, offset: u32, data: &[T]) {
+        if self.data_type != T::data_type() || self.data_size != T::size() {
+            panic!("the data type of the subset does not match the data type of the buffer");
+        }
+        if offset as usize + data.len() > self.attribute_count as usize {
+            panic!(
+                "cannot fill the subset [{}, {}] of a buffer with {} elements",
+                offset,
+                offset as usize + data.len(),
+                self.attribute_count
+            );
+        }
+        self.bind();
+        unsafe {
+            self.context.buffer_sub_data_u8_slice(
+                crate::context::ARRAY_BUFFER,
+                (offset as usize * std::mem::size_of::<T>()) as i32,
+                to_byte_slice(data),
+            );
+            self.context.bind_buffer(crate::context::ARRAY_BUFFER, None);
+        }
+    }
+
+    pub fn set_usage(&mut self, usage: BufferUsage) {
+        self.usage = Some(usage);
+    }
+
+    pub fn usage(&self) -> BufferUsage {
+        self.usage.unwrap_or(if self.attribute_count > 0 {
+            BufferUsage::Dynamic
+        } else {
+            BufferUsage::Static
+        })
+    }
+
     pub fn attribute_count(&self) -> u32 {
         self.attribute_count
     }
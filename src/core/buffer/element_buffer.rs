@@ -33,6 +33,7 @@ pub struct ElementBuffer {
     id: crate::context::Buffer,
     count: usize,
     data_type: u32,
+    usage: Option<BufferUsage>,
 }
 
 impl ElementBuffer {
@@ -46,6 +47,7 @@ impl ElementBuffer {
             id,
             count: 0,
             data_type: 0,
+            usage: None,
         }
     }
 
@@ -65,17 +67,70 @@ impl ElementBuffer {
     ///
     pub fn fill<T: ElementBufferDataType>(&mut self, data: &[T]) {
         self.bind();
+        let usage = self.usage.unwrap_or(BufferUsage::Static);
         unsafe {
-            self.context.buffer_data_u8_slice(
+            if usage == BufferUsage::Stream {
+                // Orphan the old data storage
+                self.context.buffer_data_size(
+                    crate::context::ELEMENT_ARRAY_BUFFER,
+                    std::mem::size_of_val(data) as i32,
+                    usage.gl_usage(),
+                );
+                self.context.buffer_sub_data_u8_slice(
+                    crate::context::ELEMENT_ARRAY_BUFFER,
+                    0,
+                    to_byte_slice(data),
+                );
+            } else {
+                self.context.buffer_data_u8_slice(
+                    crate::context::ELEMENT_ARRAY_BUFFER,
+                    to_byte_slice(data),
+                    usage.gl_usage(),
+                );
+            }
+            self.context
+                .bind_buffer(crate::context::ELEMENT_ARRAY_BUFFER, None);
+        }
+        self.count = data.len();
+        self.data_type = T::data_type();
+    }
+
+    ///
+    /// Fills a subset of the buffer, starting at the index with the given offset, with the given indices without reallocating the buffer.
+    ///
+    /// # Panic
+    /// Will panic if the data type does not match the data type of the buffer or if the subset is outside the buffer.
+    ///
+    pub fn fill_subset<T: ElementBufferDataType>(&mut self, offset: u32, data: &[T]) {
+        if self.data_type != T::data_type() {
+            panic!("the data type of the subset does not match the data type of the buffer");
+        }
+        if offset as usize + data.len() > self.count {
+            panic!(
+                "cannot fill the subset [{}, {}] of a buffer with {} indices",
+                offset,
+                offset as usize + data.len(),
+                self.count
+            );
+        }
+        self.bind();
+        unsafe {
+            self.context.buffer_sub_data_u8_slice(
                 crate::context::ELEMENT_ARRAY_BUFFER,
+                (offset as usize * std::mem::size_of::<T>()) as i32,
                 to_byte_slice(data),
-                crate::context::STATIC_DRAW,
             );
             self.context
                 .bind_buffer(crate::context::ELEMENT_ARRAY_BUFFER, None);
         }
-        self.count = data.len();
-        self.data_type = T::data_type();
+    }
+
+    ///
+    /// Sets the usage hint which is applied the next time the buffer is filled using [Self::fill].
+    /// The default usage is [BufferUsage::Static].
+    ///
+    pub fn set_usage(&mut self, usage: BufferUsage) {
+        self.usage = Some(usage);
     }
 
     ///
@@ -37,6 +37,25 @@ impl InstanceBuffer {
         self.buffer.fill(data)
     }
 
+    ///
+    /// Fills a subset of the instance buffer, starting at the instance attribute with the given offset, with the given data without reallocating the buffer.
+    /// This is faster than [Self::fill] when only part of the data has changed.
+    ///
+    /// # Panic
+    /// Will panic if the data type does not match the data type of the buffer or if the subset is outside the buffer.
+    ///
+    pub fn fill_subset<T: BufferDataType>(&mut self, offset: u32, data: &[T]) {
+        self.buffer.fill_subset(offset, data);
+    }
+
+    ///
+    /// Sets the usage hint which is applied the next time the buffer is filled using [Self::fill].
+    /// If no usage is set, the usage is [BufferUsage::Static] for the first fill and [BufferUsage::Dynamic] for the following.
+    ///
+    pub fn set_usage(&mut self, usage: BufferUsage) {
+        self.buffer.set_usage(usage);
+    }
+
     ///
     /// The number of values in the buffer.
     ///
@@ -0,0 +1,95 @@
+use crate::core::*;
+
+///
+/// A buffer containing a [UniformBlock], ie. a Rust struct which is uploaded as is to a uniform block with the `std140` layout
+/// (see also [use_uniform_block_buffer](crate::core::Program::use_uniform_block_buffer)).
+/// Can be shared between programs, for example to upload data which is the same for all programs, like the camera, once per frame.
+///
+pub struct UniformBlockBuffer<T: UniformBlock> {
+    context: Context,
+    id: crate::context::Buffer,
+    data: T,
+}
+
+impl<T: UniformBlock> UniformBlockBuffer<T> {
+    ///
+    /// Creates a new uniform block buffer containing the given data.
+    ///
+    /// # Panic
+    /// Will panic if the layout of the Rust struct does not match the `std140` layout, see [UniformBlock::check_layout].
+    ///
+    pub fn new(context: &Context, data: T) -> Self {
+        if let Err(e) = T::check_layout() {
+            panic!("{}", e);
+        }
+        let id = unsafe {
+            let id = context.create_buffer().expect("Failed creating buffer");
+            context.bind_buffer(crate::context::UNIFORM_BUFFER, Some(id));
+            // The size of a uniform block is rounded up to a multiple of 16 bytes
+            context.buffer_data_size(
+                crate::context::UNIFORM_BUFFER,
+                Self::byte_size() as i32,
+                crate::context::DYNAMIC_DRAW,
+            );
+            context.bind_buffer(crate::context::UNIFORM_BUFFER, None);
+            id
+        };
+        let buffer = Self {
+            context: context.clone(),
+            id,
+            data,
+        };
+        buffer.send();
+        buffer
+    }
+
+    ///
+    /// Replaces the data in this buffer with the given data.
+    ///
+    pub fn update(&mut self, data: T) {
+        self.data = data;
+        self.send();
+    }
+
+    ///
+    /// Returns the data in this buffer.
+    ///
+    pub fn get(&self) -> &T {
+        &self.data
+    }
+
+    pub(crate) fn byte_size() -> usize {
+        std::mem::size_of::<T>().div_ceil(16) * 16
+    }
+
+    pub(crate) fn bind(&self, id: u32) {
+        unsafe {
+            self.context
+                .bind_buffer_base(crate::context::UNIFORM_BUFFER, id, Some(self.id))
+        };
+    }
+
+    fn send(&self) {
+        unsafe {
+            // The layout is checked at construction and the members only consist of 32 bit numbers, so there is no padding
+            let bytes = std::slice::from_raw_parts(
+                &self.data as *const T as *const u8,
+                std::mem::size_of::<T>(),
+            );
+            self.context
+                .bind_buffer(crate::context::UNIFORM_BUFFER, Some(self.id));
+            self.context
+                .buffer_sub_data_u8_slice(crate::context::UNIFORM_BUFFER, 0, bytes);
+            self.context
+                .bind_buffer(crate::context::UNIFORM_BUFFER, None);
+        }
+    }
+}
+
+impl<T: UniformBlock> Drop for UniformBlockBuffer<T> {
+    fn drop(&mut self) {
+        unsafe {
+            self.context.delete_buffer(self.id);
+        }
+    }
+}
@@ -3,6 +3,7 @@ use crate::core::*;
 ///
 /// A buffer for transferring a set of uniform variables to the shader program
 /// (see also [use_uniform_block](crate::core::Program::use_uniform_block)).
+/// To upload a Rust struct to a uniform block instead of a list of `f32` values, see [UniformBlockBuffer].
 ///
 pub struct UniformBuffer {
     context: Context,
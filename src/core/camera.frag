@@ -0,0 +1,4 @@
+layout (std140) uniform CameraBlock
+{
+    mat4 viewProjection;
+};
@@ -6,6 +6,12 @@ use std::sync::RwLock;
 #[doc(hidden)]
 pub use crate::context::HasContext;
 
+crate::uniform_block! {
+    pub(super) struct CameraBlock {
+        pub view_projection: Mat4,
+    }
+}
+
 ///
 /// Contains the low-level OpenGL/WebGL graphics context as well as other "global" variables.
 /// Implements Deref with the low-level graphics context as target, so you can call low-level functionality
@@ -14,8 +20,17 @@ pub use crate::context::HasContext;
 #[derive(Clone)]
 pub struct Context {
     context: Arc<crate::context::Context>,
-    pub(super) vao: crate::context::VertexArray,
+    vao: crate::context::VertexArray,
     programs: Arc<RwLock<HashMap<(String, String), Program>>>,
+    compiling_programs: Arc<RwLock<HashMap<(String, String), CompilingProgram>>>,
+    parallel_shader_compile: Arc<RwLock<bool>>,
+    shader_chunks: Arc<RwLock<HashMap<String, String>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    shader_hot_reload: Arc<RwLock<Option<shader_hot_reload::ShaderHotReload>>>,
+    pub(super) profiler: Arc<RwLock<Option<profiler::Profiler>>>,
+    state_cache: Arc<RwLock<state_cache::StateCache>>,
+    scratch_textures: Arc<RwLock<HashMap<&'static str, Texture2DArray>>>,
+    pub(super) camera_block: Arc<RwLock<Option<UniformBlockBuffer<CameraBlock>>>>,
 }
 
 impl Context {
@@ -30,6 +45,8 @@ impl Context {
             if !context.version().is_embedded {
                 // Enable seamless cube map textures - not available on OpenGL ES and WebGL
                 context.enable(crate::context::TEXTURE_CUBE_MAP_SEAMLESS);
+                // Enable setting the point size in the vertex shader - always enabled on OpenGL ES and WebGL
+                context.enable(crate::context::PROGRAM_POINT_SIZE);
             }
             context.pixel_store_i32(crate::context::UNPACK_ALIGNMENT, 1);
             context.pixel_store_i32(crate::context::PACK_ALIGNMENT, 1);
@@ -43,8 +60,19 @@ impl Context {
                 context,
                 vao,
                 programs: Arc::new(RwLock::new(HashMap::new())),
+                compiling_programs: Arc::new(RwLock::new(HashMap::new())),
+                parallel_shader_compile: Arc::new(RwLock::new(false)),
+                shader_chunks: Arc::new(RwLock::new(shader_include::builtin_shader_chunks())),
+                #[cfg(not(target_arch = "wasm32"))]
+                shader_hot_reload: Arc::new(RwLock::new(None)),
+                profiler: Arc::new(RwLock::new(None)),
+                state_cache: Arc::new(RwLock::new(state_cache::StateCache::default())),
+                scratch_textures: Arc::new(RwLock::new(HashMap::new())),
+                camera_block: Arc::new(RwLock::new(None)),
             }
         };
+        // Compiling in parallel is most important on web, where the compilation cannot be cached between runs
+        c.set_parallel_shader_compile(cfg!(target_arch = "wasm32"));
         Ok(c)
     }
 
@@ -52,28 +80,406 @@ impl Context {
     /// Compiles a [Program] with the given vertex and fragment shader source and stores it for later use.
     /// If it has already been created, then it is just returned.
     ///
+    /// If shader hot reloading is enabled (see [Context::enable_shader_hot_reload]), compilation errors are not returned but stored
+    /// and can be retrieved using [Context::shader_errors], in which case the callback is not called.
+    ///
+    /// If parallel shader compilation is enabled (see [Context::set_parallel_shader_compile]), the program is compiled without blocking
+    /// and the callback is not called until the program is ready, which means that whatever is drawn in the callback is skipped until then.
+    /// Use [Context::with_blocking_shader_compile] for rendering that cannot be skipped.
+    ///
     pub fn program(
         &self,
         vertex_shader_source: String,
         fragment_shader_source: String,
         callback: impl FnOnce(&Program),
     ) -> Result<(), CoreError> {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.reload_changed_shader_chunks();
+
         let key = (vertex_shader_source, fragment_shader_source);
         let mut programs = self.programs.write().unwrap();
         if let Some(program) = programs.get(&key) {
             callback(program);
         } else {
-            let program = Program::from_source(self, &key.0, &key.1)?;
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(hot_reload) = self.shader_hot_reload.read().unwrap().as_ref() {
+                if hot_reload.failed_programs.contains(&key) {
+                    // Wait for a change to the shader chunks before trying again
+                    return Ok(());
+                }
+            }
+            let parallel_shader_compile = *self.parallel_shader_compile.read().unwrap();
+            let compiling_program = self.compiling_programs.write().unwrap().remove(&key);
+            let result = match compiling_program {
+                Some(compiling_program)
+                    if !parallel_shader_compile || compiling_program.is_ready() =>
+                {
+                    compiling_program.finish()
+                }
+                Some(compiling_program) => {
+                    self.compiling_programs
+                        .write()
+                        .unwrap()
+                        .insert(key, compiling_program);
+                    return Ok(());
+                }
+                None if parallel_shader_compile => match Program::compile(self, &key.0, &key.1) {
+                    Ok(compiling_program) => {
+                        self.compiling_programs
+                            .write()
+                            .unwrap()
+                            .insert(key, compiling_program);
+                        return Ok(());
+                    }
+                    Err(e) => Err(e),
+                },
+                None => Program::from_source(self, &key.0, &key.1),
+            };
+            let program = match result {
+                Ok(program) => program,
+                #[cfg(not(target_arch = "wasm32"))]
+                Err(e) if self.shader_hot_reload.read().unwrap().is_some() => {
+                    if let Some(hot_reload) = self.shader_hot_reload.write().unwrap().as_mut() {
+                        hot_reload.errors.push(e);
+                        hot_reload.failed_programs.insert(key);
+                    }
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
             callback(&program);
             programs.insert(key, program);
         }
         Ok(())
     }
 
+    ///
+    /// Calls the callback with a texture array with RGBA u8 layers which is kept between calls with the same name,
+    /// so that intermediate textures used when rendering do not have to be allocated every frame.
+    /// The texture is only allocated again if the size changes and the content is undefined at the start of each call.
+    ///
+    pub(crate) fn scratch_texture_array<T>(
+        &self,
+        name: &'static str,
+        width: u32,
+        height: u32,
+        depth: u32,
+        callback: impl FnOnce(&mut Texture2DArray) -> T,
+    ) -> T {
+        let mut scratch_textures = self.scratch_textures.write().unwrap();
+        let texture = scratch_textures
+            .entry(name)
+            .and_modify(|texture| {
+                if texture.width() != width
+                    || texture.height() != height
+                    || texture.depth() != depth
+                {
+                    *texture = self.new_scratch_texture_array(width, height, depth);
+                }
+            })
+            .or_insert_with(|| self.new_scratch_texture_array(width, height, depth));
+        callback(texture)
+    }
+
+    fn new_scratch_texture_array(&self, width: u32, height: u32, depth: u32) -> Texture2DArray {
+        Texture2DArray::new_empty::<[u8; 4]>(
+            self,
+            width,
+            height,
+            depth,
+            Interpolation::Nearest,
+            Interpolation::Nearest,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        )
+    }
+
+    ///
+    /// Starts compiling the [Program] with the given vertex and fragment shader source, if it is not already compiled,
+    /// so that it is ready when it is used with [Context::program], for example when rendering a new material for the first time.
+    /// If parallel shader compilation is enabled (see [Context::set_parallel_shader_compile]), this does not block,
+    /// otherwise the program is compiled before returning.
+    /// See also [precompile](crate::renderer::precompile) for compiling the programs needed to render a geometry with a material.
+    ///
+    pub fn precompile_program(
+        &self,
+        vertex_shader_source: String,
+        fragment_shader_source: String,
+    ) -> Result<(), CoreError> {
+        self.program(vertex_shader_source, fragment_shader_source, |_| {})
+    }
+
+    ///
+    /// Returns whether or not there are programs which have started compiling in parallel but which have not yet been used after the compilation finished.
+    ///
+    pub fn is_compiling_programs(&self) -> bool {
+        !self.compiling_programs.read().unwrap().is_empty()
+    }
+
+    ///
+    /// Returns whether or not half float color textures can be rendered into, which is always the case on desktop
+    /// but requires the `EXT_color_buffer_float` or `EXT_color_buffer_half_float` extension on OpenGL ES and WebGL.
+    ///
+    pub fn is_half_float_render_target_supported(&self) -> bool {
+        if !self.version().is_embedded {
+            return true;
+        }
+        let extensions = self.supported_extensions();
+        extensions.contains("EXT_color_buffer_float")
+            || extensions.contains("GL_EXT_color_buffer_float")
+            || extensions.contains("EXT_color_buffer_half_float")
+            || extensions.contains("GL_EXT_color_buffer_half_float")
+    }
+
+    ///
+    /// Returns whether or not the graphics driver supports compiling shader programs in parallel with the rendering
+    /// (using the `KHR_parallel_shader_compile` extension), see [Context::set_parallel_shader_compile].
+    ///
+    pub fn is_parallel_shader_compile_supported(&self) -> bool {
+        let extensions = self.supported_extensions();
+        extensions.contains("KHR_parallel_shader_compile")
+            || extensions.contains("GL_KHR_parallel_shader_compile")
+            || extensions.contains("GL_ARB_parallel_shader_compile")
+    }
+
+    ///
+    /// Enables or disables compiling shader programs in parallel with the rendering, see [Context::program].
+    /// This avoids the hitch when a program is used for the first time, at the cost of skipping the drawing with the program until it is compiled.
+    /// Enabled by default on web and only has an effect if supported by the graphics driver (see [Context::is_parallel_shader_compile_supported]).
+    /// Rendering which only happens once, like shadow maps, picking and copying textures, always waits for the programs to compile (see [Context::with_blocking_shader_compile]),
+    /// while the per frame passes, like the deferred lighting, fog and FXAA, are skipped until they are ready.
+    /// There is no cache of the compiled program binaries, so the programs are compiled again every time the application starts.
+    ///
+    pub fn set_parallel_shader_compile(&self, enabled: bool) {
+        *self.parallel_shader_compile.write().unwrap() =
+            enabled && self.is_parallel_shader_compile_supported();
+    }
+
+    ///
+    /// Calls the callback with parallel shader compilation disabled (see [Context::set_parallel_shader_compile]),
+    /// so that [Context::program] always compiles the program before calling its callback.
+    /// Use this for rendering which only happens once and therefore cannot be skipped until the program is ready,
+    /// for example rendering a shadow map, picking or applying an effect to a texture.
+    ///
+    pub fn with_blocking_shader_compile<T>(&self, callback: impl FnOnce() -> T) -> T {
+        let parallel_shader_compile =
+            std::mem::replace(&mut *self.parallel_shader_compile.write().unwrap(), false);
+        let result = callback();
+        *self.parallel_shader_compile.write().unwrap() = parallel_shader_compile;
+        result
+    }
+
+    ///
+    /// Enables hot reloading of the built-in material and effect shaders, which is useful while developing shaders.
+    /// Instead of using the shader source embedded in the binary, the shader chunks (see [Context::set_shader_chunk]) are loaded from the source files of this crate on disk
+    /// and whenever a source file is changed, all programs which include the changed chunk are recompiled.
+    /// If a program fails to compile, the last successfully compiled version of the program continues to be used
+    /// and the compilation error can be retrieved using [Context::shader_errors] instead of causing a panic.
+    /// Use [Context::watch_shader_chunk] to also hot reload your own shader chunks.
+    ///
+    /// The files are found relative to the directory of this crate at compile time, so this only works on the machine where the application is compiled.
+    /// Not available on web.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn enable_shader_hot_reload(&self) -> Result<(), CoreError> {
+        let source_directory = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        self.shader_hot_reload
+            .write()
+            .unwrap()
+            .get_or_insert_with(shader_hot_reload::ShaderHotReload::new);
+        for (name, path, _) in shader_include::BUILTIN_SHADER_CHUNKS {
+            self.watch_shader_chunk(*name, source_directory.join(path))?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Registers a shader chunk (see [Context::set_shader_chunk]) with the source in the file at the given path
+    /// and, if shader hot reloading is enabled (see [Context::enable_shader_hot_reload]), recompiles all programs which include the chunk whenever the file is changed.
+    /// Not available on web.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn watch_shader_chunk(
+        &self,
+        name: impl Into<String>,
+        path: impl Into<std::path::PathBuf>,
+    ) -> Result<(), CoreError> {
+        let name = name.into();
+        let path = path.into();
+        let source = if let Some(hot_reload) = self.shader_hot_reload.write().unwrap().as_mut() {
+            hot_reload.watch(name.clone(), path)?
+        } else {
+            std::fs::read_to_string(&path).map_err(|e| {
+                CoreError::ShaderChunkRead(name.clone(), path.display().to_string(), e.to_string())
+            })?
+        };
+        self.set_shader_chunk(name, source);
+        Ok(())
+    }
+
+    ///
+    /// Returns and removes the errors that have happened while hot reloading shaders since the last call to this method,
+    /// for example [CoreError::ShaderCompilation] if a changed shader fails to compile.
+    /// Not available on web.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn shader_errors(&self) -> Vec<CoreError> {
+        self.shader_hot_reload
+            .write()
+            .unwrap()
+            .as_mut()
+            .map(|hot_reload| std::mem::take(&mut hot_reload.errors))
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_changed_shader_chunks(&self) {
+        let changes = match self.shader_hot_reload.write().unwrap().as_mut() {
+            Some(hot_reload) => {
+                let changes = hot_reload.poll();
+                if !changes.is_empty() {
+                    hot_reload.failed_programs.clear();
+                }
+                changes
+            }
+            None => return,
+        };
+        if !changes.is_empty() {
+            // Programs which are still compiling might include an old version of a chunk, so they are discarded and compiled again when used
+            for (_, compiling_program) in self.compiling_programs.write().unwrap().drain() {
+                compiling_program.finish().ok();
+            }
+        }
+        for (name, source) in changes {
+            // The programs are compiled again right away instead of being evicted, so that a program is kept if the new source fails to compile
+            self.shader_chunks
+                .write()
+                .unwrap()
+                .insert(name.clone(), source);
+            let mut programs = self.programs.write().unwrap();
+            for ((vertex_shader_source, fragment_shader_source), program) in programs.iter_mut() {
+                if program.includes_chunk(&name) {
+                    match Program::from_source(self, vertex_shader_source, fragment_shader_source) {
+                        Ok(new_program) => *program = new_program,
+                        Err(e) => {
+                            if let Some(hot_reload) =
+                                self.shader_hot_reload.write().unwrap().as_mut()
+                            {
+                                hot_reload.errors.push(e);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    ///
+    /// Registers a named chunk of shader source which can then be included in any shader source given to [Context::program] or [Program::from_source]
+    /// using an `#include "name"` directive on a separate line.
+    /// A chunk can itself include other chunks and each chunk is only included once per shader, so it is safe to include the same chunk several times.
+    /// If a chunk with the same name is already registered, it is replaced and the programs which include it are compiled again the next time they are used.
+    ///
+    /// The following chunks are always available:
+    /// - `shared`: Common constants and functions, for example `saturate`, sRGB conversion (`srgb_from_rgb` and `rgb_from_srgb`) and tone mapping.
+    /// - `light_shared`: The BRDF functions used by the built-in lights (for example `calculate_light`) and shadow lookup (`calculate_shadow`).
+    /// - `normal_mapping`: The function `vec3 perturb_normal(vec3 normal, vec3 tangent, vec3 bitangent, vec3 normal_map_value, float normal_scale)`.
+    /// - The fragment shaders of the built-in materials and effects, for example `physical_material` and `fxaa_effect`.
+    ///
+    pub fn set_shader_chunk(&self, name: impl Into<String>, source: impl Into<String>) {
+        let name = name.into();
+        let replaced = self
+            .shader_chunks
+            .write()
+            .unwrap()
+            .insert(name.clone(), source.into())
+            .is_some();
+        if replaced {
+            self.programs
+                .write()
+                .unwrap()
+                .retain(|_, program| !program.includes_chunk(&name));
+            self.compiling_programs
+                .write()
+                .unwrap()
+                .retain(|_, program| !program.includes_chunk(&name));
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(hot_reload) = self.shader_hot_reload.write().unwrap().as_mut() {
+                hot_reload.failed_programs.clear();
+            }
+        }
+    }
+
+    ///
+    /// Returns the source of the shader chunk with the given name if it is registered (see [Context::set_shader_chunk]).
+    ///
+    pub fn shader_chunk(&self, name: &str) -> Option<String> {
+        self.shader_chunks.read().unwrap().get(name).cloned()
+    }
+
+    pub(super) fn shader_chunks(&self) -> std::sync::RwLockReadGuard<'_, HashMap<String, String>> {
+        self.shader_chunks.read().unwrap()
+    }
+
+    ///
+    /// Enables the frame profiler which measures the CPU and GPU time spent inside the scopes created with [Context::profile_scope].
+    /// The render functions in this crate, for example the geometry and lighting passes in [RenderTarget::render](crate::renderer::RenderTarget::render), shadow map generation and effects,
+    /// are measured automatically. The timings of the latest finished frame are available from [Context::profiler_frame].
+    ///
+    /// The GPU timings require timer queries (see [GpuTimer::is_supported]) and while the profiler is enabled, no other [GpuTimer] can be used.
+    ///
+    pub fn enable_profiler(&self) {
+        self.profiler
+            .write()
+            .unwrap()
+            .get_or_insert_with(profiler::Profiler::new);
+    }
+
+    ///
+    /// Disables the frame profiler, see [Context::enable_profiler].
+    ///
+    pub fn disable_profiler(&self) {
+        *self.profiler.write().unwrap() = None;
+    }
+
+    ///
+    /// Measures the CPU and GPU time spent from now until the returned guard is dropped, if the profiler is enabled (see [Context::enable_profiler]).
+    /// Scopes can be nested and the timings are reported as a tree, see [ProfileFrame].
+    ///
+    pub fn profile_scope(&self, name: &str) -> ProfileScopeGuard<'_> {
+        ProfileScopeGuard::new(self, name)
+    }
+
+    ///
+    /// Ends the current profiler frame and starts a new one. This is called automatically at the end of each frame in [Window::render_loop](crate::window::Window::render_loop),
+    /// so it should only be called when using a custom render loop.
+    ///
+    pub fn end_profiler_frame(&self) {
+        if let Some(profiler) = self.profiler.write().unwrap().as_mut() {
+            profiler.end_frame(self);
+        }
+    }
+
+    ///
+    /// Returns the timings of the latest frame for which all of the GPU timings are available, which is usually a few frames behind the current frame.
+    /// Returns `None` if the profiler is disabled or if no frame is finished yet.
+    ///
+    pub fn profiler_frame(&self) -> Option<ProfileFrame> {
+        self.profiler
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|profiler| profiler.latest_frame().cloned())
+    }
+
     ///
     /// Set the scissor test for this context (see [ScissorBox]).
     ///
     pub fn set_scissor(&self, scissor_box: ScissorBox) {
+        if !state_cache::changed(&mut self.state_cache.write().unwrap().scissor, scissor_box) {
+            return;
+        }
         unsafe {
             if scissor_box.width > 0 && scissor_box.height > 0 {
                 self.enable(crate::context::SCISSOR_TEST);
@@ -93,6 +499,12 @@ impl Context {
     /// Set the viewport for this context (See [Viewport]).
     ///
     pub fn set_viewport(&self, viewport: Viewport) {
+        if !state_cache::changed(
+            &mut self.state_cache.write().unwrap().viewport,
+            (viewport.x, viewport.y, viewport.width, viewport.height),
+        ) {
+            return;
+        }
         unsafe {
             self.viewport(
                 viewport.x,
@@ -107,6 +519,9 @@ impl Context {
     /// Set the face culling for this context (see [Cull]).
     ///
     pub fn set_cull(&self, cull: Cull) {
+        if !state_cache::changed(&mut self.state_cache.write().unwrap().cull, cull) {
+            return;
+        }
         unsafe {
             match cull {
                 Cull::None => {
@@ -132,6 +547,12 @@ impl Context {
     /// Set the write mask for this context (see [WriteMask]).
     ///
     pub fn set_write_mask(&self, write_mask: WriteMask) {
+        if !state_cache::changed(
+            &mut self.state_cache.write().unwrap().write_mask,
+            write_mask,
+        ) {
+            return;
+        }
         unsafe {
             self.color_mask(
                 write_mask.red,
@@ -147,8 +568,14 @@ impl Context {
     /// Set the depth test for this context (see [DepthTest]).
     ///
     pub fn set_depth_test(&self, depth_test: DepthTest) {
+        self.set_depth_test_enabled(true);
+        if !state_cache::changed(
+            &mut self.state_cache.write().unwrap().depth_test,
+            depth_test,
+        ) {
+            return;
+        }
         unsafe {
-            self.enable(crate::context::DEPTH_TEST);
             match depth_test {
                 DepthTest::Never => {
                     self.depth_func(crate::context::NEVER);
@@ -182,6 +609,9 @@ impl Context {
     /// Set the blend state for this context (see [Blend]).
     ///
     pub fn set_blend(&self, blend: Blend) {
+        if !state_cache::changed(&mut self.state_cache.write().unwrap().blend, blend) {
+            return;
+        }
         unsafe {
             if let Blend::Enabled {
                 source_rgb_multiplier,
@@ -209,6 +639,82 @@ impl Context {
         }
     }
 
+    ///
+    /// Set the stencil test for this context (see [StencilTest]).
+    ///
+    pub fn set_stencil_test(&self, stencil_test: StencilTest) {
+        if !state_cache::changed(
+            &mut self.state_cache.write().unwrap().stencil_test,
+            stencil_test,
+        ) {
+            return;
+        }
+        unsafe {
+            if let StencilTest::Enabled { front, back } = stencil_test {
+                self.enable(crate::context::STENCIL_TEST);
+                for (face, state) in [(crate::context::FRONT, front), (crate::context::BACK, back)]
+                {
+                    self.stencil_func_separate(
+                        face,
+                        Self::stencil_const_from_function(state.function),
+                        state.reference as i32,
+                        state.read_mask as u32,
+                    );
+                    self.stencil_op_separate(
+                        face,
+                        Self::stencil_const_from_operation(state.stencil_fail),
+                        Self::stencil_const_from_operation(state.depth_fail),
+                        Self::stencil_const_from_operation(state.pass),
+                    );
+                    self.stencil_mask_separate(face, state.write_mask as u32);
+                }
+            } else {
+                self.disable(crate::context::STENCIL_TEST);
+            }
+        }
+    }
+
+    fn set_depth_test_enabled(&self, enabled: bool) {
+        if state_cache::changed(
+            &mut self.state_cache.write().unwrap().depth_test_enabled,
+            enabled,
+        ) {
+            unsafe {
+                if enabled {
+                    self.enable(crate::context::DEPTH_TEST);
+                } else {
+                    self.disable(crate::context::DEPTH_TEST);
+                }
+            }
+        }
+    }
+
+    fn stencil_const_from_function(function: StencilFunction) -> u32 {
+        match function {
+            StencilFunction::Never => crate::context::NEVER,
+            StencilFunction::Less => crate::context::LESS,
+            StencilFunction::Equal => crate::context::EQUAL,
+            StencilFunction::LessOrEqual => crate::context::LEQUAL,
+            StencilFunction::Greater => crate::context::GREATER,
+            StencilFunction::NotEqual => crate::context::NOTEQUAL,
+            StencilFunction::GreaterOrEqual => crate::context::GEQUAL,
+            StencilFunction::Always => crate::context::ALWAYS,
+        }
+    }
+
+    fn stencil_const_from_operation(operation: StencilOperation) -> u32 {
+        match operation {
+            StencilOperation::Keep => crate::context::KEEP,
+            StencilOperation::Zero => crate::context::ZERO,
+            StencilOperation::Replace => crate::context::REPLACE,
+            StencilOperation::Increment => crate::context::INCR,
+            StencilOperation::IncrementWrap => crate::context::INCR_WRAP,
+            StencilOperation::Decrement => crate::context::DECR,
+            StencilOperation::DecrementWrap => crate::context::DECR_WRAP,
+            StencilOperation::Invert => crate::context::INVERT,
+        }
+    }
+
     fn blend_const_from_multiplier(multiplier: BlendMultiplierType) -> u32 {
         match multiplier {
             BlendMultiplierType::Zero => crate::context::ZERO,
@@ -236,16 +742,113 @@ impl Context {
 
     ///
     /// Set the render states for this context (see [RenderStates]).
+    /// States that are already set are not set again, see [Context::invalidate_state_cache].
     ///
     pub fn set_render_states(&self, render_states: RenderStates) {
         self.set_cull(render_states.cull);
         self.set_write_mask(render_states.write_mask);
         if !render_states.write_mask.depth && render_states.depth_test == DepthTest::Always {
-            unsafe { self.disable(crate::context::DEPTH_TEST) }
+            self.set_depth_test_enabled(false);
         } else {
             self.set_depth_test(render_states.depth_test);
         }
         self.set_blend(render_states.blend);
+        self.set_stencil_test(render_states.stencil);
+    }
+
+    ///
+    /// Forgets the graphics state which is cached to avoid setting the same state several times, for example the render states, viewport and the program in use.
+    /// This must be called after changing any of that state using the low-level graphics context directly (see [context](crate::context)),
+    /// for example when integrating another library which renders using the same graphics context, otherwise the state might not be set correctly afterwards.
+    ///
+    pub fn invalidate_state_cache(&self) {
+        *self.state_cache.write().unwrap() = state_cache::StateCache::default();
+    }
+
+    ///
+    /// Uses the given program, if it is not already in use.
+    ///
+    pub(super) fn set_program(&self, program: Option<crate::context::Program>) {
+        if state_cache::changed(&mut self.state_cache.write().unwrap().program, program) {
+            unsafe {
+                self.use_program(program);
+            }
+        }
+    }
+
+    ///
+    /// Forgets that the given program is in use, if it is, since it is about to be deleted.
+    ///
+    pub(super) fn forget_program(&self, program: crate::context::Program) {
+        let mut state_cache = self.state_cache.write().unwrap();
+        if state_cache.program == Some(Some(program)) {
+            state_cache.program = None;
+        }
+    }
+
+    ///
+    /// Forgets the stencil test state, since the stencil write mask is about to be changed directly.
+    ///
+    pub(super) fn forget_stencil_test(&self) {
+        self.state_cache.write().unwrap().stencil_test = None;
+    }
+    ///
+    /// Binds the vertex array object which is used for all vertex attributes, if it is not already bound.
+    ///
+    pub(super) fn bind_vertex_array_object(&self) {
+        let mut state_cache = self.state_cache.write().unwrap();
+        if !state_cache.vertex_array_bound {
+            unsafe {
+                self.bind_vertex_array(Some(self.vao));
+            }
+            state_cache.vertex_array_bound = true;
+        }
+    }
+
+    ///
+    /// Enables the vertex attribute at the given location for the next draw call, if it is not already enabled.
+    ///
+    pub(super) fn enable_vertex_attribute(&self, location: u32) {
+        self.bind_vertex_array_object();
+        let mut state_cache = self.state_cache.write().unwrap();
+        state_cache.used_attributes.insert(location);
+        let newly_enabled = match state_cache.enabled_attributes.as_mut() {
+            Some(enabled_attributes) => enabled_attributes.insert(location),
+            None => true,
+        };
+        if newly_enabled {
+            unsafe {
+                self.enable_vertex_attrib_array(location);
+            }
+        }
+    }
+
+    ///
+    /// Disables the vertex attributes which have not been enabled since the last call to this method, ie. which are not used by the upcoming draw call.
+    ///
+    pub(super) fn disable_unused_vertex_attributes(&self) {
+        self.bind_vertex_array_object();
+        let mut state_cache = self.state_cache.write().unwrap();
+        let used_attributes = std::mem::take(&mut state_cache.used_attributes);
+        match state_cache.enabled_attributes.as_ref() {
+            Some(enabled_attributes) => {
+                for location in enabled_attributes.difference(&used_attributes) {
+                    unsafe {
+                        self.disable_vertex_attrib_array(*location);
+                    }
+                }
+            }
+            None => {
+                let max_attributes =
+                    unsafe { self.get_parameter_i32(crate::context::MAX_VERTEX_ATTRIBS) } as u32;
+                for location in (0..max_attributes).filter(|l| !used_attributes.contains(l)) {
+                    unsafe {
+                        self.disable_vertex_attrib_array(location);
+                    }
+                }
+            }
+        }
+        state_cache.enabled_attributes = Some(used_attributes);
     }
 
     ///
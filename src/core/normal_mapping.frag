@@ -0,0 +1,7 @@
+
+// applies a value sampled from a tangent space normal map to the given normal
+vec3 perturb_normal(vec3 normal, vec3 tangent, vec3 bitangent, vec3 normal_map_value, float normal_scale)
+{
+    mat3 tbn = mat3(tangent, bitangent, normal);
+    return tbn * ((2.0 * normal_map_value - 1.0) * vec3(normal_scale, normal_scale, 1.0));
+}
@@ -0,0 +1,369 @@
+use crate::core::*;
+#[cfg(target_arch = "wasm32")]
+use instant::Instant;
+use std::collections::VecDeque;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// The maximum number of frames waiting for GPU timings before the oldest frame is discarded.
+const MAX_PENDING_FRAMES: usize = 4;
+
+///
+/// The CPU and GPU timings of a named scope measured by the profiler, see [Context::profile_scope].
+///
+#[derive(Clone, Debug)]
+pub struct ProfileScope {
+    /// The name of the scope.
+    pub name: String,
+    /// The time from the start of the frame to the start of the scope on the CPU.
+    pub start: Duration,
+    /// The time spent on the CPU inside the scope, including the time spent in the child scopes.
+    pub cpu_time: Duration,
+    /// The time spent on the GPU executing the commands issued inside the scope, including the time spent in the child scopes.
+    /// `None` if timer queries are not supported, see [GpuTimer::is_supported].
+    pub gpu_time: Option<Duration>,
+    /// The scopes nested inside this scope.
+    pub children: Vec<ProfileScope>,
+}
+
+///
+/// The timings of all of the scopes measured by the profiler during one frame, see [Context::profiler_frame].
+///
+#[derive(Clone, Debug)]
+pub struct ProfileFrame {
+    /// The index of the frame, counting from when the profiler was enabled.
+    pub index: u64,
+    /// The time from the start to the end of the frame on the CPU.
+    pub cpu_time: Duration,
+    /// The scopes measured during the frame which are not nested inside another scope.
+    pub scopes: Vec<ProfileScope>,
+}
+
+impl ProfileFrame {
+    ///
+    /// Returns the total time spent on the GPU executing the commands issued inside the top level scopes,
+    /// or `None` if timer queries are not supported.
+    ///
+    pub fn gpu_time(&self) -> Option<Duration> {
+        self.scopes.iter().map(|scope| scope.gpu_time).sum()
+    }
+
+    ///
+    /// Returns the timings in the [Chrome trace event format](https://docs.google.com/document/d/1CvAClvFfyA8R-PhYUmn5OfmGqvmKx1K6lGj4DkUOy6c),
+    /// which can be loaded into `chrome://tracing` or [Perfetto](https://ui.perfetto.dev).
+    /// The CPU timings are placed on thread 0 and the GPU timings on thread 1.
+    /// Since only the duration of the GPU work is measured, the GPU timings start at the same time as the corresponding CPU timings.
+    ///
+    pub fn to_chrome_trace(&self) -> String {
+        let mut events = Vec::new();
+        events.push(format!(
+            "{{\"name\":\"Frame {}\",\"ph\":\"X\",\"ts\":0,\"dur\":{},\"pid\":0,\"tid\":0}}",
+            self.index,
+            self.cpu_time.as_micros()
+        ));
+        for scope in self.scopes.iter() {
+            add_chrome_trace_events(scope, &mut events);
+        }
+        format!("{{\"traceEvents\":[{}]}}", events.join(","))
+    }
+}
+
+fn add_chrome_trace_events(scope: &ProfileScope, events: &mut Vec<String>) {
+    let name = json_escape(&scope.name);
+    events.push(format!(
+        "{{\"name\":\"{}\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":0,\"tid\":0}}",
+        name,
+        scope.start.as_micros(),
+        scope.cpu_time.as_micros()
+    ));
+    if let Some(gpu_time) = scope.gpu_time {
+        events.push(format!(
+            "{{\"name\":\"{}\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":0,\"tid\":1}}",
+            name,
+            scope.start.as_micros(),
+            gpu_time.as_micros()
+        ));
+    }
+    for child in scope.children.iter() {
+        add_chrome_trace_events(child, events);
+    }
+}
+
+fn json_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+///
+/// Measures the scope with the given name until it is dropped, see [Context::profile_scope].
+///
+#[must_use = "the scope is measured until the guard is dropped"]
+pub struct ProfileScopeGuard<'a> {
+    context: Option<&'a Context>,
+}
+
+impl<'a> ProfileScopeGuard<'a> {
+    pub(super) fn new(context: &'a Context, name: &str) -> Self {
+        let enabled = if let Some(profiler) = context.profiler.write().unwrap().as_mut() {
+            profiler.begin_scope(context, name);
+            true
+        } else {
+            false
+        };
+        Self {
+            context: if enabled { Some(context) } else { None },
+        }
+    }
+}
+
+impl Drop for ProfileScopeGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(context) = self.context {
+            if let Some(profiler) = context.profiler.write().unwrap().as_mut() {
+                profiler.end_scope(context);
+            }
+        }
+    }
+}
+
+struct PendingScope {
+    name: String,
+    start: Instant,
+    cpu_time: Duration,
+    gpu_timers: Option<Vec<GpuTimer>>,
+    children: Vec<PendingScope>,
+}
+
+impl PendingScope {
+    fn is_ready(&self) -> bool {
+        self.gpu_timers
+            .iter()
+            .flatten()
+            .all(|timer| timer.try_result().is_some())
+            && self.children.iter().all(|child| child.is_ready())
+    }
+
+    ///
+    /// Resolves the timings of this scope and its children and returns the GPU timers to the given pool.
+    ///
+    fn resolve(self, frame_start: Instant, free_timers: &mut Vec<GpuTimer>) -> ProfileScope {
+        let children = self
+            .children
+            .into_iter()
+            .map(|child| child.resolve(frame_start, free_timers))
+            .collect::<Vec<_>>();
+        // The timers of this scope are paused while measuring the child scopes, so the time spent in the children is added
+        let gpu_time = self.gpu_timers.map(|timers| {
+            let own_time: Duration = timers.iter().filter_map(|timer| timer.try_result()).sum();
+            free_timers.extend(timers);
+            own_time
+                + children
+                    .iter()
+                    .filter_map(|child| child.gpu_time)
+                    .sum::<Duration>()
+        });
+        ProfileScope {
+            name: self.name,
+            start: self.start.duration_since(frame_start),
+            cpu_time: self.cpu_time,
+            gpu_time,
+            children,
+        }
+    }
+
+    ///
+    /// Returns the GPU timers of this scope and its children to the given pool without resolving the timings.
+    ///
+    fn recycle(self, free_timers: &mut Vec<GpuTimer>) {
+        free_timers.extend(self.gpu_timers.into_iter().flatten());
+        for child in self.children {
+            child.recycle(free_timers);
+        }
+    }
+}
+
+struct PendingFrame {
+    index: u64,
+    start: Instant,
+    cpu_time: Duration,
+    scopes: Vec<PendingScope>,
+}
+
+///
+/// Records the scopes of the current frame and keeps the previous frames until their GPU timings are available.
+///
+pub(super) struct Profiler {
+    frame_index: u64,
+    frame_start: Instant,
+    open_scopes: Vec<PendingScope>,
+    scopes: Vec<PendingScope>,
+    pending_frames: VecDeque<PendingFrame>,
+    latest_frame: Option<ProfileFrame>,
+    /// GPU timers which are no longer in use and can be reused instead of creating new query objects.
+    free_timers: Vec<GpuTimer>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self {
+            frame_index: 0,
+            frame_start: Instant::now(),
+            open_scopes: Vec::new(),
+            scopes: Vec::new(),
+            pending_frames: VecDeque::new(),
+            latest_frame: None,
+            free_timers: Vec::new(),
+        }
+    }
+
+    fn start_timer(&mut self, context: &Context) -> Option<GpuTimer> {
+        let timer = self.free_timers.pop().or_else(|| GpuTimer::new(context))?;
+        timer.begin();
+        Some(timer)
+    }
+
+    fn begin_scope(&mut self, context: &Context, name: &str) {
+        // Timer queries cannot be nested, so the measurement of the parent scope is paused while measuring the child scope
+        if let Some(parent) = self.open_scopes.last() {
+            if let Some(timer) = parent.gpu_timers.as_ref().and_then(|timers| timers.last()) {
+                timer.end();
+            }
+        }
+        let gpu_timers = self.start_timer(context).map(|timer| vec![timer]);
+        self.open_scopes.push(PendingScope {
+            name: name.to_owned(),
+            start: Instant::now(),
+            cpu_time: Duration::ZERO,
+            gpu_timers,
+            children: Vec::new(),
+        });
+    }
+
+    fn end_scope(&mut self, context: &Context) {
+        let mut scope = if let Some(scope) = self.open_scopes.pop() {
+            scope
+        } else {
+            return;
+        };
+        scope.cpu_time = scope.start.elapsed();
+        if let Some(timer) = scope.gpu_timers.as_ref().and_then(|timers| timers.last()) {
+            timer.end();
+        }
+        if !self.open_scopes.is_empty() {
+            let timer = if self.open_scopes.last().unwrap().gpu_timers.is_some() {
+                self.start_timer(context)
+            } else {
+                None
+            };
+            let parent = self.open_scopes.last_mut().unwrap();
+            if let (Some(timers), Some(timer)) = (parent.gpu_timers.as_mut(), timer) {
+                timers.push(timer);
+            }
+            parent.children.push(scope);
+        } else {
+            self.scopes.push(scope);
+        }
+    }
+
+    pub fn end_frame(&mut self, context: &Context) {
+        // Close any scopes that are still open, for example if a guard is kept alive across frames
+        while !self.open_scopes.is_empty() {
+            self.end_scope(context);
+        }
+        let now = Instant::now();
+        self.pending_frames.push_back(PendingFrame {
+            index: self.frame_index,
+            start: self.frame_start,
+            cpu_time: now.duration_since(self.frame_start),
+            scopes: std::mem::take(&mut self.scopes),
+        });
+        self.frame_index += 1;
+        self.frame_start = now;
+
+        while self
+            .pending_frames
+            .front()
+            .map(|frame| frame.scopes.iter().all(|scope| scope.is_ready()))
+            .unwrap_or(false)
+        {
+            let frame = self.pending_frames.pop_front().unwrap();
+            let free_timers = &mut self.free_timers;
+            self.latest_frame = Some(ProfileFrame {
+                index: frame.index,
+                cpu_time: frame.cpu_time,
+                scopes: frame
+                    .scopes
+                    .into_iter()
+                    .map(|scope| scope.resolve(frame.start, free_timers))
+                    .collect(),
+            });
+        }
+        while self.pending_frames.len() > MAX_PENDING_FRAMES {
+            let frame = self.pending_frames.pop_front().unwrap();
+            for scope in frame.scopes {
+                scope.recycle(&mut self.free_timers);
+            }
+        }
+    }
+
+    pub fn latest_frame(&self) -> Option<&ProfileFrame> {
+        self.latest_frame.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn chrome_trace() {
+        let frame = ProfileFrame {
+            index: 3,
+            cpu_time: Duration::from_micros(1000),
+            scopes: vec![ProfileScope {
+                name: "render \"scene\"".to_owned(),
+                start: Duration::from_micros(10),
+                cpu_time: Duration::from_micros(500),
+                gpu_time: Some(Duration::from_micros(300)),
+                children: vec![ProfileScope {
+                    name: "shadow map".to_owned(),
+                    start: Duration::from_micros(20),
+                    cpu_time: Duration::from_micros(100),
+                    gpu_time: Some(Duration::from_micros(50)),
+                    children: Vec::new(),
+                }],
+            }],
+        };
+        assert_eq!(frame.gpu_time(), Some(Duration::from_micros(300)));
+        assert_eq!(
+            frame.to_chrome_trace(),
+            "{\"traceEvents\":[\
+            {\"name\":\"Frame 3\",\"ph\":\"X\",\"ts\":0,\"dur\":1000,\"pid\":0,\"tid\":0},\
+            {\"name\":\"render \\\"scene\\\"\",\"ph\":\"X\",\"ts\":10,\"dur\":500,\"pid\":0,\"tid\":0},\
+            {\"name\":\"render \\\"scene\\\"\",\"ph\":\"X\",\"ts\":10,\"dur\":300,\"pid\":0,\"tid\":1},\
+            {\"name\":\"shadow map\",\"ph\":\"X\",\"ts\":20,\"dur\":100,\"pid\":0,\"tid\":0},\
+            {\"name\":\"shadow map\",\"ph\":\"X\",\"ts\":20,\"dur\":50,\"pid\":0,\"tid\":1}\
+            ]}"
+        );
+    }
+
+    #[test]
+    fn json_escape_control_characters() {
+        assert_eq!(
+            json_escape("a\"b\\c\nd\te\u{1}"),
+            "a\\\"b\\\\c\\nd\\te\\u0001"
+        );
+    }
+}
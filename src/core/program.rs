@@ -1,7 +1,40 @@
 use crate::core::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::RwLock;
 
+///
+/// Defines how the vertices in a draw call are assembled into primitives.
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum PrimitiveTopology {
+    /// Each vertex is drawn as a point. The size of the points is specified by writing to `gl_PointSize` in the vertex shader.
+    Points,
+    /// Each pair of vertices is drawn as a line segment.
+    Lines,
+    /// A connected line where each vertex after the first is connected to the previous vertex.
+    LineStrip,
+    /// Each three vertices are drawn as a triangle.
+    #[default]
+    Triangles,
+    /// A connected strip of triangles where each vertex after the first two defines a triangle together with the two previous vertices.
+    TriangleStrip,
+    /// A fan of triangles where each vertex after the first two defines a triangle together with the first and the previous vertex.
+    TriangleFan,
+}
+
+impl PrimitiveTopology {
+    fn to_const(self) -> u32 {
+        match self {
+            Self::Points => crate::context::POINTS,
+            Self::Lines => crate::context::LINES,
+            Self::LineStrip => crate::context::LINE_STRIP,
+            Self::Triangles => crate::context::TRIANGLES,
+            Self::TriangleStrip => crate::context::TRIANGLE_STRIP,
+            Self::TriangleFan => crate::context::TRIANGLE_FAN,
+        }
+    }
+}
+
 ///
 /// A shader program consisting of a programmable vertex shader followed by a programmable fragment shader.
 /// Functionality includes transferring per vertex data to the vertex shader (see the use_attribute functionality)
@@ -14,26 +47,34 @@ pub struct Program {
     attributes: HashMap<String, u32>,
     textures: RwLock<HashMap<String, u32>>,
     uniforms: HashMap<String, crate::context::UniformLocation>,
+    interned_uniforms: Vec<Option<crate::context::UniformLocation>>,
     uniform_blocks: RwLock<HashMap<String, (u32, u32)>>,
+    included_chunks: HashSet<String>,
 }
 
 impl Program {
     ///
     /// Creates a new shader program from the given vertex and fragment glsl shader source.
+    /// The sources can include registered shader chunks using `#include "name"` directives (see [Context::set_shader_chunk]).
+    /// The line numbers in a [CoreError::ShaderCompilation] error refer to the line in the chunk or source where the error occurred.
     ///
     pub fn from_source(
         context: &Context,
         vertex_shader_source: &str,
         fragment_shader_source: &str,
     ) -> Result<Self, CoreError> {
-        unsafe {
-            let vert_shader = context
-                .create_shader(crate::context::VERTEX_SHADER)
-                .expect("Failed creating vertex shader");
-            let frag_shader = context
-                .create_shader(crate::context::FRAGMENT_SHADER)
-                .expect("Failed creating fragment shader");
+        Self::compile(context, vertex_shader_source, fragment_shader_source)?.finish()
+    }
 
+    ///
+    /// Starts compiling and linking the program without waiting for the result, see [CompilingProgram].
+    ///
+    pub(super) fn compile(
+        context: &Context,
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+    ) -> Result<CompilingProgram, CoreError> {
+        unsafe {
             let header: &str = if context.version().is_embedded {
                 "#version 300 es
                     #ifdef GL_FRAGMENT_PRECISION_HIGH
@@ -50,11 +91,31 @@ impl Program {
             } else {
                 "#version 330 core\n"
             };
-            let vertex_shader_source = format!("{}{}", header, vertex_shader_source);
-            let fragment_shader_source = format!("{}{}", header, fragment_shader_source);
+            let (vertex_shader_source, fragment_shader_source) = {
+                let chunks = context.shader_chunks();
+                (
+                    shader_include::ExpandedShaderSource::new(
+                        header,
+                        vertex_shader_source,
+                        &chunks,
+                    )?,
+                    shader_include::ExpandedShaderSource::new(
+                        header,
+                        fragment_shader_source,
+                        &chunks,
+                    )?,
+                )
+            };
 
-            context.shader_source(vert_shader, &vertex_shader_source);
-            context.shader_source(frag_shader, &fragment_shader_source);
+            let vert_shader = context
+                .create_shader(crate::context::VERTEX_SHADER)
+                .expect("Failed creating vertex shader");
+            let frag_shader = context
+                .create_shader(crate::context::FRAGMENT_SHADER)
+                .expect("Failed creating fragment shader");
+
+            context.shader_source(vert_shader, &vertex_shader_source.source);
+            context.shader_source(frag_shader, &fragment_shader_source.source);
             context.compile_shader(vert_shader);
             context.compile_shader(frag_shader);
 
@@ -63,78 +124,14 @@ impl Program {
             context.attach_shader(id, frag_shader);
             context.link_program(id);
 
-            if !context.get_program_link_status(id) {
-                let log = context.get_shader_info_log(vert_shader);
-                if !log.is_empty() {
-                    Err(CoreError::ShaderCompilation(
-                        "vertex".to_string(),
-                        log,
-                        vertex_shader_source,
-                    ))?;
-                }
-                let log = context.get_shader_info_log(frag_shader);
-                if !log.is_empty() {
-                    Err(CoreError::ShaderCompilation(
-                        "fragment".to_string(),
-                        log,
-                        fragment_shader_source,
-                    ))?;
-                }
-                let log = context.get_program_info_log(id);
-                if !log.is_empty() {
-                    Err(CoreError::ShaderLink(log))?;
-                }
-                unreachable!();
-            }
-
-            context.detach_shader(id, vert_shader);
-            context.detach_shader(id, frag_shader);
-            context.delete_shader(vert_shader);
-            context.delete_shader(frag_shader);
-
-            // Init vertex attributes
-            let num_attribs = context.get_active_attributes(id);
-            let mut attributes = HashMap::new();
-            for i in 0..num_attribs {
-                if let Some(crate::context::ActiveAttribute { name, .. }) =
-                    context.get_active_attribute(id, i)
-                {
-                    let location = context.get_attrib_location(id, &name).unwrap_or_else(|| {
-                        panic!("Could not get the location of uniform {}", name)
-                    });
-                    /*println!(
-                        "Attribute location: {}, name: {}, type: {}, size: {}",
-                        location, name, atype, size
-                    );*/
-                    attributes.insert(name, location);
-                }
-            }
-
-            // Init uniforms
-            let num_uniforms = context.get_active_uniforms(id);
-            let mut uniforms = HashMap::new();
-            for i in 0..num_uniforms {
-                if let Some(crate::context::ActiveUniform { name, .. }) =
-                    context.get_active_uniform(id, i)
-                {
-                    if let Some(location) = context.get_uniform_location(id, &name) {
-                        let name = name.split('[').collect::<Vec<_>>()[0].to_string();
-                        /*println!(
-                            "Uniform location: {:?}, name: {}, type: {}, size: {}",
-                            location, name, utype, size
-                        );*/
-                        uniforms.insert(name, location);
-                    }
-                }
-            }
-
-            Ok(Program {
+            Ok(CompilingProgram {
                 context: context.clone(),
                 id,
-                attributes,
-                uniforms,
-                uniform_blocks: RwLock::new(HashMap::new()),
-                textures: RwLock::new(HashMap::new()),
+                vert_shader,
+                frag_shader,
+                vertex_shader_source,
+                fragment_shader_source,
+                finished: false,
             })
         }
     }
@@ -151,7 +148,6 @@ impl Program {
     pub fn use_uniform<T: UniformDataType>(&self, name: &str, data: T) {
         let location = self.get_uniform_location(name);
         T::send_uniform(&self.context, location, &[data]);
-        self.unuse_program();
     }
 
     ///
@@ -175,7 +171,49 @@ impl Program {
     pub fn use_uniform_array<T: UniformDataType>(&self, name: &str, data: &[T]) {
         let location = self.get_uniform_location(name);
         T::send_uniform(&self.context, location, data);
-        self.unuse_program();
+    }
+
+    ///
+    /// Same as [Program::use_uniform] except that the uniform is specified by an interned name,
+    /// which avoids hashing and comparing the name when finding the location of the uniform.
+    ///
+    /// # Panic
+    /// Will panic if the uniform is not defined or not used in the shader code.
+    /// In the latter case the variable is removed by the shader compiler.
+    ///
+    pub fn use_interned_uniform<T: UniformDataType>(&self, name: UniformName, data: T) {
+        let location = self.get_interned_uniform_location(name);
+        T::send_uniform(&self.context, location, &[data]);
+    }
+
+    ///
+    /// Calls [Self::use_interned_uniform] if [Self::requires_interned_uniform] returns true.
+    ///
+    pub fn use_interned_uniform_if_required<T: UniformDataType>(&self, name: UniformName, data: T) {
+        if self.requires_interned_uniform(name) {
+            self.use_interned_uniform(name, data);
+        }
+    }
+
+    ///
+    /// Same as [Program::use_uniform_array] except that the uniform is specified by an interned name,
+    /// which avoids hashing and comparing the name when finding the location of the uniform.
+    ///
+    /// # Panic
+    /// Will panic if the uniform is not defined in the shader code or not used.
+    /// In the latter case the variable is removed by the shader compiler.
+    ///
+    pub fn use_interned_uniform_array<T: UniformDataType>(&self, name: UniformName, data: &[T]) {
+        let location = self.get_interned_uniform_location(name);
+        T::send_uniform(&self.context, location, data);
+    }
+
+    fn get_interned_uniform_location(&self, name: UniformName) -> &crate::context::UniformLocation {
+        self.use_program();
+        match self.interned_uniforms.get(name.index()) {
+            Some(Some(location)) => location,
+            _ => self.get_uniform_location(&name.name()),
+        }
     }
 
     fn get_uniform_location(&self, name: &str) -> &crate::context::UniformLocation {
@@ -298,26 +336,97 @@ impl Program {
     /// Use the given [UniformBuffer] in this shader program and associate it with the given named variable.
     ///
     pub fn use_uniform_block(&self, name: &str, buffer: &UniformBuffer) {
-        if !self.uniform_blocks.read().unwrap().contains_key(name) {
-            let mut map = self.uniform_blocks.write().unwrap();
-            let location = unsafe {
-                self.context
-                    .get_uniform_block_index(self.id, name)
-                    .unwrap_or_else(|| panic!("the uniform block {} is sent to the shader but not defined or never used",
-                        name))
-            };
-            let index = map.len() as u32;
-            map.insert(name.to_owned(), (location, index));
-        };
-        let (location, index) = *self.uniform_blocks.read().unwrap().get(name).unwrap();
+        let index = self.uniform_block_binding(name, None);
+        buffer.bind(index);
         unsafe {
-            self.context.uniform_block_binding(self.id, location, index);
-            buffer.bind(index);
             self.context
                 .bind_buffer(crate::context::UNIFORM_BUFFER, None);
         }
     }
 
+    ///
+    /// Use the given [UniformBlockBuffer] in this shader program and associate it with the uniform block with the given name.
+    ///
+    /// # Panic
+    /// Will panic if the uniform block is not defined in the shader code or not used
+    /// or if the size of the uniform block in the shader does not match the size of the [UniformBlock].
+    ///
+    pub fn use_uniform_block_buffer<T: UniformBlock>(
+        &self,
+        name: &str,
+        buffer: &UniformBlockBuffer<T>,
+    ) {
+        let index = self.uniform_block_binding(name, Some(UniformBlockBuffer::<T>::byte_size()));
+        buffer.bind(index);
+        unsafe {
+            self.context
+                .bind_buffer(crate::context::UNIFORM_BUFFER, None);
+        }
+    }
+
+    ///
+    /// Uses the `CameraBlock` uniform block, defined in the `camera` shader chunk, with the view and projection of the given camera.
+    /// The buffer is shared between all programs, so the data is only uploaded again when the camera changes.
+    /// Note that there is no such block for the lights, they are still sent as uniforms to each program.
+    ///
+    pub(crate) fn use_camera_block(&self, camera: &Camera) {
+        let data = super::context::CameraBlock {
+            view_projection: camera.projection() * camera.view(),
+        };
+        let mut camera_block = self.context.camera_block.write().unwrap();
+        if let Some(buffer) = camera_block.as_mut() {
+            if buffer.get().view_projection != data.view_projection {
+                buffer.update(data);
+            }
+        } else {
+            *camera_block = Some(UniformBlockBuffer::new(&self.context, data));
+        }
+        self.use_uniform_block_buffer("CameraBlock", camera_block.as_ref().unwrap());
+    }
+
+    fn uniform_block_binding(&self, name: &str, byte_size: Option<usize>) -> u32 {
+        if let Some((_, index)) = self.uniform_blocks.read().unwrap().get(name) {
+            return *index;
+        }
+        let mut map = self.uniform_blocks.write().unwrap();
+        if let Some((_, index)) = map.get(name) {
+            // Another thread bound the block while waiting for the lock
+            return *index;
+        }
+        let location = unsafe {
+            self.context
+                .get_uniform_block_index(self.id, name)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "the uniform block {} is sent to the shader but not defined or never used",
+                        name
+                    )
+                })
+        };
+        if let Some(byte_size) = byte_size {
+            let shader_byte_size = unsafe {
+                self.context.get_active_uniform_block_parameter_i32(
+                    self.id,
+                    location,
+                    crate::context::UNIFORM_BLOCK_DATA_SIZE,
+                )
+            } as usize;
+            if shader_byte_size.div_ceil(16) * 16 != byte_size {
+                panic!(
+                    "the uniform block {} has a size of {} bytes in the shader but the data has a size of {} bytes",
+                    name, shader_byte_size, byte_size
+                );
+            }
+        }
+        let index = map.len() as u32;
+        // The binding is part of the program state, so it only needs to be set once
+        unsafe {
+            self.context.uniform_block_binding(self.id, location, index);
+        }
+        map.insert(name.to_owned(), (location, index));
+        index
+    }
+
     ///
     /// Uses the given [VertexBuffer] data in this shader program and associates it with the given named variable.
     /// Each value in the buffer is used when rendering one vertex using the [Program::draw_arrays] or [Program::draw_elements] methods.
@@ -331,9 +440,8 @@ impl Program {
         if buffer.count() > 0 {
             buffer.bind();
             let loc = self.location(name);
+            self.context.enable_vertex_attribute(loc);
             unsafe {
-                self.context.bind_vertex_array(Some(self.context.vao));
-                self.context.enable_vertex_attrib_array(loc);
                 self.context.vertex_attrib_pointer_f32(
                     loc,
                     buffer.data_size() as i32,
@@ -345,7 +453,6 @@ impl Program {
                 self.context.vertex_attrib_divisor(loc, 0);
                 self.context.bind_buffer(crate::context::ARRAY_BUFFER, None);
             }
-            self.unuse_program();
         }
     }
 
@@ -362,9 +469,8 @@ impl Program {
         if buffer.count() > 0 {
             buffer.bind();
             let loc = self.location(name);
+            self.context.enable_vertex_attribute(loc);
             unsafe {
-                self.context.bind_vertex_array(Some(self.context.vao));
-                self.context.enable_vertex_attrib_array(loc);
                 self.context.vertex_attrib_pointer_f32(
                     loc,
                     buffer.data_size() as i32,
@@ -376,29 +482,30 @@ impl Program {
                 self.context.vertex_attrib_divisor(loc, 1);
                 self.context.bind_buffer(crate::context::ARRAY_BUFFER, None);
             }
-            self.unuse_program();
         }
     }
 
     ///
-    /// Draws `count` number of triangles with the given render states and viewport using this shader program.
+    /// Draws `count` number of vertices assembled into primitives using the given topology with the given render states and viewport using this shader program.
     /// Requires that all attributes and uniforms have been defined using the use_attribute and use_uniform methods.
-    /// Assumes that the data for the three vertices in a triangle is defined contiguous in each vertex buffer.
+    /// Assumes that the data for the vertices in a primitive, for example the three vertices in a triangle, is defined contiguous in each vertex buffer.
     /// If you want to use an [ElementBuffer], see [Program::draw_elements].
     ///
-    pub fn draw_arrays(&self, render_states: RenderStates, viewport: Viewport, count: u32) {
+    pub fn draw_arrays(
+        &self,
+        render_states: RenderStates,
+        viewport: Viewport,
+        topology: PrimitiveTopology,
+        count: u32,
+    ) {
         self.context.set_viewport(viewport);
         self.context.set_render_states(render_states);
         self.use_program();
+        self.context.disable_unused_vertex_attributes();
         unsafe {
             self.context
-                .draw_arrays(crate::context::TRIANGLES, 0, count as i32);
-            for location in self.attributes.values() {
-                self.context.disable_vertex_attrib_array(*location);
-            }
-            self.context.bind_vertex_array(None);
+                .draw_arrays(topology.to_const(), 0, count as i32);
         }
-        self.unuse_program();
 
         #[cfg(debug_assertions)]
         self.context
@@ -407,34 +514,31 @@ impl Program {
     }
 
     ///
-    /// Same as [Program::draw_arrays] except it renders 'instance_count' instances of the same set of triangles.
+    /// Same as [Program::draw_arrays] except it renders 'instance_count' instances of the same set of primitives.
     /// Use the [Program::use_instance_attribute], method to send unique data for each instance to the shader.
     ///
     pub fn draw_arrays_instanced(
         &self,
         render_states: RenderStates,
         viewport: Viewport,
+        topology: PrimitiveTopology,
         count: u32,
         instance_count: u32,
     ) {
         self.context.set_viewport(viewport);
         self.context.set_render_states(render_states);
         self.use_program();
+        self.context.disable_unused_vertex_attributes();
         unsafe {
             self.context.draw_arrays_instanced(
-                crate::context::TRIANGLES,
+                topology.to_const(),
                 0,
                 count as i32,
                 instance_count as i32,
             );
             self.context
                 .bind_buffer(crate::context::ELEMENT_ARRAY_BUFFER, None);
-            for location in self.attributes.values() {
-                self.context.disable_vertex_attrib_array(*location);
-            }
-            self.context.bind_vertex_array(None);
         }
-        self.unuse_program();
 
         #[cfg(debug_assertions)]
         self.context
@@ -443,19 +547,21 @@ impl Program {
     }
 
     ///
-    /// Draws the triangles defined by the given [ElementBuffer] with the given render states and viewport using this shader program.
+    /// Draws the primitives defined by the given [ElementBuffer] and topology with the given render states and viewport using this shader program.
     /// Requires that all attributes and uniforms have been defined using the use_attribute and use_uniform methods.
-    /// If you do not want to use an [ElementBuffer], see [Program::draw_arrays]. If you only want to draw a subset of the triangles in the given [ElementBuffer], see [Program::draw_subset_of_elements].
+    /// If you do not want to use an [ElementBuffer], see [Program::draw_arrays]. If you only want to draw a subset of the primitives in the given [ElementBuffer], see [Program::draw_subset_of_elements].
     ///
     pub fn draw_elements(
         &self,
         render_states: RenderStates,
         viewport: Viewport,
+        topology: PrimitiveTopology,
         element_buffer: &ElementBuffer,
     ) {
         self.draw_subset_of_elements(
             render_states,
             viewport,
+            topology,
             element_buffer,
             0,
             element_buffer.count() as u32,
@@ -463,7 +569,7 @@ impl Program {
     }
 
     ///
-    /// Draws a subset of the triangles defined by the given [ElementBuffer] with the given render states and viewport using this shader program.
+    /// Draws a subset of the primitives defined by the given [ElementBuffer] and topology with the given render states and viewport using this shader program.
     /// Requires that all attributes and uniforms have been defined using the use_attribute and use_uniform methods.
     /// If you do not want to use an [ElementBuffer], see [Program::draw_arrays].
     ///
@@ -471,6 +577,7 @@ impl Program {
         &self,
         render_states: RenderStates,
         viewport: Viewport,
+        topology: PrimitiveTopology,
         element_buffer: &ElementBuffer,
         first: u32,
         count: u32,
@@ -478,23 +585,18 @@ impl Program {
         self.context.set_viewport(viewport);
         self.context.set_render_states(render_states);
         self.use_program();
+        self.context.disable_unused_vertex_attributes();
         element_buffer.bind();
         unsafe {
             self.context.draw_elements(
-                crate::context::TRIANGLES,
+                topology.to_const(),
                 count as i32,
                 element_buffer.data_type(),
                 first as i32,
             );
             self.context
                 .bind_buffer(crate::context::ELEMENT_ARRAY_BUFFER, None);
-
-            for location in self.attributes.values() {
-                self.context.disable_vertex_attrib_array(*location);
-            }
-            self.context.bind_vertex_array(None);
         }
-        self.unuse_program();
 
         #[cfg(debug_assertions)]
         self.context
@@ -503,19 +605,21 @@ impl Program {
     }
 
     ///
-    /// Same as [Program::draw_elements] except it renders 'instance_count' instances of the same set of triangles.
+    /// Same as [Program::draw_elements] except it renders 'instance_count' instances of the same set of primitives.
     /// Use the [Program::use_instance_attribute] method to send unique data for each instance to the shader.
     ///
     pub fn draw_elements_instanced(
         &self,
         render_states: RenderStates,
         viewport: Viewport,
+        topology: PrimitiveTopology,
         element_buffer: &ElementBuffer,
         instance_count: u32,
     ) {
         self.draw_subset_of_elements_instanced(
             render_states,
             viewport,
+            topology,
             element_buffer,
             0,
             element_buffer.count() as u32,
@@ -524,13 +628,14 @@ impl Program {
     }
 
     ///
-    /// Same as [Program::draw_subset_of_elements] except it renders 'instance_count' instances of the same set of triangles.
+    /// Same as [Program::draw_subset_of_elements] except it renders 'instance_count' instances of the same set of primitives.
     /// Use the [Program::use_instance_attribute] method to send unique data for each instance to the shader.
     ///
     pub fn draw_subset_of_elements_instanced(
         &self,
         render_states: RenderStates,
         viewport: Viewport,
+        topology: PrimitiveTopology,
         element_buffer: &ElementBuffer,
         first: u32,
         count: u32,
@@ -539,10 +644,11 @@ impl Program {
         self.context.set_viewport(viewport);
         self.context.set_render_states(render_states);
         self.use_program();
+        self.context.disable_unused_vertex_attributes();
         element_buffer.bind();
         unsafe {
             self.context.draw_elements_instanced(
-                crate::context::TRIANGLES,
+                topology.to_const(),
                 count as i32,
                 element_buffer.data_type(),
                 first as i32,
@@ -550,12 +656,7 @@ impl Program {
             );
             self.context
                 .bind_buffer(crate::context::ELEMENT_ARRAY_BUFFER, None);
-            for location in self.attributes.values() {
-                self.context.disable_vertex_attrib_array(*location);
-            }
-            self.context.bind_vertex_array(None);
         }
-        self.unuse_program();
 
         #[cfg(debug_assertions)]
         self.context
@@ -570,6 +671,14 @@ impl Program {
         self.uniforms.contains_key(name)
     }
 
+    ///
+    /// Returns true if this program uses the uniform with the given interned name.
+    ///
+    pub fn requires_interned_uniform(&self, name: UniformName) -> bool {
+        matches!(self.interned_uniforms.get(name.index()), Some(Some(_)))
+            || self.requires_uniform(&name.name())
+    }
+
     ///
     /// Returns true if this program uses the attribute with the given name.
     ///
@@ -588,20 +697,167 @@ impl Program {
     }
 
     fn use_program(&self) {
+        self.context.set_program(Some(self.id));
+    }
+
+    ///
+    /// Returns true if the shader chunk with the given name is included in the vertex or fragment shader source.
+    ///
+    pub(super) fn includes_chunk(&self, name: &str) -> bool {
+        self.included_chunks.contains(name)
+    }
+}
+
+///
+/// A program which is being compiled and linked, possibly in parallel with the rendering if the graphics driver supports it
+/// (see [Context::set_parallel_shader_compile]).
+///
+pub(super) struct CompilingProgram {
+    context: Context,
+    id: crate::context::Program,
+    vert_shader: crate::context::Shader,
+    frag_shader: crate::context::Shader,
+    vertex_shader_source: shader_include::ExpandedShaderSource,
+    fragment_shader_source: shader_include::ExpandedShaderSource,
+    finished: bool,
+}
+
+impl CompilingProgram {
+    ///
+    /// Returns whether or not the compilation and linking is finished, ie. whether [CompilingProgram::finish] returns without blocking.
+    ///
+    pub fn is_ready(&self) -> bool {
+        !self.context.is_parallel_shader_compile_supported()
+            || unsafe { self.context.get_program_completion_status(self.id) }
+    }
+
+    ///
+    /// Returns whether or not the program includes the shader chunk with the given name.
+    ///
+    pub fn includes_chunk(&self, name: &str) -> bool {
+        self.vertex_shader_source.included_chunks.contains(name)
+            || self.fragment_shader_source.included_chunks.contains(name)
+    }
+
+    ///
+    /// Waits for the compilation and linking to finish and returns the program or the compilation or link error.
+    ///
+    pub fn finish(mut self) -> Result<Program, CoreError> {
+        let context = self.context.clone();
+        let (id, vert_shader, frag_shader) = (self.id, self.vert_shader, self.frag_shader);
         unsafe {
-            self.context.use_program(Some(self.id));
+            if !context.get_program_link_status(id) {
+                let log = context.get_shader_info_log(vert_shader);
+                if !log.is_empty() {
+                    Err(CoreError::ShaderCompilation(
+                        "vertex".to_string(),
+                        self.vertex_shader_source.remap_log(&log),
+                        std::mem::take(&mut self.vertex_shader_source.source),
+                    ))?;
+                }
+                let log = context.get_shader_info_log(frag_shader);
+                if !log.is_empty() {
+                    Err(CoreError::ShaderCompilation(
+                        "fragment".to_string(),
+                        self.fragment_shader_source.remap_log(&log),
+                        std::mem::take(&mut self.fragment_shader_source.source),
+                    ))?;
+                }
+                let log = context.get_program_info_log(id);
+                if !log.is_empty() {
+                    Err(CoreError::ShaderLink(log))?;
+                }
+                unreachable!();
+            }
+
+            // The shaders are deleted when this is dropped
+            context.detach_shader(id, vert_shader);
+            context.detach_shader(id, frag_shader);
+
+            // Init vertex attributes
+            let num_attribs = context.get_active_attributes(id);
+            let mut attributes = HashMap::new();
+            for i in 0..num_attribs {
+                if let Some(crate::context::ActiveAttribute { name, .. }) =
+                    context.get_active_attribute(id, i)
+                {
+                    let location = context.get_attrib_location(id, &name).unwrap_or_else(|| {
+                        panic!("Could not get the location of uniform {}", name)
+                    });
+                    /*println!(
+                        "Attribute location: {}, name: {}, type: {}, size: {}",
+                        location, name, atype, size
+                    );*/
+                    attributes.insert(name, location);
+                }
+            }
+
+            // Init uniforms
+            let num_uniforms = context.get_active_uniforms(id);
+            let mut uniforms = HashMap::new();
+            for i in 0..num_uniforms {
+                if let Some(crate::context::ActiveUniform { name, .. }) =
+                    context.get_active_uniform(id, i)
+                {
+                    if let Some(location) = context.get_uniform_location(id, &name) {
+                        let name = name.split('[').collect::<Vec<_>>()[0].to_string();
+                        /*println!(
+                            "Uniform location: {:?}, name: {}, type: {}, size: {}",
+                            location, name, utype, size
+                        );*/
+                        uniforms.insert(name, location);
+                    }
+                }
+            }
+
+            let mut interned_uniforms = Vec::new();
+            // Only names that are already interned are looked up here, so compiling
+            // a program never grows the interner, other names fall back to a lookup by name
+            for name in uniforms.keys() {
+                if let Some(index) = UniformName::find(name).map(|name| name.index()) {
+                    if index >= interned_uniforms.len() {
+                        interned_uniforms.resize(index + 1, None);
+                    }
+                    interned_uniforms[index] = uniforms.get(name).cloned();
+                }
+            }
+
+            self.finished = true;
+            Ok(Program {
+                context,
+                id,
+                attributes,
+                uniforms,
+                interned_uniforms,
+                uniform_blocks: RwLock::new(HashMap::new()),
+                textures: RwLock::new(HashMap::new()),
+                included_chunks: self
+                    .vertex_shader_source
+                    .included_chunks
+                    .union(&self.fragment_shader_source.included_chunks)
+                    .cloned()
+                    .collect(),
+            })
         }
     }
+}
 
-    fn unuse_program(&self) {
+impl Drop for CompilingProgram {
+    fn drop(&mut self) {
         unsafe {
-            self.context.use_program(None);
+            self.context.delete_shader(self.vert_shader);
+            self.context.delete_shader(self.frag_shader);
+            // A finished program is owned by the returned Program
+            if !self.finished {
+                self.context.delete_program(self.id);
+            }
         }
     }
 }
 
 impl Drop for Program {
     fn drop(&mut self) {
+        self.context.forget_program(self.id);
         unsafe {
             self.context.delete_program(self.id);
         }
@@ -31,6 +31,13 @@ pub struct RenderStates {
     /// Defines whether the triangles that are backfacing, frontfacing or both should be skipped in a render call.
     ///
     pub cull: Cull,
+
+    ///
+    /// Defines the stencil test in a render call.
+    /// The stencil test determines whether or not a fragment from the current render call should be discarded
+    /// when comparing a reference value with the value in the stencil buffer and how the stencil buffer is updated.
+    ///
+    pub stencil: StencilTest,
 }
 
 ///
@@ -79,6 +86,115 @@ impl Default for DepthTest {
     }
 }
 
+///
+/// Determines whether or not a fragment/pixel from the current render call should be discarded
+/// when comparing the reference value with the value in the stencil buffer, see [StencilState].
+///
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum StencilFunction {
+    Never,
+    Less,
+    Equal,
+    LessOrEqual,
+    Greater,
+    NotEqual,
+    GreaterOrEqual,
+    #[default]
+    Always,
+}
+
+///
+/// Defines how the value in the stencil buffer is updated, see [StencilState].
+///
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum StencilOperation {
+    /// Keeps the current value.
+    #[default]
+    Keep,
+    /// Sets the value to zero.
+    Zero,
+    /// Sets the value to the reference value.
+    Replace,
+    /// Increments the value, clamping at the maximum value.
+    Increment,
+    /// Increments the value, wrapping to zero at the maximum value.
+    IncrementWrap,
+    /// Decrements the value, clamping at zero.
+    Decrement,
+    /// Decrements the value, wrapping to the maximum value at zero.
+    DecrementWrap,
+    /// Inverts the bits of the value.
+    Invert,
+}
+
+///
+/// The stencil test and stencil operations for either front- or backfacing triangles, see [StencilTest].
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StencilState {
+    /// The function used to compare the reference value with the value in the stencil buffer.
+    pub function: StencilFunction,
+    /// The reference value.
+    pub reference: u8,
+    /// The mask that is applied to both the reference value and the value in the stencil buffer before they are compared.
+    pub read_mask: u8,
+    /// The mask that defines which bits of the value in the stencil buffer that are updated.
+    pub write_mask: u8,
+    /// The operation applied when the stencil test fails.
+    pub stencil_fail: StencilOperation,
+    /// The operation applied when the stencil test passes but the depth test fails.
+    pub depth_fail: StencilOperation,
+    /// The operation applied when both the stencil test and the depth test passes.
+    pub pass: StencilOperation,
+}
+
+impl Default for StencilState {
+    fn default() -> Self {
+        Self {
+            function: StencilFunction::Always,
+            reference: 0,
+            read_mask: 0xFF,
+            write_mask: 0xFF,
+            stencil_fail: StencilOperation::Keep,
+            depth_fail: StencilOperation::Keep,
+            pass: StencilOperation::Keep,
+        }
+    }
+}
+
+///
+/// Defines the stencil test and how the stencil buffer is updated in a render call, which for example can be used to mask out parts of the render target.
+///
+/// **Note:** The stencil test only has an effect if the render target has a stencil buffer, for example a depth texture with the [f24s8](crate::core::texture::f24s8) data type
+/// or the screen when a stencil buffer is requested in the surface settings.
+///
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum StencilTest {
+    /// No stencil test, all fragments pass and the stencil buffer is not updated.
+    #[default]
+    Disabled,
+    /// The stencil test is enabled with separate states for front- and backfacing triangles.
+    Enabled {
+        /// The stencil state for frontfacing triangles.
+        front: StencilState,
+        /// The stencil state for backfacing triangles.
+        back: StencilState,
+    },
+}
+
+impl StencilTest {
+    ///
+    /// Enables the stencil test with the same stencil state for both front- and backfacing triangles.
+    ///
+    pub fn new(state: StencilState) -> Self {
+        Self::Enabled {
+            front: state,
+            back: state,
+        }
+    }
+}
+
 ///
 /// Defines which channels (red, green, blue, alpha and depth) to write to in a render call.
 ///
@@ -26,6 +26,10 @@ mod depth_target_multisample;
 #[doc(inline)]
 pub use depth_target_multisample::*;
 
+mod readback;
+#[doc(inline)]
+pub use readback::*;
+
 use crate::core::*;
 
 use crate::context::Framebuffer;
@@ -205,6 +209,72 @@ impl<'a> RenderTarget<'a> {
         from_byte_slice(&pixels).to_vec()
     }
 
+    ///
+    /// Same as [RenderTarget::read_color] except that the pixels are copied into a pixel buffer on the GPU without stalling the pipeline.
+    /// Use the returned [PixelReadback] to retrieve the pixels when the copy is finished.
+    ///
+    pub fn read_color_async<T: TextureDataType>(&self) -> PixelReadback<T> {
+        self.read_color_partially_async(self.scissor_box())
+    }
+
+    ///
+    /// Same as [RenderTarget::read_color_partially] except that the pixels are copied into a pixel buffer on the GPU without stalling the pipeline.
+    /// Use the returned [PixelReadback] to retrieve the pixels when the copy is finished.
+    ///
+    pub fn read_color_partially_async<T: TextureDataType>(
+        &self,
+        scissor_box: ScissorBox,
+    ) -> PixelReadback<T> {
+        if self.id.is_some() && self.color.is_none() {
+            panic!("cannot read color from a render target without a color target");
+        }
+        self.bind(crate::context::DRAW_FRAMEBUFFER);
+        self.bind(crate::context::READ_FRAMEBUFFER);
+        let mut data_size = std::mem::size_of::<T>();
+        // On web, the format needs to be RGBA if the data type is byte.
+        if data_size / T::size() as usize == 1 {
+            data_size *= 4 / T::size() as usize
+        }
+        PixelReadback::new(
+            &self.context,
+            scissor_box,
+            format_from_data_type::<T>(),
+            T::data_type(),
+            scissor_box.width as usize * scissor_box.height as usize * data_size,
+            true,
+        )
+    }
+
+    ///
+    /// Same as [RenderTarget::read_depth] except that the depth values are copied into a pixel buffer on the GPU without stalling the pipeline.
+    /// Use the returned [PixelReadback] to retrieve the depth values when the copy is finished.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_depth_async(&self) -> PixelReadback<f32> {
+        self.read_depth_partially_async(self.scissor_box())
+    }
+
+    ///
+    /// Same as [RenderTarget::read_depth_partially] except that the depth values are copied into a pixel buffer on the GPU without stalling the pipeline.
+    /// Use the returned [PixelReadback] to retrieve the depth values when the copy is finished.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_depth_partially_async(&self, scissor_box: ScissorBox) -> PixelReadback<f32> {
+        if self.id.is_some() && self.depth.is_none() {
+            panic!("cannot read depth from a render target without a depth target");
+        }
+        self.bind(crate::context::DRAW_FRAMEBUFFER);
+        self.bind(crate::context::READ_FRAMEBUFFER);
+        PixelReadback::new(
+            &self.context,
+            scissor_box,
+            crate::context::DEPTH_COMPONENT,
+            crate::context::FLOAT,
+            scissor_box.width as usize * scissor_box.height as usize * 4,
+            false,
+        )
+    }
+
     ///
     /// Copies the content of the color and depth texture as limited by the [WriteMask]
     /// to the part of this render target specified by the [Viewport].
@@ -250,20 +320,22 @@ impl<'a> RenderTarget<'a> {
                 color_texture.fragment_shader_source(),
                 depth_texture.fragment_shader_source()
             );
-            apply_effect(
-                &self.context,
-                &fragment_shader_source,
-                RenderStates {
-                    depth_test: DepthTest::Always,
-                    write_mask,
-                    ..Default::default()
-                },
-                viewport,
-                |program| {
-                    color_texture.use_uniforms(program);
-                    depth_texture.use_uniforms(program);
-                },
-            )
+            self.context.with_blocking_shader_compile(|| {
+                apply_effect(
+                    &self.context,
+                    &fragment_shader_source,
+                    RenderStates {
+                        depth_test: DepthTest::Always,
+                        write_mask,
+                        ..Default::default()
+                    },
+                    viewport,
+                    |program| {
+                        color_texture.use_uniforms(program);
+                        depth_texture.use_uniforms(program);
+                    },
+                )
+            })
         })
     }
 
@@ -301,19 +373,21 @@ impl<'a> RenderTarget<'a> {
                 }}",
                 color_texture.fragment_shader_source()
             );
-            apply_effect(
-                &self.context,
-                &fragment_shader_source,
-                RenderStates {
-                    depth_test: DepthTest::Always,
-                    write_mask,
-                    ..Default::default()
-                },
-                viewport,
-                |program| {
-                    color_texture.use_uniforms(program);
-                },
-            )
+            self.context.with_blocking_shader_compile(|| {
+                apply_effect(
+                    &self.context,
+                    &fragment_shader_source,
+                    RenderStates {
+                        depth_test: DepthTest::Always,
+                        write_mask,
+                        ..Default::default()
+                    },
+                    viewport,
+                    |program| {
+                        color_texture.use_uniforms(program);
+                    },
+                )
+            })
         })
     }
 
@@ -345,19 +419,21 @@ impl<'a> RenderTarget<'a> {
                     }}",
                 depth_texture.fragment_shader_source(),
             );
-            apply_effect(
-                &self.context,
-                &fragment_shader_source,
-                RenderStates {
-                    depth_test: DepthTest::Always,
-                    write_mask: WriteMask::DEPTH,
-                    ..Default::default()
-                },
-                viewport,
-                |program| {
-                    depth_texture.use_uniforms(program);
-                },
-            )
+            self.context.with_blocking_shader_compile(|| {
+                apply_effect(
+                    &self.context,
+                    &fragment_shader_source,
+                    RenderStates {
+                        depth_test: DepthTest::Always,
+                        write_mask: WriteMask::DEPTH,
+                        ..Default::default()
+                    },
+                    viewport,
+                    |program| {
+                        depth_texture.use_uniforms(program);
+                    },
+                )
+            })
         })
     }
 
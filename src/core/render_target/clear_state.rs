@@ -1,8 +1,8 @@
 use crate::core::*;
 
 ///
-/// Defines which channels (red, green, blue, alpha and depth) to clear when starting to write to a [RenderTarget].
-/// If `None` then the channel is not cleared and if `Some(value)` the channel is cleared to that value (the value must be between 0 and 1).
+/// Defines which channels (red, green, blue, alpha, depth and stencil) to clear when starting to write to a [RenderTarget].
+/// If `None` then the channel is not cleared and if `Some(value)` the channel is cleared to that value (the value must be between 0 and 1, except for the stencil value).
 ///
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct ClearState {
@@ -16,6 +16,8 @@ pub struct ClearState {
     pub alpha: Option<f32>,
     /// Defines the clear value for the depth channel. A value of 1 means a depth value equal to the far plane and 0 means a depth value equal to the near plane.
     pub depth: Option<f32>,
+    /// Defines the clear value for the stencil buffer, if the render target has a stencil buffer.
+    pub stencil: Option<u8>,
 }
 
 impl ClearState {
@@ -29,6 +31,7 @@ impl ClearState {
             blue: None,
             alpha: None,
             depth: None,
+            stencil: None,
         }
     }
 
@@ -42,6 +45,21 @@ impl ClearState {
             blue: None,
             alpha: None,
             depth: Some(depth),
+            stencil: None,
+        }
+    }
+
+    ///
+    /// The stencil buffer will be cleared to the given value.
+    ///
+    pub const fn stencil(stencil: u8) -> Self {
+        Self {
+            red: None,
+            green: None,
+            blue: None,
+            alpha: None,
+            depth: None,
+            stencil: Some(stencil),
         }
     }
 
@@ -55,6 +73,7 @@ impl ClearState {
             blue: Some(blue),
             alpha: Some(alpha),
             depth: None,
+            stencil: None,
         }
     }
 
@@ -68,6 +87,7 @@ impl ClearState {
             blue: Some(blue),
             alpha: Some(alpha),
             depth: Some(depth),
+            stencil: None,
         }
     }
 
@@ -95,13 +115,24 @@ impl ClearState {
             if let Some(depth) = self.depth {
                 context.clear_depth_f32(depth);
             }
-            context.clear(if clear_color && self.depth.is_some() {
-                crate::context::COLOR_BUFFER_BIT | crate::context::DEPTH_BUFFER_BIT
-            } else if clear_color {
-                crate::context::COLOR_BUFFER_BIT
-            } else {
-                crate::context::DEPTH_BUFFER_BIT
-            });
+            if let Some(stencil) = self.stencil {
+                context.forget_stencil_test();
+                context.stencil_mask(0xFF);
+                context.clear_stencil(stencil as i32);
+            }
+            let mut mask = 0;
+            if clear_color {
+                mask |= crate::context::COLOR_BUFFER_BIT;
+            }
+            if self.depth.is_some() {
+                mask |= crate::context::DEPTH_BUFFER_BIT;
+            }
+            if self.stencil.is_some() {
+                mask |= crate::context::STENCIL_BUFFER_BIT;
+            }
+            if mask != 0 {
+                context.clear(mask);
+            }
         }
     }
 }
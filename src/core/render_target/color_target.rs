@@ -5,6 +5,7 @@ use super::*;
 /// Use the `as_color_target` function directly on the texture structs (for example [Texture2D]) to construct a color target.
 /// Combine this together with a [DepthTarget] with [RenderTarget::new] to be able to write to both a depth and color target at the same time.
 /// A color target purely adds functionality, so it can be created each time it is needed, the actual data is saved in the texture.
+/// To write to several textures with different formats at the same time, see [ColorTarget::from_textures].
 ///
 /// **Note:** [DepthTest] is disabled if not also writing to a [DepthTarget].
 ///
@@ -14,9 +15,45 @@ pub struct ColorTarget<'a> {
     mip_level: Option<u32>,
     target: Option<ColorTexture<'a>>,
     multisample_target: Option<&'a Texture2DMultisample>,
+    multiple_targets: Option<&'a [&'a Texture2D]>,
 }
 
 impl<'a> ColorTarget<'a> {
+    ///
+    /// Constructs a color target which writes to all of the given textures at the same time, using multiple render targets.
+    /// The textures can have different formats, for example `[u8; 4]` for albedo and `[f16; 2]` for velocity,
+    /// but must have the same size. The fragment shader output at location `i`, ie. `layout (location = i) out vec4 outColor;`, is written to the texture at index `i`.
+    /// If `None` is specified as the mip level, the 0 level mip level is used and mip maps are generated after a write operation if a mip map filter is specified.
+    /// Otherwise, the given mip level is used and no mip maps are generated.
+    ///
+    /// **Note:** Only the first texture is used when reading from or copying to the color target.
+    ///
+    /// # Panic
+    /// Will panic if no textures are given or if the textures do not have the same size.
+    ///
+    pub fn from_textures(
+        context: &Context,
+        textures: &'a [&'a Texture2D],
+        mip_level: Option<u32>,
+    ) -> Self {
+        if textures.is_empty() {
+            panic!("cannot construct a color target without any textures");
+        }
+        if textures
+            .iter()
+            .any(|t| t.width() != textures[0].width() || t.height() != textures[0].height())
+        {
+            panic!("all of the textures in a color target must have the same size");
+        }
+        ColorTarget {
+            context: context.clone(),
+            mip_level,
+            target: None,
+            multisample_target: None,
+            multiple_targets: Some(textures),
+        }
+    }
+
     pub(in crate::core) fn new_texture2d(
         context: &Context,
         texture: &'a Texture2D,
@@ -27,6 +64,7 @@ impl<'a> ColorTarget<'a> {
             mip_level,
             target: Some(ColorTexture::Single(texture)),
             multisample_target: None,
+            multiple_targets: None,
         }
     }
 
@@ -41,6 +79,7 @@ impl<'a> ColorTarget<'a> {
             mip_level,
             target: Some(ColorTexture::CubeMap { texture, sides }),
             multisample_target: None,
+            multiple_targets: None,
         }
     }
 
@@ -55,6 +94,7 @@ impl<'a> ColorTarget<'a> {
             mip_level,
             target: Some(ColorTexture::Array { texture, layers }),
             multisample_target: None,
+            multiple_targets: None,
         }
     }
 
@@ -67,6 +107,7 @@ impl<'a> ColorTarget<'a> {
             mip_level: None,
             target: None,
             multisample_target: Some(texture),
+            multiple_targets: None,
         }
     }
 
@@ -85,6 +126,7 @@ impl<'a> ColorTarget<'a> {
             scissor_box,
             ClearState {
                 depth: None,
+                stencil: None,
                 ..clear_state
             },
         );
@@ -126,6 +168,26 @@ impl<'a> ColorTarget<'a> {
         self.as_render_target().read_color_partially(scissor_box)
     }
 
+    ///
+    /// Same as [ColorTarget::read] except that the pixels are copied into a pixel buffer on the GPU without stalling the pipeline.
+    /// Use the returned [PixelReadback] to retrieve the pixels when the copy is finished.
+    ///
+    pub fn read_async<T: TextureDataType>(&self) -> PixelReadback<T> {
+        self.read_partially_async(self.scissor_box())
+    }
+
+    ///
+    /// Same as [ColorTarget::read_partially] except that the pixels are copied into a pixel buffer on the GPU without stalling the pipeline.
+    /// Use the returned [PixelReadback] to retrieve the pixels when the copy is finished.
+    ///
+    pub fn read_partially_async<T: TextureDataType>(
+        &self,
+        scissor_box: ScissorBox,
+    ) -> PixelReadback<T> {
+        self.as_render_target()
+            .read_color_partially_async(scissor_box)
+    }
+
     ///
     /// Copies the content of the color texture as limited by the [WriteMask]
     /// to the part of this color target specified by the [Viewport].
@@ -164,7 +226,9 @@ impl<'a> ColorTarget<'a> {
     /// If using the zero mip level of the underlying texture, then this is simply the width of that texture, otherwise it is the width of the given mip level.
     ///
     pub fn width(&self) -> u32 {
-        if let Some(target) = self.target {
+        if let Some(textures) = self.multiple_targets {
+            size_with_mip(textures[0].width(), self.mip_level)
+        } else if let Some(target) = self.target {
             match target {
                 ColorTexture::Single(texture) => size_with_mip(texture.width(), self.mip_level),
                 ColorTexture::Array { texture, .. } => {
@@ -184,7 +248,9 @@ impl<'a> ColorTarget<'a> {
     /// If using the zero mip level of the underlying texture, then this is simply the height of that texture, otherwise it is the height of the given mip level.
     ///
     pub fn height(&self) -> u32 {
-        if let Some(target) = self.target {
+        if let Some(textures) = self.multiple_targets {
+            size_with_mip(textures[0].height(), self.mip_level)
+        } else if let Some(target) = self.target {
             match target {
                 ColorTexture::Single(texture) => size_with_mip(texture.height(), self.mip_level),
                 ColorTexture::Array { texture, .. } => {
@@ -204,6 +270,13 @@ impl<'a> ColorTarget<'a> {
     }
 
     pub(super) fn generate_mip_maps(&self) {
+        if let Some(textures) = self.multiple_targets {
+            if self.mip_level.is_none() {
+                textures
+                    .iter()
+                    .for_each(|texture| texture.generate_mip_maps());
+            }
+        }
         if let Some(target) = self.target {
             match target {
                 ColorTexture::Single(texture) => {
@@ -226,7 +299,18 @@ impl<'a> ColorTarget<'a> {
     }
 
     pub(super) fn bind(&self, context: &Context) {
-        if let Some(target) = self.target {
+        if let Some(textures) = self.multiple_targets {
+            unsafe {
+                context.draw_buffers(
+                    &(0..textures.len())
+                        .map(|i| crate::context::COLOR_ATTACHMENT0 + i as u32)
+                        .collect::<Vec<u32>>(),
+                );
+            }
+            textures.iter().enumerate().for_each(|(channel, texture)| {
+                texture.bind_as_color_target(channel as u32, self.mip_level.unwrap_or(0));
+            });
+        } else if let Some(target) = self.target {
             match target {
                 ColorTexture::Single(texture) => unsafe {
                     context.draw_buffers(&[crate::context::COLOR_ATTACHMENT0]);
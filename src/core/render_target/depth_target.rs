@@ -72,6 +72,7 @@ impl<'a> DepthTarget<'a> {
             scissor_box,
             ClearState {
                 depth: clear_state.depth,
+                stencil: clear_state.stencil,
                 ..ClearState::none()
             },
         );
@@ -109,6 +110,25 @@ impl<'a> DepthTarget<'a> {
         self.as_render_target().read_depth_partially(scissor_box)
     }
 
+    ///
+    /// Same as [DepthTarget::read] except that the depth values are copied into a pixel buffer on the GPU without stalling the pipeline.
+    /// Use the returned [PixelReadback] to retrieve the depth values when the copy is finished.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_async(&self) -> PixelReadback<f32> {
+        self.read_partially_async(self.scissor_box())
+    }
+
+    ///
+    /// Same as [DepthTarget::read_partially] except that the depth values are copied into a pixel buffer on the GPU without stalling the pipeline.
+    /// Use the returned [PixelReadback] to retrieve the depth values when the copy is finished.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_partially_async(&self, scissor_box: ScissorBox) -> PixelReadback<f32> {
+        self.as_render_target()
+            .read_depth_partially_async(scissor_box)
+    }
+
     ///
     /// Copies the content of the depth texture
     /// to the part of this depth target specified by the [Viewport].
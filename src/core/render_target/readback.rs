@@ -0,0 +1,121 @@
+use crate::core::*;
+use std::marker::PhantomData;
+
+///
+/// A handle to pixel data that is being copied from a render target into a pixel buffer on the GPU,
+/// returned by for example [RenderTarget::read_color_async] and [ColorTarget::read_async].
+/// Contrary to the synchronous read methods, this does not stall the pipeline,
+/// instead use [PixelReadback::try_read] a frame or two later to retrieve the data when the copy is finished.
+///
+pub struct PixelReadback<T: TextureDataType> {
+    context: Context,
+    buffer: crate::context::Buffer,
+    fence: crate::context::Fence,
+    width: u32,
+    height: u32,
+    byte_size: usize,
+    flip_y: bool,
+    _data: PhantomData<T>,
+}
+
+impl<T: TextureDataType> PixelReadback<T> {
+    ///
+    /// Starts copying the pixels inside the scissor box of the currently bound read framebuffer into a new pixel buffer.
+    ///
+    pub(in crate::core) fn new(
+        context: &Context,
+        scissor_box: ScissorBox,
+        format: u32,
+        data_type: u32,
+        byte_size: usize,
+        flip_y: bool,
+    ) -> Self {
+        unsafe {
+            let buffer = context.create_buffer().expect("Failed creating buffer");
+            context.bind_buffer(crate::context::PIXEL_PACK_BUFFER, Some(buffer));
+            context.buffer_data_size(
+                crate::context::PIXEL_PACK_BUFFER,
+                byte_size as i32,
+                crate::context::STREAM_READ,
+            );
+            context.read_pixels(
+                scissor_box.x,
+                scissor_box.y,
+                scissor_box.width as i32,
+                scissor_box.height as i32,
+                format,
+                data_type,
+                crate::context::PixelPackData::BufferOffset(0),
+            );
+            context.bind_buffer(crate::context::PIXEL_PACK_BUFFER, None);
+            let fence = context
+                .fence_sync(crate::context::SYNC_GPU_COMMANDS_COMPLETE, 0)
+                .expect("Failed creating fence");
+            // Make sure the commands are sent to the GPU so the fence is eventually signaled
+            context.flush();
+            Self {
+                context: context.clone(),
+                buffer,
+                fence,
+                width: scissor_box.width,
+                height: scissor_box.height,
+                byte_size,
+                flip_y,
+                _data: PhantomData,
+            }
+        }
+    }
+
+    ///
+    /// Returns whether or not the copy is finished, in which case [PixelReadback::try_read] returns the data without blocking.
+    ///
+    /// **Note:** On web, the status is only updated between frames.
+    ///
+    pub fn is_ready(&self) -> bool {
+        let status = unsafe { self.context.client_wait_sync(self.fence, 0, 0) };
+        status == crate::context::ALREADY_SIGNALED || status == crate::context::CONDITION_SATISFIED
+    }
+
+    ///
+    /// Returns the pixel data if the copy is finished, otherwise `None`.
+    /// The pixels are ordered in the same way as the result of the corresponding synchronous read method.
+    ///
+    pub fn try_read(&self) -> Option<Vec<T>> {
+        if !self.is_ready() {
+            return None;
+        }
+        let mut bytes = vec![0u8; self.byte_size];
+        unsafe {
+            self.context
+                .bind_buffer(crate::context::PIXEL_PACK_BUFFER, Some(self.buffer));
+            self.context
+                .get_buffer_sub_data(crate::context::PIXEL_PACK_BUFFER, 0, &mut bytes);
+            self.context
+                .bind_buffer(crate::context::PIXEL_PACK_BUFFER, None);
+        }
+        let mut pixels = from_byte_slice(&bytes).to_vec();
+        if self.flip_y {
+            flip_y(&mut pixels, self.width as usize, self.height as usize);
+        }
+        Some(pixels)
+    }
+
+    /// The width of the area that is read.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the area that is read.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+impl<T: TextureDataType> Drop for PixelReadback<T> {
+    fn drop(&mut self) {
+        unsafe {
+            self.context.delete_buffer(self.buffer);
+            self.context.delete_sync(self.fence);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::core::*;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// The minimum time between two checks for changes to the watched files.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+///
+/// Watches the source files of shader chunks by polling the modification time of the files.
+///
+pub(super) struct ShaderHotReload {
+    files: HashMap<String, (PathBuf, Option<SystemTime>)>,
+    last_poll: Instant,
+    pub errors: Vec<CoreError>,
+    pub failed_programs: HashSet<(String, String)>,
+}
+
+impl ShaderHotReload {
+    pub fn new() -> Self {
+        Self {
+            files: HashMap::new(),
+            last_poll: Instant::now(),
+            errors: Vec::new(),
+            failed_programs: HashSet::new(),
+        }
+    }
+
+    ///
+    /// Starts watching the file at the given path which contains the source of the shader chunk with the given name.
+    /// Returns the current source of the file.
+    ///
+    pub fn watch(&mut self, name: String, path: PathBuf) -> Result<String, CoreError> {
+        let source = read_shader_chunk(&name, &path)?;
+        let modified = modified(&path);
+        self.files.insert(name, (path, modified));
+        Ok(source)
+    }
+
+    ///
+    /// Returns the name and new source of the shader chunks where the source file has changed since the last poll.
+    /// Does nothing if the last poll was too recent.
+    ///
+    pub fn poll(&mut self) -> Vec<(String, String)> {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return Vec::new();
+        }
+        self.last_poll = Instant::now();
+        let mut changes = Vec::new();
+        for (name, (path, last_modified)) in self.files.iter_mut() {
+            let modified = modified(path);
+            if modified != *last_modified {
+                *last_modified = modified;
+                match read_shader_chunk(name, path) {
+                    Ok(source) => changes.push((name.clone(), source)),
+                    Err(e) => self.errors.push(e),
+                }
+            }
+        }
+        changes
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn read_shader_chunk(name: &str, path: &Path) -> Result<String, CoreError> {
+    std::fs::read_to_string(path).map_err(|e| {
+        CoreError::ShaderChunkRead(name.to_owned(), path.display().to_string(), e.to_string())
+    })
+}
@@ -0,0 +1,294 @@
+use crate::core::*;
+use std::collections::{HashMap, HashSet};
+
+macro_rules! builtin_shader_chunk {
+    ($name:literal, $path:literal) => {
+        ($name, $path, include_str!(concat!("../", $path)))
+    };
+}
+
+///
+/// The shader chunks that are registered when a [Context] is created,
+/// given by the name, the path of the source file relative to the `src` directory of this crate and the source.
+///
+pub(super) const BUILTIN_SHADER_CHUNKS: &[(&str, &str, &str)] = &[
+    builtin_shader_chunk!("shared", "core/shared.frag"),
+    builtin_shader_chunk!("camera", "core/camera.frag"),
+    builtin_shader_chunk!("normal_mapping", "core/normal_mapping.frag"),
+    builtin_shader_chunk!("light_shared", "renderer/light/shaders/light_shared.frag"),
+    builtin_shader_chunk!(
+        "color_material",
+        "renderer/material/shaders/color_material.frag"
+    ),
+    builtin_shader_chunk!(
+        "deferred_lighting",
+        "renderer/material/shaders/deferred_lighting.frag"
+    ),
+    builtin_shader_chunk!(
+        "deferred_physical_material",
+        "renderer/material/shaders/deferred_physical_material.frag"
+    ),
+    builtin_shader_chunk!(
+        "depth_material",
+        "renderer/material/shaders/depth_material.frag"
+    ),
+    builtin_shader_chunk!(
+        "isosurface_material",
+        "renderer/material/shaders/isosurface_material.frag"
+    ),
+    builtin_shader_chunk!(
+        "normal_material",
+        "renderer/material/shaders/normal_material.frag"
+    ),
+    builtin_shader_chunk!(
+        "orm_material",
+        "renderer/material/shaders/orm_material.frag"
+    ),
+    builtin_shader_chunk!(
+        "physical_material",
+        "renderer/material/shaders/physical_material.frag"
+    ),
+    builtin_shader_chunk!(
+        "position_material",
+        "renderer/material/shaders/position_material.frag"
+    ),
+    builtin_shader_chunk!(
+        "skybox_material",
+        "renderer/material/shaders/skybox_material.frag"
+    ),
+    builtin_shader_chunk!(
+        "subsurface_material",
+        "renderer/material/shaders/subsurface_material.frag"
+    ),
+    builtin_shader_chunk!(
+        "subsurface_scattering",
+        "renderer/material/shaders/subsurface_scattering.frag"
+    ),
+    builtin_shader_chunk!("uv_material", "renderer/material/shaders/uv_material.frag"),
+    builtin_shader_chunk!(
+        "water_material",
+        "renderer/material/shaders/water_material.frag"
+    ),
+    builtin_shader_chunk!("fog_effect", "renderer/effect/shaders/fog_effect.frag"),
+    builtin_shader_chunk!("fxaa_effect", "renderer/effect/shaders/fxaa_effect.frag"),
+];
+
+///
+/// The shader chunks that are registered when a [Context] is created.
+///
+pub(super) fn builtin_shader_chunks() -> HashMap<String, String> {
+    BUILTIN_SHADER_CHUNKS
+        .iter()
+        .map(|(name, _, source)| (name.to_string(), source.to_string()))
+        .collect()
+}
+
+///
+/// Shader source where all `#include` directives have been replaced by the source of the included chunk.
+/// Each chunk is only included once. The included source is wrapped in an include guard, so that the first `#include` directive
+/// of a chunk that is not removed by the preprocessor, ie. which is not in an inactive `#if` branch, is the one that takes effect.
+///
+pub(super) struct ExpandedShaderSource {
+    pub source: String,
+    pub included_chunks: HashSet<String>,
+    origins: Vec<(String, u32)>,
+}
+
+impl ExpandedShaderSource {
+    pub fn new(
+        header: &str,
+        source: &str,
+        chunks: &HashMap<String, String>,
+    ) -> Result<Self, CoreError> {
+        let mut expanded = Self {
+            source: String::new(),
+            included_chunks: HashSet::new(),
+            origins: Vec::new(),
+        };
+        expanded.push(
+            "header",
+            header,
+            chunks,
+            &mut Vec::new(),
+            &mut HashSet::new(),
+            false,
+        )?;
+        expanded.push(
+            "source",
+            source,
+            chunks,
+            &mut Vec::new(),
+            &mut HashSet::new(),
+            false,
+        )?;
+        Ok(expanded)
+    }
+
+    fn push(
+        &mut self,
+        name: &str,
+        source: &str,
+        chunks: &HashMap<String, String>,
+        stack: &mut Vec<String>,
+        unconditionally_included: &mut HashSet<String>,
+        conditional: bool,
+    ) -> Result<(), CoreError> {
+        let mut depth = 0u32;
+        for (i, line) in source.lines().enumerate() {
+            if let Some(chunk_name) = include_directive(line) {
+                // A chunk which is already included outside of any conditional branch is certainly defined,
+                // and a chunk which includes itself, directly or indirectly, is already being defined
+                if unconditionally_included.contains(chunk_name)
+                    || stack.iter().any(|n| n == chunk_name)
+                {
+                    continue;
+                }
+                let chunk = chunks
+                    .get(chunk_name)
+                    .ok_or_else(|| CoreError::MissingShaderChunk(chunk_name.to_owned()))?;
+                let conditional = conditional || depth > 0;
+                if !conditional {
+                    unconditionally_included.insert(chunk_name.to_owned());
+                }
+                self.included_chunks.insert(chunk_name.to_owned());
+                let guard = include_guard(chunk_name);
+                self.push_line(&format!("#ifndef {}", guard), name, i);
+                self.push_line(&format!("#define {}", guard), name, i);
+                stack.push(chunk_name.to_owned());
+                self.push(
+                    chunk_name,
+                    chunk,
+                    chunks,
+                    stack,
+                    unconditionally_included,
+                    conditional,
+                )?;
+                stack.pop();
+                self.push_line("#endif", name, i);
+            } else {
+                let directive = line.trim_start();
+                if directive.starts_with("#if") {
+                    depth += 1;
+                } else if directive.starts_with("#endif") {
+                    depth = depth.saturating_sub(1);
+                }
+                self.push_line(line, name, i);
+            }
+        }
+        Ok(())
+    }
+
+    fn push_line(&mut self, line: &str, name: &str, index: usize) {
+        self.source.push_str(line);
+        self.source.push('\n');
+        self.origins.push((name.to_owned(), index as u32 + 1));
+    }
+
+    ///
+    /// Replaces the line numbers in the given shader info log, which refers to lines in the expanded source,
+    /// with the name of the chunk and the line number in that chunk.
+    ///
+    pub fn remap_log(&self, log: &str) -> String {
+        log.lines()
+            .map(|line| self.remap_log_line(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn remap_log_line(&self, line: &str) -> String {
+        // Drivers refer to a line as either `0:12` or `0(12)` where 0 is the source string index.
+        for (start, pattern) in line.match_indices("0:").chain(line.match_indices("0(")) {
+            if start > 0 && line.as_bytes()[start - 1].is_ascii_digit() {
+                continue;
+            }
+            let digits_start = start + pattern.len();
+            let digits_length = line[digits_start..]
+                .chars()
+                .take_while(|c| c.is_ascii_digit())
+                .count();
+            let mut end = digits_start + digits_length;
+            if let Some((name, origin_line)) = line[digits_start..end]
+                .parse::<usize>()
+                .ok()
+                .and_then(|l| l.checked_sub(1))
+                .and_then(|i| self.origins.get(i))
+            {
+                if pattern == "0(" && line[end..].starts_with(')') {
+                    end += 1;
+                }
+                return format!("{}{}:{}{}", &line[..start], name, origin_line, &line[end..]);
+            }
+        }
+        line.to_owned()
+    }
+}
+
+fn include_guard(chunk_name: &str) -> String {
+    format!(
+        "CHUNK_{}",
+        chunk_name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>()
+    )
+}
+
+fn include_directive(line: &str) -> Option<&str> {
+    let name = line.trim().strip_prefix("#include")?.trim();
+    name.strip_prefix('"')
+        .and_then(|n| n.strip_suffix('"'))
+        .or_else(|| name.strip_prefix('<').and_then(|n| n.strip_suffix('>')))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_expand_includes() {
+        let mut chunks = HashMap::new();
+        chunks.insert("a".to_owned(), "float a;".to_owned());
+        chunks.insert("b".to_owned(), "#include \"a\"\nfloat b;".to_owned());
+        let expanded = ExpandedShaderSource::new(
+            "#version 330 core\n",
+            "#include \"b\"\n#include <a>\nvoid main() {}",
+            &chunks,
+        )
+        .unwrap();
+        assert_eq!(
+            expanded.source,
+            "#version 330 core\n\
+            #ifndef CHUNK_b\n#define CHUNK_b\n\
+            #ifndef CHUNK_a\n#define CHUNK_a\nfloat a;\n#endif\n\
+            float b;\n#endif\n\
+            void main() {}\n"
+        );
+        assert_eq!(
+            expanded.included_chunks,
+            HashSet::from(["a".to_owned(), "b".to_owned()])
+        );
+        assert_eq!(
+            expanded.remap_log("0:6(5): error: syntax error\nERROR: 0:10: 'main'\n0(8) : error"),
+            "a:1(5): error: syntax error\nERROR: source:3: 'main'\nb:2 : error"
+        );
+        assert!(matches!(
+            ExpandedShaderSource::new("", "#include \"c\"", &chunks),
+            Err(CoreError::MissingShaderChunk(_))
+        ));
+    }
+
+    #[test]
+    fn test_expand_includes_in_conditional_branch() {
+        let mut chunks = HashMap::new();
+        chunks.insert("a".to_owned(), "float a;".to_owned());
+        let expanded = ExpandedShaderSource::new(
+            "",
+            "#ifdef USE_A\n#include \"a\"\n#endif\n#include \"a\"\n#include \"a\"",
+            &chunks,
+        )
+        .unwrap();
+        // The second include is kept since the first might be removed by the preprocessor, the third is removed
+        assert_eq!(expanded.source.matches("float a;").count(), 2);
+        assert_eq!(expanded.source.matches("#ifndef CHUNK_a").count(), 2);
+    }
+}
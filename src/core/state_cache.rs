@@ -0,0 +1,34 @@
+use crate::core::*;
+use std::collections::HashSet;
+
+///
+/// A shadow copy of the graphics state set by this crate, used to skip calls that would not change the state.
+/// `None` means that the state is unknown, for example after [Context::invalidate_state_cache], in which case the state is always set.
+///
+#[derive(Default)]
+pub(super) struct StateCache {
+    pub viewport: Option<(i32, i32, u32, u32)>,
+    pub scissor: Option<ScissorBox>,
+    pub cull: Option<Cull>,
+    pub write_mask: Option<WriteMask>,
+    pub depth_test_enabled: Option<bool>,
+    pub depth_test: Option<DepthTest>,
+    pub blend: Option<Blend>,
+    pub stencil_test: Option<StencilTest>,
+    pub program: Option<Option<crate::context::Program>>,
+    pub vertex_array_bound: bool,
+    pub enabled_attributes: Option<HashSet<u32>>,
+    pub used_attributes: HashSet<u32>,
+}
+
+///
+/// Updates the cached state and returns whether or not it changed, ie. whether or not the state needs to be set.
+///
+pub(super) fn changed<T: PartialEq>(cached: &mut Option<T>, value: T) -> bool {
+    if cached.as_ref() == Some(&value) {
+        false
+    } else {
+        *cached = Some(value);
+        true
+    }
+}
@@ -37,6 +37,10 @@ mod depth_texture2d_multisample;
 #[doc(inline)]
 pub(in crate::core) use depth_texture2d_multisample::*;
 
+mod compressed;
+#[doc(inline)]
+pub use compressed::*;
+
 use data_type::*;
 pub use three_d_asset::texture::{
     Interpolation, Texture2D as CpuTexture, Texture3D as CpuTexture3D, TextureData, Wrapping,
@@ -68,9 +72,21 @@ pub trait DepthTextureDataType: DepthDataType {}
 #[derive(Clone, Copy, Default, Debug)]
 pub struct f24 {}
 
+/// 24 bit float depth combined with an 8 bit stencil value which can be used as [DepthTextureDataType] when a stencil buffer is needed.
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct f24s8 {}
+
+/// 32 bit float depth combined with an 8 bit stencil value which can be used as [DepthTextureDataType] when a stencil buffer is needed.
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct f32s8 {}
+
 impl DepthTextureDataType for f16 {}
 impl DepthTextureDataType for f24 {}
 impl DepthTextureDataType for f32 {}
+impl DepthTextureDataType for f24s8 {}
+impl DepthTextureDataType for f32s8 {}
 
 ///
 /// A reference to some type of texture containing colors.
@@ -288,6 +304,72 @@ fn check_data_length<T: TextureDataType>(
     }
 }
 
+fn mip_level_size(size: u32, mip_level: u32) -> u32 {
+    (size >> mip_level).max(1)
+}
+
+fn check_mip_level(number_of_mip_maps: u32, mip_level: u32) {
+    if mip_level >= number_of_mip_maps {
+        panic!(
+            "cannot access the mip level {}, since there are only {} mip levels in the texture",
+            mip_level, number_of_mip_maps
+        )
+    }
+}
+
+fn check_region(size: (u32, u32, u32), offset: (u32, u32, u32), region_size: (u32, u32, u32)) {
+    if offset.0 + region_size.0 > size.0
+        || offset.1 + region_size.1 > size.1
+        || offset.2 + region_size.2 > size.2
+    {
+        panic!(
+            "the region with offset {:?} and size {:?} is outside the texture of size {:?}",
+            offset, region_size, size
+        )
+    }
+}
+
+fn set_mip_level_range(context: &Context, target: u32, base_level: u32, max_level: u32) {
+    unsafe {
+        context.tex_parameter_i32(
+            target,
+            crate::context::TEXTURE_BASE_LEVEL,
+            base_level as i32,
+        );
+        context.tex_parameter_i32(target, crate::context::TEXTURE_MAX_LEVEL, max_level as i32);
+    }
+}
+
+fn set_lod_bias(context: &Context, target: u32, bias: f32) {
+    // Not available on OpenGL ES and WebGL
+    if !context.version().is_embedded {
+        unsafe {
+            context.tex_parameter_f32(target, crate::context::TEXTURE_LOD_BIAS, bias);
+        }
+    }
+}
+
+fn set_anisotropic_filtering(context: &Context, target: u32, max_anisotropy: f32) {
+    let extensions = context.supported_extensions();
+    if [
+        "GL_EXT_texture_filter_anisotropic",
+        "GL_ARB_texture_filter_anisotropic",
+        "EXT_texture_filter_anisotropic",
+    ]
+    .iter()
+    .any(|name| extensions.contains(*name))
+    {
+        unsafe {
+            let limit = context.get_parameter_f32(crate::context::MAX_TEXTURE_MAX_ANISOTROPY_EXT);
+            context.tex_parameter_f32(
+                target,
+                crate::context::TEXTURE_MAX_ANISOTROPY_EXT,
+                max_anisotropy.clamp(1.0, limit),
+            );
+        }
+    }
+}
+
 fn ru8_data(t: &CpuTexture) -> &[u8] {
     if let TextureData::RU8(data) = &t.data {
         data
@@ -0,0 +1,630 @@
+use crate::core::texture::*;
+
+///
+/// The block compressed formats that can be used for a [CpuCompressedTexture].
+/// All formats store 8 bit unsigned normalized values, color textures should be encoded in sRGB color space as is the case for uncompressed color textures.
+///
+/// If the graphics driver does not support a format (see [CompressedTextureFormat::is_supported]), BC1, BC3 and ETC2 textures are decompressed on the CPU.
+/// BC7 and ASTC have no CPU fallback, so they can only be used when supported, for example ship BC3 or ETC2 versions of the textures as well
+/// and choose the supported format at runtime.
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CompressedTextureFormat {
+    /// BC1 (DXT1) with RGB colors and 1 bit alpha, 8 bytes per 4x4 block.
+    Bc1Rgba,
+    /// BC3 (DXT5) with RGBA colors, 16 bytes per 4x4 block.
+    Bc3Rgba,
+    /// BC7 with RGBA colors, 16 bytes per 4x4 block. Has no CPU fallback.
+    Bc7Rgba,
+    /// ETC2 with RGB colors, 8 bytes per 4x4 block.
+    Etc2Rgb,
+    /// ETC2 with RGB colors and EAC alpha, 16 bytes per 4x4 block.
+    Etc2Rgba,
+    /// ASTC with RGBA colors, 16 bytes per 4x4 block. Has no CPU fallback.
+    Astc4x4Rgba,
+    /// ASTC with RGBA colors, 16 bytes per 6x6 block. Has no CPU fallback.
+    Astc6x6Rgba,
+    /// ASTC with RGBA colors, 16 bytes per 8x8 block. Has no CPU fallback.
+    Astc8x8Rgba,
+}
+
+impl CompressedTextureFormat {
+    ///
+    /// Returns the width and height in pixels of a block.
+    ///
+    pub fn block_size(&self) -> (u32, u32) {
+        match self {
+            Self::Astc6x6Rgba => (6, 6),
+            Self::Astc8x8Rgba => (8, 8),
+            _ => (4, 4),
+        }
+    }
+
+    ///
+    /// Returns the size in bytes of a block.
+    ///
+    pub fn block_byte_size(&self) -> usize {
+        match self {
+            Self::Bc1Rgba | Self::Etc2Rgb => 8,
+            _ => 16,
+        }
+    }
+
+    ///
+    /// Returns the size in bytes of an image with the given width and height in this format.
+    ///
+    pub fn byte_size(&self, width: u32, height: u32) -> usize {
+        let (block_width, block_height) = self.block_size();
+        width.div_ceil(block_width) as usize
+            * height.div_ceil(block_height) as usize
+            * self.block_byte_size()
+    }
+
+    ///
+    /// Returns whether or not the graphics driver behind the given context supports this format.
+    /// If not, textures in this format are decompressed on the CPU when possible, see [Texture2D::new_compressed].
+    ///
+    pub fn is_supported(&self, context: &Context) -> bool {
+        let extensions = context.supported_extensions();
+        let has_any = |names: &[&str]| names.iter().any(|name| extensions.contains(*name));
+        let version = context.version();
+        match self {
+            Self::Bc1Rgba | Self::Bc3Rgba => has_any(&[
+                "GL_EXT_texture_compression_s3tc",
+                "WEBGL_compressed_texture_s3tc",
+            ]),
+            Self::Bc7Rgba => {
+                (!version.is_embedded && (version.major, version.minor) >= (4, 2))
+                    || has_any(&[
+                        "GL_ARB_texture_compression_bptc",
+                        "GL_EXT_texture_compression_bptc",
+                        "EXT_texture_compression_bptc",
+                    ])
+            }
+            Self::Etc2Rgb | Self::Etc2Rgba => {
+                if cfg!(target_arch = "wasm32") {
+                    has_any(&["WEBGL_compressed_texture_etc"])
+                } else {
+                    // Core in OpenGL ES 3.0 and OpenGL 4.3
+                    version.is_embedded
+                        || (version.major, version.minor) >= (4, 3)
+                        || has_any(&["GL_ARB_ES3_compatibility"])
+                }
+            }
+            Self::Astc4x4Rgba | Self::Astc6x6Rgba | Self::Astc8x8Rgba => has_any(&[
+                "GL_KHR_texture_compression_astc_ldr",
+                "WEBGL_compressed_texture_astc",
+            ]),
+        }
+    }
+
+    pub(in crate::core) fn internal_format(&self) -> u32 {
+        match self {
+            Self::Bc1Rgba => crate::context::COMPRESSED_RGBA_S3TC_DXT1_EXT,
+            Self::Bc3Rgba => crate::context::COMPRESSED_RGBA_S3TC_DXT5_EXT,
+            Self::Bc7Rgba => crate::context::COMPRESSED_RGBA_BPTC_UNORM,
+            Self::Etc2Rgb => crate::context::COMPRESSED_RGB8_ETC2,
+            Self::Etc2Rgba => crate::context::COMPRESSED_RGBA8_ETC2_EAC,
+            Self::Astc4x4Rgba => crate::context::COMPRESSED_RGBA_ASTC_4x4_KHR,
+            Self::Astc6x6Rgba => crate::context::COMPRESSED_RGBA_ASTC_6x6_KHR,
+            Self::Astc8x8Rgba => crate::context::COMPRESSED_RGBA_ASTC_8x8_KHR,
+        }
+    }
+
+    fn from_vk_format(vk_format: u32) -> Option<Self> {
+        // Both the UNORM and SRGB variants map to the same format since sRGB decoding is done in the shaders
+        match vk_format {
+            131..=134 => Some(Self::Bc1Rgba),
+            137 | 138 => Some(Self::Bc3Rgba),
+            145 | 146 => Some(Self::Bc7Rgba),
+            147 | 148 => Some(Self::Etc2Rgb),
+            151 | 152 => Some(Self::Etc2Rgba),
+            157 | 158 => Some(Self::Astc4x4Rgba),
+            165 | 166 => Some(Self::Astc6x6Rgba),
+            171 | 172 => Some(Self::Astc8x8Rgba),
+            _ => None,
+        }
+    }
+}
+
+///
+/// A block compressed image including a pre-computed mip chain which can be uploaded to the GPU without decompression,
+/// see [Texture2D::new_compressed] and [TextureCubeMap::new_compressed].
+///
+/// **Note:** Compressed data is uploaded as is, which means that the first row of blocks is the bottom of the image
+/// as opposed to uncompressed data where the first row is the top of the image.
+/// When encoding textures, for example using `toktx`, make sure to use a lower left origin (`--lower_left_maps_to_s0t0`).
+///
+#[derive(Debug, Clone)]
+pub struct CpuCompressedTexture {
+    /// Name of this texture.
+    pub name: String,
+    /// The width of the image at the base mip level.
+    pub width: u32,
+    /// The height of the image at the base mip level.
+    pub height: u32,
+    /// The compressed format.
+    pub format: CompressedTextureFormat,
+    /// The compressed data of each mip level starting with the base level. The width and height are halved for each level.
+    pub mip_levels: Vec<Vec<u8>>,
+    /// The way the pixel data is interpolated when the texture is far away.
+    pub min_filter: Interpolation,
+    /// The way the pixel data is interpolated when the texture is close.
+    pub mag_filter: Interpolation,
+    /// Specifies which mip map to use when the texture is far away. Only used if the texture has more than one mip level.
+    pub mip_map_filter: Option<Interpolation>,
+    /// Determines how the texture is sampled outside the [0..1] s coordinate range (the first value of the uv coordinates).
+    pub wrap_s: Wrapping,
+    /// Determines how the texture is sampled outside the [0..1] t coordinate range (the second value of the uv coordinates).
+    pub wrap_t: Wrapping,
+}
+
+impl CpuCompressedTexture {
+    ///
+    /// Parses a [KTX2](https://registry.khronos.org/KTX/specs/2.0/ktxspec.v2.html) file containing BC1, BC3, BC7, ETC2 or ASTC compressed data.
+    /// Returns one texture for a 2D image and six textures, in the order right, left, top, bottom, front and back, for a cube map.
+    ///
+    /// **Note:** There is no Basis Universal transcoder, so supercompressed files, including Basis Universal (ETC1S and UASTC) encoded files,
+    /// return [CoreError::UnsupportedCompressedTextureFormat]. They have to be transcoded to one of the above formats before loading,
+    /// for example using `ktx transcode`, which on web means shipping a file for each format that should be supported.
+    ///
+    pub fn from_ktx2(bytes: &[u8]) -> Result<Vec<Self>, CoreError> {
+        const IDENTIFIER: [u8; 12] = [
+            0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+        ];
+        let error = |message: &str| CoreError::CompressedTextureParsing(message.to_string());
+        if bytes.len() < 80 || bytes[0..12] != IDENTIFIER {
+            Err(error("not a KTX2 file"))?;
+        }
+        let read_u32 = |offset: usize| {
+            u32::from_le_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ])
+        };
+        let read_u64 =
+            |offset: usize| read_u32(offset) as u64 | (read_u32(offset + 4) as u64) << 32;
+
+        let vk_format = read_u32(12);
+        let width = read_u32(20);
+        let height = read_u32(24);
+        let depth = read_u32(28);
+        let layer_count = read_u32(32);
+        let face_count = read_u32(36);
+        let level_count = read_u32(40).max(1);
+        let supercompression_scheme = read_u32(44);
+
+        if supercompression_scheme != 0 || vk_format == 0 {
+            Err(CoreError::UnsupportedCompressedTextureFormat(
+                "supercompressed or Basis Universal encoded KTX2 (transcode before loading)"
+                    .to_string(),
+            ))?;
+        }
+        let format = CompressedTextureFormat::from_vk_format(vk_format)
+            .ok_or_else(|| error(&format!("unsupported vkFormat {}", vk_format)))?;
+        if depth > 1 || layer_count > 1 {
+            Err(error("3D textures and texture arrays are not supported"))?;
+        }
+        if face_count != 1 && face_count != 6 {
+            Err(error("the face count must be 1 or 6"))?;
+        }
+        if width == 0 || height == 0 {
+            Err(error("the width and height must be positive"))?;
+        }
+        let max_level_count = u32::BITS - width.max(height).leading_zeros();
+        if level_count > max_level_count {
+            Err(error(&format!(
+                "{} mip levels is more than the {} possible for the image size",
+                level_count, max_level_count
+            )))?;
+        }
+        if bytes.len() < 80 + level_count as usize * 24 {
+            Err(error("the level index is truncated"))?;
+        }
+
+        let mut faces = vec![Vec::new(); face_count as usize];
+        for level in 0..level_count {
+            let offset = read_u64(80 + level as usize * 24);
+            let length = read_u64(88 + level as usize * 24);
+            let (block_width, block_height) = format.block_size();
+            let face_size = ((width >> level).max(1).div_ceil(block_width) as u64)
+                .checked_mul((height >> level).max(1).div_ceil(block_height) as u64)
+                .and_then(|blocks| blocks.checked_mul(format.block_byte_size() as u64));
+            let end = offset.checked_add(length);
+            let (face_size, offset) = match (face_size, end) {
+                (Some(face_size), Some(end))
+                    if face_size.checked_mul(face_count as u64) == Some(length)
+                        && end <= bytes.len() as u64 =>
+                {
+                    (face_size as usize, offset as usize)
+                }
+                _ => Err(error(&format!("invalid data for mip level {}", level)))?,
+            };
+            for (face, levels) in faces.iter_mut().enumerate() {
+                let start = offset + face * face_size;
+                levels.push(bytes[start..start + face_size].to_vec());
+            }
+        }
+
+        Ok(faces
+            .into_iter()
+            .map(|mip_levels| Self {
+                name: String::new(),
+                width,
+                height,
+                format,
+                mip_map_filter: if mip_levels.len() > 1 {
+                    Some(Interpolation::Linear)
+                } else {
+                    None
+                },
+                mip_levels,
+                min_filter: Interpolation::Linear,
+                mag_filter: Interpolation::Linear,
+                wrap_s: Wrapping::Repeat,
+                wrap_t: Wrapping::Repeat,
+            })
+            .collect())
+    }
+
+    ///
+    /// Returns an error if the data of any of the mip levels does not match the size and format of this texture.
+    ///
+    pub fn validate(&self) -> Result<(), CoreError> {
+        if self.mip_levels.is_empty() {
+            Err(CoreError::CompressedTextureParsing(
+                "a compressed texture must contain at least one mip level".to_string(),
+            ))?;
+        }
+        for (level, data) in self.mip_levels.iter().enumerate() {
+            let expected = self
+                .format
+                .byte_size((self.width >> level).max(1), (self.height >> level).max(1));
+            if data.len() != expected {
+                Err(CoreError::CompressedTextureParsing(format!(
+                    "invalid size of the data for mip level {} (expected {} bytes but got {} bytes)",
+                    level,
+                    expected,
+                    data.len()
+                )))?;
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Decompresses the base mip level into RGBA pixels where the first row is the bottom of the image.
+    /// Only BC1, BC3 and ETC2 can be decompressed, BC7 and ASTC return [CoreError::UnsupportedCompressedTextureFormat].
+    ///
+    pub fn decompress(&self) -> Result<Vec<[u8; 4]>, CoreError> {
+        let decode_block: fn(&[u8]) -> [[u8; 4]; 16] = match self.format {
+            CompressedTextureFormat::Bc1Rgba => |block| decode_bc1(block, true),
+            CompressedTextureFormat::Bc3Rgba => decode_bc3,
+            CompressedTextureFormat::Etc2Rgb => decode_etc2_rgb,
+            CompressedTextureFormat::Etc2Rgba => decode_etc2_rgba,
+            _ => Err(CoreError::UnsupportedCompressedTextureFormat(format!(
+                "{:?} (no CPU decompression fallback)",
+                self.format
+            )))?,
+        };
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let blocks_x = width.div_ceil(4);
+        let block_byte_size = self.format.block_byte_size();
+        let mut pixels = vec![[0u8; 4]; width * height];
+        for (i, block) in self.mip_levels[0].chunks_exact(block_byte_size).enumerate() {
+            let texels = decode_block(block);
+            let (bx, by) = (i % blocks_x * 4, i / blocks_x * 4);
+            for y in 0..4 {
+                for x in 0..4 {
+                    if bx + x < width && by + y < height {
+                        pixels[(by + y) * width + bx + x] = texels[y * 4 + x];
+                    }
+                }
+            }
+        }
+        Ok(pixels)
+    }
+}
+
+fn rgb565(color: u16) -> [i32; 3] {
+    let r = (color >> 11) as i32 & 0x1F;
+    let g = (color >> 5) as i32 & 0x3F;
+    let b = color as i32 & 0x1F;
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+    ]
+}
+
+fn decode_bc1(block: &[u8], allow_transparency: bool) -> [[u8; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let (a, b) = (rgb565(c0), rgb565(c1));
+    let mix = |wa: i32, wb: i32, d: i32| {
+        let channel = |i: usize| ((a[i] * wa + b[i] * wb) / d) as u8;
+        [channel(0), channel(1), channel(2), 255]
+    };
+    let palette = if c0 > c1 || !allow_transparency {
+        [mix(1, 0, 1), mix(0, 1, 1), mix(2, 1, 3), mix(1, 2, 3)]
+    } else {
+        [mix(1, 0, 1), mix(0, 1, 1), mix(1, 1, 2), [0, 0, 0, 0]]
+    };
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    let mut texels = [[0u8; 4]; 16];
+    for (i, texel) in texels.iter_mut().enumerate() {
+        *texel = palette[(indices >> (2 * i)) as usize & 3];
+    }
+    texels
+}
+
+fn decode_bc3(block: &[u8]) -> [[u8; 4]; 16] {
+    let mut texels = decode_bc1(&block[8..16], false);
+    let (a0, a1) = (block[0] as u32, block[1] as u32);
+    let alphas: [u32; 8] = if a0 > a1 {
+        [
+            a0,
+            a1,
+            (6 * a0 + a1) / 7,
+            (5 * a0 + 2 * a1) / 7,
+            (4 * a0 + 3 * a1) / 7,
+            (3 * a0 + 4 * a1) / 7,
+            (2 * a0 + 5 * a1) / 7,
+            (a0 + 6 * a1) / 7,
+        ]
+    } else {
+        [
+            a0,
+            a1,
+            (4 * a0 + a1) / 5,
+            (3 * a0 + 2 * a1) / 5,
+            (2 * a0 + 3 * a1) / 5,
+            (a0 + 4 * a1) / 5,
+            0,
+            255,
+        ]
+    };
+    let indices = block[2..8]
+        .iter()
+        .rev()
+        .fold(0u64, |bits, byte| bits << 8 | *byte as u64);
+    for (i, texel) in texels.iter_mut().enumerate() {
+        texel[3] = alphas[(indices >> (3 * i)) as usize & 7] as u8;
+    }
+    texels
+}
+
+const ETC_MODIFIERS: [[i32; 4]; 8] = [
+    [2, 8, -2, -8],
+    [5, 17, -5, -17],
+    [9, 29, -9, -29],
+    [13, 42, -13, -42],
+    [18, 60, -18, -60],
+    [24, 80, -24, -80],
+    [33, 106, -33, -106],
+    [47, 183, -47, -183],
+];
+
+const ETC_DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+fn decode_etc2_rgb(block: &[u8]) -> [[u8; 4]; 16] {
+    let bits = u64::from_be_bytes([
+        block[0], block[1], block[2], block[3], block[4], block[5], block[6], block[7],
+    ]);
+    let get = |offset: u32, count: u32| ((bits >> offset) & ((1 << count) - 1)) as i32;
+    let extend4 = |x: i32| x << 4 | x;
+    let extend5 = |x: i32| x << 3 | x >> 2;
+    let extend6 = |x: i32| x << 2 | x >> 4;
+    let extend7 = |x: i32| x << 1 | x >> 6;
+    let clamp = |x: i32| x.clamp(0, 255) as u8;
+    let add = |c: [i32; 3], d: i32| [clamp(c[0] + d), clamp(c[1] + d), clamp(c[2] + d), 255];
+    // The pixels are ordered column by column and the index of each pixel is split into two bit planes
+    let pixel_index = |k: usize| (get(16 + k as u32, 1) << 1 | get(k as u32, 1)) as usize;
+
+    let mut texels = [[0u8; 4]; 16];
+    let r = get(59, 5);
+    let g = get(51, 5);
+    let b = get(43, 5);
+    let (dr, dg, db) = (
+        get(56, 3) << 29 >> 29,
+        get(48, 3) << 29 >> 29,
+        get(40, 3) << 29 >> 29,
+    );
+    let differential = get(33, 1) == 1;
+
+    if differential && !(0..32).contains(&(r + dr)) {
+        // T mode
+        let c0 = [
+            extend4(get(59, 2) << 2 | get(56, 2)),
+            extend4(get(52, 4)),
+            extend4(get(48, 4)),
+        ];
+        let c1 = [
+            extend4(get(44, 4)),
+            extend4(get(40, 4)),
+            extend4(get(36, 4)),
+        ];
+        let d = ETC_DISTANCES[(get(34, 2) << 1 | get(32, 1)) as usize];
+        let paint = [add(c0, 0), add(c1, d), add(c1, 0), add(c1, -d)];
+        for k in 0..16 {
+            texels[(k % 4) * 4 + k / 4] = paint[pixel_index(k)];
+        }
+    } else if differential && !(0..32).contains(&(g + dg)) {
+        // H mode
+        let c0 = [
+            extend4(get(59, 4)),
+            extend4(get(56, 3) << 1 | get(52, 1)),
+            extend4(get(51, 1) << 3 | get(47, 3)),
+        ];
+        let c1 = [
+            extend4(get(43, 4)),
+            extend4(get(39, 4)),
+            extend4(get(35, 4)),
+        ];
+        let value = |c: [i32; 3]| c[0] << 16 | c[1] << 8 | c[2];
+        let ordering = (value(c0) >= value(c1)) as i32;
+        let d = ETC_DISTANCES[(get(34, 1) << 2 | get(32, 1) << 1 | ordering) as usize];
+        let paint = [add(c0, d), add(c0, -d), add(c1, d), add(c1, -d)];
+        for k in 0..16 {
+            texels[(k % 4) * 4 + k / 4] = paint[pixel_index(k)];
+        }
+    } else if differential && !(0..32).contains(&(b + db)) {
+        // Planar mode
+        let o = [
+            extend6(get(57, 6)),
+            extend7(get(56, 1) << 6 | get(49, 6)),
+            extend6(get(48, 1) << 5 | get(43, 2) << 3 | get(39, 3)),
+        ];
+        let h = [
+            extend6(get(34, 5) << 1 | get(32, 1)),
+            extend7(get(25, 7)),
+            extend6(get(19, 6)),
+        ];
+        let v = [extend6(get(13, 6)), extend7(get(6, 7)), extend6(get(0, 6))];
+        for (i, texel) in texels.iter_mut().enumerate() {
+            let (x, y) = ((i % 4) as i32, (i / 4) as i32);
+            let channel =
+                |c: usize| clamp((x * (h[c] - o[c]) + y * (v[c] - o[c]) + 4 * o[c] + 2) >> 2);
+            *texel = [channel(0), channel(1), channel(2), 255];
+        }
+    } else {
+        // Individual or differential mode
+        let (c0, c1) = if differential {
+            (
+                [extend5(r), extend5(g), extend5(b)],
+                [extend5(r + dr), extend5(g + dg), extend5(b + db)],
+            )
+        } else {
+            (
+                [
+                    extend4(get(60, 4)),
+                    extend4(get(52, 4)),
+                    extend4(get(44, 4)),
+                ],
+                [
+                    extend4(get(56, 4)),
+                    extend4(get(48, 4)),
+                    extend4(get(40, 4)),
+                ],
+            )
+        };
+        let tables = [get(37, 3) as usize, get(34, 3) as usize];
+        let flip = get(32, 1) == 1;
+        for k in 0..16 {
+            let (x, y) = (k / 4, k % 4);
+            let second = if flip { y >= 2 } else { x >= 2 };
+            let color = if second { c1 } else { c0 };
+            let modifier = ETC_MODIFIERS[tables[second as usize]][pixel_index(k)];
+            texels[y * 4 + x] = add(color, modifier);
+        }
+    }
+    texels
+}
+
+fn decode_etc2_rgba(block: &[u8]) -> [[u8; 4]; 16] {
+    let mut texels = decode_etc2_rgb(&block[8..16]);
+    let base = block[0] as i32;
+    let multiplier = (block[1] >> 4) as i32;
+    let table = EAC_MODIFIERS[(block[1] & 0xF) as usize];
+    let indices = block[2..8]
+        .iter()
+        .fold(0u64, |bits, byte| bits << 8 | *byte as u64);
+    for k in 0..16 {
+        let index = (indices >> (45 - 3 * k)) as usize & 7;
+        texels[(k % 4) * 4 + k / 4][3] = (base + table[index] * multiplier).clamp(0, 255) as u8;
+    }
+    texels
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decompress_bc1() {
+        // Red and blue end points where every second pixel uses the blue end point
+        let texture = CpuCompressedTexture {
+            name: String::new(),
+            width: 4,
+            height: 4,
+            format: CompressedTextureFormat::Bc1Rgba,
+            mip_levels: vec![vec![0x00, 0xF8, 0x1F, 0x00, 0x44, 0x44, 0x44, 0x44]],
+            min_filter: Interpolation::Linear,
+            mag_filter: Interpolation::Linear,
+            mip_map_filter: None,
+            wrap_s: Wrapping::Repeat,
+            wrap_t: Wrapping::Repeat,
+        };
+        texture.validate().unwrap();
+        let pixels = texture.decompress().unwrap();
+        assert_eq!(pixels[0], [255, 0, 0, 255]);
+        assert_eq!(pixels[1], [0, 0, 255, 255]);
+        assert_eq!(pixels[15], [0, 0, 255, 255]);
+    }
+
+    fn ktx2_header(width: u32, height: u32, level_count: u32) -> Vec<u8> {
+        let mut bytes = vec![
+            0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+        ];
+        for value in [131, 1, width, height, 0, 0, 1, level_count, 0] {
+            bytes.extend(u32::to_le_bytes(value));
+        }
+        bytes.resize(80, 0);
+        bytes
+    }
+
+    #[test]
+    fn test_ktx2_rejects_invalid_data() {
+        assert!(CpuCompressedTexture::from_ktx2(&[0; 100]).is_err());
+    }
+
+    #[test]
+    fn test_ktx2_rejects_malformed_header() {
+        // Too many mip levels
+        let mut bytes = ktx2_header(4, 4, 40);
+        bytes.resize(80 + 40 * 24, 0);
+        assert!(CpuCompressedTexture::from_ktx2(&bytes).is_err());
+
+        // A level whose offset and length overflow
+        let mut bytes = ktx2_header(4, 4, 1);
+        bytes.extend(u64::to_le_bytes(u64::MAX));
+        bytes.extend(u64::to_le_bytes(8));
+        bytes.extend(u64::to_le_bytes(0));
+        assert!(CpuCompressedTexture::from_ktx2(&bytes).is_err());
+
+        // A huge image
+        let mut bytes = ktx2_header(u32::MAX, u32::MAX, 1);
+        bytes.extend([0; 24]);
+        assert!(CpuCompressedTexture::from_ktx2(&bytes).is_err());
+
+        // A valid 4x4 BC1 image
+        let mut bytes = ktx2_header(4, 4, 1);
+        bytes.extend(u64::to_le_bytes(104));
+        bytes.extend(u64::to_le_bytes(8));
+        bytes.extend(u64::to_le_bytes(0));
+        bytes.extend([0; 8]);
+        assert_eq!(CpuCompressedTexture::from_ktx2(&bytes).unwrap().len(), 1);
+    }
+}
@@ -6,6 +6,7 @@ use crate::core::texture::*;
 pub struct DepthTexture2D {
     context: Context,
     id: crate::context::Texture,
+    attachment: u32,
     width: u32,
     height: u32,
 }
@@ -13,6 +14,7 @@ pub struct DepthTexture2D {
 impl DepthTexture2D {
     ///
     /// Constructs a new 2D depth texture.
+    /// Use [f24s8] or [f32s8] as the data type to also have a stencil buffer which can be used with [StencilTest](crate::core::StencilTest).
     ///
     pub fn new<T: DepthTextureDataType>(
         context: &Context,
@@ -25,6 +27,7 @@ impl DepthTexture2D {
         let texture = Self {
             context: context.clone(),
             id,
+            attachment: T::attachment(),
             width,
             height,
         };
@@ -73,7 +76,7 @@ impl DepthTexture2D {
         unsafe {
             self.context.framebuffer_texture_2d(
                 crate::context::FRAMEBUFFER,
-                crate::context::DEPTH_ATTACHMENT,
+                self.attachment,
                 crate::context::TEXTURE_2D,
                 Some(self.id),
                 0,
@@ -6,6 +6,7 @@ use crate::core::texture::*;
 pub struct DepthTextureCubeMap {
     context: Context,
     id: crate::context::Texture,
+    attachment: u32,
     width: u32,
     height: u32,
 }
@@ -26,6 +27,7 @@ impl DepthTextureCubeMap {
         let texture = Self {
             context: context.clone(),
             id,
+            attachment: T::attachment(),
             width,
             height,
         };
@@ -74,7 +76,7 @@ impl DepthTextureCubeMap {
         unsafe {
             self.context.framebuffer_texture_2d(
                 crate::context::DRAW_FRAMEBUFFER,
-                crate::context::DEPTH_ATTACHMENT,
+                self.attachment,
                 side.to_const(),
                 Some(self.id),
                 0,
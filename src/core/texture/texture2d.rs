@@ -33,6 +33,91 @@ impl Texture2D {
         }
     }
 
+    ///
+    /// Constructs a new texture from the given block compressed data including all of the mip levels in the [CpuCompressedTexture].
+    /// If the graphics driver does not support the [CompressedTextureFormat], the base mip level is decompressed on the CPU instead
+    /// and the rest of the mip levels are generated, which is only possible for BC1, BC3 and ETC2 compressed data.
+    /// BC7 and ASTC have no CPU fallback and return [CoreError::UnsupportedCompressedTextureFormat] if the format is not supported.
+    ///
+    /// **Note:** A compressed texture cannot be filled with new data or used as a render target.
+    ///
+    pub fn new_compressed(
+        context: &Context,
+        cpu_texture: &CpuCompressedTexture,
+    ) -> Result<Self, CoreError> {
+        cpu_texture.validate()?;
+        if !cpu_texture.format.is_supported(context) {
+            let mut data = cpu_texture.decompress()?;
+            // The compressed data starts with the bottom row which is flipped again when filling the texture
+            flip_y(
+                &mut data,
+                cpu_texture.width as usize,
+                cpu_texture.height as usize,
+            );
+            let mut texture = Self::new_empty::<[u8; 4]>(
+                context,
+                cpu_texture.width,
+                cpu_texture.height,
+                cpu_texture.min_filter,
+                cpu_texture.mag_filter,
+                cpu_texture.mip_map_filter,
+                cpu_texture.wrap_s,
+                cpu_texture.wrap_t,
+            );
+            texture.fill(&data);
+            return Ok(texture);
+        }
+
+        let number_of_mip_maps = cpu_texture.mip_levels.len() as u32;
+        let texture = Self {
+            context: context.clone(),
+            id: generate(context),
+            width: cpu_texture.width,
+            height: cpu_texture.height,
+            number_of_mip_maps,
+            // The size of a pixel is not defined for compressed data
+            data_byte_size: 0,
+        };
+        texture.bind();
+        set_parameters(
+            context,
+            crate::context::TEXTURE_2D,
+            cpu_texture.min_filter,
+            cpu_texture.mag_filter,
+            if number_of_mip_maps == 1 {
+                None
+            } else {
+                cpu_texture.mip_map_filter
+            },
+            cpu_texture.wrap_s,
+            cpu_texture.wrap_t,
+            None,
+        );
+        let internal_format = cpu_texture.format.internal_format();
+        unsafe {
+            context.tex_storage_2d(
+                crate::context::TEXTURE_2D,
+                number_of_mip_maps as i32,
+                internal_format,
+                cpu_texture.width as i32,
+                cpu_texture.height as i32,
+            );
+            for (level, data) in cpu_texture.mip_levels.iter().enumerate() {
+                context.compressed_tex_sub_image_2d(
+                    crate::context::TEXTURE_2D,
+                    level as i32,
+                    0,
+                    0,
+                    (cpu_texture.width >> level).max(1) as i32,
+                    (cpu_texture.height >> level).max(1) as i32,
+                    internal_format,
+                    crate::context::CompressedPixelUnpackData::Slice(data),
+                );
+            }
+        }
+        Ok(texture)
+    }
+
     fn new_with_data<T: TextureDataType>(
         context: &Context,
         cpu_texture: &CpuTexture,
@@ -133,6 +218,73 @@ impl Texture2D {
         self.generate_mip_maps();
     }
 
+    ///
+    /// Fills the rectangular region starting at the pixel `(x, y)`, where `(0, 0)` is the top left corner,
+    /// with the given size of the base mip level with the given data.
+    /// The mip maps are not updated, so call [Texture2D::generate_mip_maps] after the last region is filled.
+    ///
+    /// # Panic
+    /// Will panic if the region is outside the texture or if the length of the data does not correspond to the size of the region.
+    ///
+    pub fn fill_region<T: TextureDataType>(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        data: &[T],
+    ) {
+        check_region((self.width, self.height, 1), (x, y, 0), (width, height, 1));
+        self.fill_mip_level_region(0, x, y, width, height, data);
+    }
+
+    ///
+    /// Fills the given mip level with the given data, for example to upload a pre-computed mip chain.
+    /// The size of the mip level is half the size of the previous mip level, rounded down and at least one pixel.
+    ///
+    /// # Panic
+    /// Will panic if the mip level does not exist or if the length of the data does not correspond to the size of the mip level.
+    ///
+    pub fn fill_mip_level<T: TextureDataType>(&mut self, mip_level: u32, data: &[T]) {
+        check_mip_level(self.number_of_mip_maps, mip_level);
+        self.fill_mip_level_region(
+            mip_level,
+            0,
+            0,
+            mip_level_size(self.width, mip_level),
+            mip_level_size(self.height, mip_level),
+            data,
+        );
+    }
+
+    fn fill_mip_level_region<T: TextureDataType>(
+        &mut self,
+        mip_level: u32,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        data: &[T],
+    ) {
+        check_data_length::<T>(width, height, 1, self.data_byte_size, data.len());
+        self.bind();
+        let mut data = data.to_owned();
+        flip_y(&mut data, width as usize, height as usize);
+        unsafe {
+            self.context.tex_sub_image_2d(
+                crate::context::TEXTURE_2D,
+                mip_level as i32,
+                x as i32,
+                (mip_level_size(self.height, mip_level) - y - height) as i32,
+                width as i32,
+                height as i32,
+                format_from_data_type::<T>(),
+                T::data_type(),
+                crate::context::PixelUnpackData::Slice(to_byte_slice(&data)),
+            );
+        }
+    }
+
     ///
     /// Returns a [ColorTarget] which can be used to clear, write to and read from the given mip level of this texture.
     /// Combine this together with a [DepthTarget] with [RenderTarget::new] to be able to write to both a depth and color target at the same time.
@@ -155,7 +307,50 @@ impl Texture2D {
         self.height
     }
 
-    pub(crate) fn generate_mip_maps(&self) {
+    ///
+    /// Restricts sampling to the mip levels from `base_level` to `max_level`, both included.
+    /// This can for example be used while streaming in mip levels, starting with the smallest.
+    ///
+    pub fn set_mip_level_range(&mut self, base_level: u32, max_level: u32) {
+        self.bind();
+        set_mip_level_range(
+            &self.context,
+            crate::context::TEXTURE_2D,
+            base_level,
+            max_level,
+        );
+    }
+
+    ///
+    /// Sets a bias which is added to the computed level of detail before choosing which mip level to sample,
+    /// a positive value gives a more blurry result and a negative value a sharper result.
+    ///
+    /// **Note:** Not available on OpenGL ES and WebGL in which case this does nothing.
+    ///
+    pub fn set_lod_bias(&mut self, bias: f32) {
+        self.bind();
+        set_lod_bias(&self.context, crate::context::TEXTURE_2D, bias);
+    }
+
+    ///
+    /// Sets the maximum degree of anisotropic filtering, where 1 means no anisotropic filtering.
+    /// The value is clamped to the maximum supported by the graphics driver and this does nothing if anisotropic filtering is not supported.
+    ///
+    pub fn set_anisotropic_filtering(&mut self, max_anisotropy: f32) {
+        self.bind();
+        set_anisotropic_filtering(&self.context, crate::context::TEXTURE_2D, max_anisotropy);
+    }
+
+    /// The number of mip levels in this texture.
+    pub fn number_of_mip_maps(&self) -> u32 {
+        self.number_of_mip_maps
+    }
+
+    ///
+    /// Generates all mip levels from the base mip level, if the texture has more than one mip level.
+    /// This is done automatically by the `fill` methods, but not when filling a region or a specific mip level.
+    ///
+    pub fn generate_mip_maps(&self) {
         if self.number_of_mip_maps > 1 {
             self.bind();
             unsafe {
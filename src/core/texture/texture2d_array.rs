@@ -245,6 +245,83 @@ impl Texture2DArray {
         self.generate_mip_maps();
     }
 
+    ///
+    /// Fills the rectangular region starting at the pixel `(x, y)`, where `(0, 0)` is the top left corner,
+    /// with the given size of the base mip level of the given layer with the given data.
+    /// The mip maps are not updated, so call [Texture2DArray::generate_mip_maps] after the last region is filled.
+    ///
+    /// # Panic
+    /// Will panic if the layer does not exist, if the region is outside the texture or if the length of the data does not correspond to the size of the region.
+    ///
+    pub fn fill_region<T: TextureDataType>(
+        &mut self,
+        layer: u32,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        data: &[T],
+    ) {
+        check_region(
+            (self.width, self.height, self.depth),
+            (x, y, layer),
+            (width, height, 1),
+        );
+        self.fill_mip_level_region(layer, 0, x, y, width, height, data);
+    }
+
+    ///
+    /// Fills the given mip level of the given layer with the given data, for example to upload a pre-computed mip chain.
+    /// The size of the mip level is half the size of the previous mip level, rounded down and at least one pixel.
+    ///
+    /// # Panic
+    /// Will panic if the layer or mip level does not exist or if the length of the data does not correspond to the size of the mip level.
+    ///
+    pub fn fill_mip_level<T: TextureDataType>(&mut self, layer: u32, mip_level: u32, data: &[T]) {
+        check_mip_level(self.number_of_mip_maps, mip_level);
+        check_region((1, 1, self.depth), (0, 0, layer), (1, 1, 1));
+        self.fill_mip_level_region(
+            layer,
+            mip_level,
+            0,
+            0,
+            mip_level_size(self.width, mip_level),
+            mip_level_size(self.height, mip_level),
+            data,
+        );
+    }
+
+    fn fill_mip_level_region<T: TextureDataType>(
+        &mut self,
+        layer: u32,
+        mip_level: u32,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        data: &[T],
+    ) {
+        check_data_length::<T>(width, height, 1, self.data_byte_size, data.len());
+        self.bind();
+        let mut data = data.to_owned();
+        flip_y(&mut data, width as usize, height as usize);
+        unsafe {
+            self.context.tex_sub_image_3d(
+                crate::context::TEXTURE_2D_ARRAY,
+                mip_level as i32,
+                x as i32,
+                (mip_level_size(self.height, mip_level) - y - height) as i32,
+                layer as i32,
+                width as i32,
+                height as i32,
+                1,
+                format_from_data_type::<T>(),
+                T::data_type(),
+                crate::context::PixelUnpackData::Slice(to_byte_slice(&data)),
+            );
+        }
+    }
+
     ///
     /// Returns a [ColorTarget] which can be used to clear, write to and read from the given layers and mip level of this texture.
     /// Combine this together with a [DepthTarget] with [RenderTarget::new] to be able to write to both a depth and color target at the same time.
@@ -276,7 +353,54 @@ impl Texture2DArray {
         self.depth
     }
 
-    pub(in crate::core) fn generate_mip_maps(&self) {
+    ///
+    /// Restricts sampling to the mip levels from `base_level` to `max_level`, both included.
+    /// This can for example be used while streaming in mip levels, starting with the smallest.
+    ///
+    pub fn set_mip_level_range(&mut self, base_level: u32, max_level: u32) {
+        self.bind();
+        set_mip_level_range(
+            &self.context,
+            crate::context::TEXTURE_2D_ARRAY,
+            base_level,
+            max_level,
+        );
+    }
+
+    ///
+    /// Sets a bias which is added to the computed level of detail before choosing which mip level to sample,
+    /// a positive value gives a more blurry result and a negative value a sharper result.
+    ///
+    /// **Note:** Not available on OpenGL ES and WebGL in which case this does nothing.
+    ///
+    pub fn set_lod_bias(&mut self, bias: f32) {
+        self.bind();
+        set_lod_bias(&self.context, crate::context::TEXTURE_2D_ARRAY, bias);
+    }
+
+    ///
+    /// Sets the maximum degree of anisotropic filtering, where 1 means no anisotropic filtering.
+    /// The value is clamped to the maximum supported by the graphics driver and this does nothing if anisotropic filtering is not supported.
+    ///
+    pub fn set_anisotropic_filtering(&mut self, max_anisotropy: f32) {
+        self.bind();
+        set_anisotropic_filtering(
+            &self.context,
+            crate::context::TEXTURE_2D_ARRAY,
+            max_anisotropy,
+        );
+    }
+
+    /// The number of mip levels in this texture.
+    pub fn number_of_mip_maps(&self) -> u32 {
+        self.number_of_mip_maps
+    }
+
+    ///
+    /// Generates all mip levels from the base mip level, if the texture has more than one mip level.
+    /// This is done automatically by the `fill` methods, but not when filling a region or a specific mip level.
+    ///
+    pub fn generate_mip_maps(&self) {
         if self.number_of_mip_maps > 1 {
             self.bind();
             unsafe {
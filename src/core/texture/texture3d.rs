@@ -144,6 +144,78 @@ impl Texture3D {
         self.generate_mip_maps();
     }
 
+    ///
+    /// Fills the box shaped region starting at the voxel `(x, y, z)` with the given size of the base mip level with the given data.
+    /// The mip maps are not updated, so call [Texture3D::generate_mip_maps] after the last region is filled.
+    ///
+    /// # Panic
+    /// Will panic if the region is outside the texture or if the length of the data does not correspond to the size of the region.
+    ///
+    pub fn fill_region<T: TextureDataType>(
+        &mut self,
+        x: u32,
+        y: u32,
+        z: u32,
+        width: u32,
+        height: u32,
+        depth: u32,
+        data: &[T],
+    ) {
+        check_region(
+            (self.width, self.height, self.depth),
+            (x, y, z),
+            (width, height, depth),
+        );
+        self.fill_mip_level_region(0, (x, y, z), (width, height, depth), data);
+    }
+
+    ///
+    /// Fills the given mip level with the given data, for example to upload a pre-computed mip chain.
+    /// The size of the mip level is half the size of the previous mip level, rounded down and at least one voxel.
+    ///
+    /// # Panic
+    /// Will panic if the mip level does not exist or if the length of the data does not correspond to the size of the mip level.
+    ///
+    pub fn fill_mip_level<T: TextureDataType>(&mut self, mip_level: u32, data: &[T]) {
+        check_mip_level(self.number_of_mip_maps, mip_level);
+        self.fill_mip_level_region(
+            mip_level,
+            (0, 0, 0),
+            (
+                mip_level_size(self.width, mip_level),
+                mip_level_size(self.height, mip_level),
+                mip_level_size(self.depth, mip_level),
+            ),
+            data,
+        );
+    }
+
+    fn fill_mip_level_region<T: TextureDataType>(
+        &mut self,
+        mip_level: u32,
+        offset: (u32, u32, u32),
+        size: (u32, u32, u32),
+        data: &[T],
+    ) {
+        check_data_length::<T>(size.0, size.1, size.2, self.data_byte_size, data.len());
+        self.bind();
+        unsafe {
+            self.context.tex_sub_image_3d(
+                crate::context::TEXTURE_3D,
+                mip_level as i32,
+                offset.0 as i32,
+                offset.1 as i32,
+                offset.2 as i32,
+                size.0 as i32,
+                size.1 as i32,
+                size.2 as i32,
+                format_from_data_type::<T>(),
+                T::data_type(),
+                crate::context::PixelUnpackData::Slice(to_byte_slice(data)),
+            );
+        }
+    }
+
     /// The width of this texture.
     pub fn width(&self) -> u32 {
         self.width
@@ -159,7 +231,50 @@ impl Texture3D {
         self.depth
     }
 
-    fn generate_mip_maps(&self) {
+    ///
+    /// Restricts sampling to the mip levels from `base_level` to `max_level`, both included.
+    /// This can for example be used while streaming in mip levels, starting with the smallest.
+    ///
+    pub fn set_mip_level_range(&mut self, base_level: u32, max_level: u32) {
+        self.bind();
+        set_mip_level_range(
+            &self.context,
+            crate::context::TEXTURE_3D,
+            base_level,
+            max_level,
+        );
+    }
+
+    ///
+    /// Sets a bias which is added to the computed level of detail before choosing which mip level to sample,
+    /// a positive value gives a more blurry result and a negative value a sharper result.
+    ///
+    /// **Note:** Not available on OpenGL ES and WebGL in which case this does nothing.
+    ///
+    pub fn set_lod_bias(&mut self, bias: f32) {
+        self.bind();
+        set_lod_bias(&self.context, crate::context::TEXTURE_3D, bias);
+    }
+
+    ///
+    /// Sets the maximum degree of anisotropic filtering, where 1 means no anisotropic filtering.
+    /// The value is clamped to the maximum supported by the graphics driver and this does nothing if anisotropic filtering is not supported.
+    ///
+    pub fn set_anisotropic_filtering(&mut self, max_anisotropy: f32) {
+        self.bind();
+        set_anisotropic_filtering(&self.context, crate::context::TEXTURE_3D, max_anisotropy);
+    }
+
+    /// The number of mip levels in this texture.
+    pub fn number_of_mip_maps(&self) -> u32 {
+        self.number_of_mip_maps
+    }
+
+    ///
+    /// Generates all mip levels from the base mip level, if the texture has more than one mip level.
+    /// This is done automatically by the `fill` methods, but not when filling a region or a specific mip level.
+    ///
+    pub fn generate_mip_maps(&self) {
         if self.number_of_mip_maps > 1 {
             self.bind();
             unsafe {
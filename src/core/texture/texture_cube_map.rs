@@ -264,6 +264,109 @@ impl TextureCubeMap {
         }
     }
 
+    ///
+    /// Creates a new cube map texture from the given block compressed data including all of the mip levels in the [CpuCompressedTexture]s.
+    /// All of the cpu textures must have the same size and [CompressedTextureFormat].
+    /// If the graphics driver does not support the format, the base mip level is decompressed on the CPU instead (see [Texture2D::new_compressed]).
+    ///
+    pub fn new_compressed(
+        context: &Context,
+        right: &CpuCompressedTexture,
+        left: &CpuCompressedTexture,
+        top: &CpuCompressedTexture,
+        bottom: &CpuCompressedTexture,
+        front: &CpuCompressedTexture,
+        back: &CpuCompressedTexture,
+    ) -> Result<Self, CoreError> {
+        let sides = [right, left, top, bottom, front, back];
+        for side in sides {
+            side.validate()?;
+            if side.width != front.width
+                || side.height != front.height
+                || side.format != front.format
+                || side.mip_levels.len() != front.mip_levels.len()
+            {
+                Err(CoreError::CompressedTextureParsing(
+                    "all of the images used for cube map sides must have the same size, format and number of mip levels".to_string(),
+                ))?;
+            }
+        }
+        if !front.format.is_supported(context) {
+            return Ok(Self::new_with_data(
+                context,
+                &CpuTexture {
+                    width: front.width,
+                    height: front.height,
+                    min_filter: front.min_filter,
+                    mag_filter: front.mag_filter,
+                    mip_map_filter: front.mip_map_filter,
+                    wrap_s: front.wrap_s,
+                    wrap_t: front.wrap_t,
+                    ..Default::default()
+                },
+                right.wrap_s,
+                &right.decompress()?,
+                &left.decompress()?,
+                &top.decompress()?,
+                &bottom.decompress()?,
+                &front.decompress()?,
+                &back.decompress()?,
+            ));
+        }
+
+        let number_of_mip_maps = front.mip_levels.len() as u32;
+        let texture = Self {
+            context: context.clone(),
+            id: generate(context),
+            width: front.width,
+            height: front.height,
+            number_of_mip_maps,
+            is_hdr: false,
+            // The size of a pixel is not defined for compressed data
+            data_byte_size: 0,
+        };
+        texture.bind();
+        set_parameters(
+            context,
+            crate::context::TEXTURE_CUBE_MAP,
+            front.min_filter,
+            front.mag_filter,
+            if number_of_mip_maps == 1 {
+                None
+            } else {
+                front.mip_map_filter
+            },
+            front.wrap_s,
+            front.wrap_t,
+            Some(right.wrap_s),
+        );
+        let internal_format = front.format.internal_format();
+        unsafe {
+            context.tex_storage_2d(
+                crate::context::TEXTURE_CUBE_MAP,
+                number_of_mip_maps as i32,
+                internal_format,
+                front.width as i32,
+                front.height as i32,
+            );
+            for (side, cpu_texture) in CubeMapSide::iter().zip(sides) {
+                for (level, data) in cpu_texture.mip_levels.iter().enumerate() {
+                    context.compressed_tex_sub_image_2d(
+                        side.to_const(),
+                        level as i32,
+                        0,
+                        0,
+                        (front.width >> level).max(1) as i32,
+                        (front.height >> level).max(1) as i32,
+                        internal_format,
+                        crate::context::CompressedPixelUnpackData::Slice(data),
+                    );
+                }
+            }
+        }
+        Ok(texture)
+    }
+
     fn new_with_data<T: TextureDataType>(
         context: &Context,
         cpu_texture: &CpuTexture,
@@ -436,6 +539,78 @@ impl TextureCubeMap {
         self.generate_mip_maps();
     }
 
+    ///
+    /// Fills the rectangular region starting at the pixel `(x, y)` with the given size of the base mip level of the given side with the given data.
+    /// The mip maps are not updated, so call [TextureCubeMap::generate_mip_maps] after the last region is filled.
+    ///
+    /// # Panic
+    /// Will panic if the region is outside the texture or if the length of the data does not correspond to the size of the region.
+    ///
+    pub fn fill_region<T: TextureDataType>(
+        &mut self,
+        side: CubeMapSide,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        data: &[T],
+    ) {
+        check_region((self.width, self.height, 1), (x, y, 0), (width, height, 1));
+        self.fill_mip_level_region(side, 0, x, y, width, height, data);
+    }
+
+    ///
+    /// Fills the given mip level of the given side with the given data, for example to upload a pre-computed mip chain.
+    /// The size of the mip level is half the size of the previous mip level, rounded down and at least one pixel.
+    ///
+    /// # Panic
+    /// Will panic if the mip level does not exist or if the length of the data does not correspond to the size of the mip level.
+    ///
+    pub fn fill_mip_level<T: TextureDataType>(
+        &mut self,
+        side: CubeMapSide,
+        mip_level: u32,
+        data: &[T],
+    ) {
+        check_mip_level(self.number_of_mip_maps, mip_level);
+        self.fill_mip_level_region(
+            side,
+            mip_level,
+            0,
+            0,
+            mip_level_size(self.width, mip_level),
+            mip_level_size(self.height, mip_level),
+            data,
+        );
+    }
+
+    fn fill_mip_level_region<T: TextureDataType>(
+        &mut self,
+        side: CubeMapSide,
+        mip_level: u32,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        data: &[T],
+    ) {
+        check_data_length::<T>(width, height, 1, self.data_byte_size, data.len());
+        self.bind();
+        unsafe {
+            self.context.tex_sub_image_2d(
+                side.to_const(),
+                mip_level as i32,
+                x as i32,
+                y as i32,
+                width as i32,
+                height as i32,
+                format_from_data_type::<T>(),
+                T::data_type(),
+                crate::context::PixelUnpackData::Slice(to_byte_slice(data)),
+            );
+        }
+    }
+
     ///
     /// Creates a new cube texture generated from the equirectangular texture given as input.
     ///
@@ -476,16 +651,18 @@ impl TextureCubeMap {
                     .as_color_target(&[side], None)
                     .clear(ClearState::default())
                     .write(|| {
-                        apply_cube_effect(
-                            context,
-                            side,
-                            fragment_shader_source,
-                            RenderStates::default(),
-                            viewport,
-                            |program| {
-                                program.use_texture("equirectangularMap", &map);
-                            },
-                        );
+                        context.with_blocking_shader_compile(|| {
+                            apply_cube_effect(
+                                context,
+                                side,
+                                fragment_shader_source,
+                                RenderStates::default(),
+                                viewport,
+                                |program| {
+                                    program.use_texture("equirectangularMap", &map);
+                                },
+                            )
+                        });
                     });
             }
         }
@@ -523,7 +700,54 @@ impl TextureCubeMap {
         self.is_hdr
     }
 
-    pub(in crate::core) fn generate_mip_maps(&self) {
+    ///
+    /// Restricts sampling to the mip levels from `base_level` to `max_level`, both included.
+    /// This can for example be used while streaming in mip levels, starting with the smallest.
+    ///
+    pub fn set_mip_level_range(&mut self, base_level: u32, max_level: u32) {
+        self.bind();
+        set_mip_level_range(
+            &self.context,
+            crate::context::TEXTURE_CUBE_MAP,
+            base_level,
+            max_level,
+        );
+    }
+
+    ///
+    /// Sets a bias which is added to the computed level of detail before choosing which mip level to sample,
+    /// a positive value gives a more blurry result and a negative value a sharper result.
+    ///
+    /// **Note:** Not available on OpenGL ES and WebGL in which case this does nothing.
+    ///
+    pub fn set_lod_bias(&mut self, bias: f32) {
+        self.bind();
+        set_lod_bias(&self.context, crate::context::TEXTURE_CUBE_MAP, bias);
+    }
+
+    ///
+    /// Sets the maximum degree of anisotropic filtering, where 1 means no anisotropic filtering.
+    /// The value is clamped to the maximum supported by the graphics driver and this does nothing if anisotropic filtering is not supported.
+    ///
+    pub fn set_anisotropic_filtering(&mut self, max_anisotropy: f32) {
+        self.bind();
+        set_anisotropic_filtering(
+            &self.context,
+            crate::context::TEXTURE_CUBE_MAP,
+            max_anisotropy,
+        );
+    }
+
+    /// The number of mip levels in this texture.
+    pub fn number_of_mip_maps(&self) -> u32 {
+        self.number_of_mip_maps
+    }
+
+    ///
+    /// Generates all mip levels from the base mip level, if the texture has more than one mip level.
+    /// This is done automatically by the `fill` methods, but not when filling a region or a specific mip level.
+    ///
+    pub fn generate_mip_maps(&self) {
         if self.number_of_mip_maps > 1 {
             self.bind();
             unsafe {
@@ -0,0 +1,99 @@
+use crate::core::*;
+use std::time::Duration;
+
+/// The `GPU_DISJOINT_EXT` parameter of the disjoint timer query extensions.
+const GPU_DISJOINT: u32 = 0x8FBB;
+
+///
+/// Measures the time the GPU spends on executing the commands issued between [GpuTimer::begin] and [GpuTimer::end],
+/// using a `TIME_ELAPSED` query. The result is available some time after the commands have finished, typically a frame or two later,
+/// so use [GpuTimer::try_result] to check for the result without stalling the pipeline.
+///
+/// Only one timer can measure at a time, ie. it is not possible to begin a timer while another timer is measuring.
+/// See [Context::profile_scope] for measuring nested scopes.
+///
+pub struct GpuTimer {
+    context: Context,
+    query: crate::context::Query,
+}
+
+impl GpuTimer {
+    ///
+    /// Returns whether or not timer queries are supported by the graphics driver behind the given context.
+    /// They are always supported on desktop and otherwise require the `GL_EXT_disjoint_timer_query` or `EXT_disjoint_timer_query_webgl2` extension.
+    ///
+    pub fn is_supported(context: &Context) -> bool {
+        if cfg!(not(target_arch = "wasm32")) && !context.version().is_embedded {
+            return true;
+        }
+        let extensions = context.supported_extensions();
+        extensions.contains("GL_EXT_disjoint_timer_query")
+            || extensions.contains("EXT_disjoint_timer_query_webgl2")
+    }
+
+    ///
+    /// Creates a new timer or returns `None` if timer queries are not supported, see [GpuTimer::is_supported].
+    ///
+    pub fn new(context: &Context) -> Option<Self> {
+        if !Self::is_supported(context) {
+            return None;
+        }
+        let query = unsafe { context.create_query().ok()? };
+        Some(Self {
+            context: context.clone(),
+            query,
+        })
+    }
+
+    ///
+    /// Starts measuring the time spent on the GPU executing the commands issued from now on.
+    ///
+    pub fn begin(&self) {
+        unsafe {
+            self.context
+                .begin_query(crate::context::TIME_ELAPSED, self.query);
+        }
+    }
+
+    ///
+    /// Stops measuring. Must be called after [GpuTimer::begin] and before beginning another timer.
+    ///
+    pub fn end(&self) {
+        unsafe {
+            self.context.end_query(crate::context::TIME_ELAPSED);
+        }
+    }
+
+    ///
+    /// Returns the measured time if it is available, otherwise `None`.
+    /// If the measurement is invalid, for example because the GPU changed frequency while measuring, the result is zero.
+    ///
+    pub fn try_result(&self) -> Option<Duration> {
+        unsafe {
+            let available = self
+                .context
+                .get_query_parameter_u32(self.query, crate::context::QUERY_RESULT_AVAILABLE);
+            if available == 0 {
+                return None;
+            }
+            let nanoseconds = self
+                .context
+                .get_query_parameter_u32(self.query, crate::context::QUERY_RESULT);
+            let disjoint = (cfg!(target_arch = "wasm32") || self.context.version().is_embedded)
+                && self.context.get_parameter_i32(GPU_DISJOINT) != 0;
+            Some(if disjoint {
+                Duration::ZERO
+            } else {
+                Duration::from_nanos(nanoseconds as u64)
+            })
+        }
+    }
+}
+
+impl Drop for GpuTimer {
+    fn drop(&mut self) {
+        unsafe {
+            self.context.delete_query(self.query);
+        }
+    }
+}
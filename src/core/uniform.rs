@@ -27,3 +27,69 @@ impl<T: UniformDataType + PrimitiveDataType> UniformDataType for Matrix3<T> {}
 impl<T: UniformDataType + PrimitiveDataType> UniformDataType for Matrix4<T> {}
 
 impl<T: UniformDataType + ?Sized> UniformDataType for &T {}
+
+///
+/// The name of a uniform variable which is interned, ie. the name is stored once and afterwards referred to by a small identifier,
+/// so that looking up the location of the uniform in a [Program] does not require hashing and comparing strings
+/// (see for example [Program::use_interned_uniform]).
+/// Create the names once, for example when constructing a material, and use them every frame.
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct UniformName(u32);
+
+struct Interner {
+    ids: std::collections::HashMap<std::sync::Arc<str>, u32>,
+    names: Vec<std::sync::Arc<str>>,
+}
+
+static INTERNER: std::sync::RwLock<Option<Interner>> = std::sync::RwLock::new(None);
+
+impl UniformName {
+    ///
+    /// Interns the given uniform name. Interning the same name several times returns the same [UniformName].
+    ///
+    pub fn new(name: &str) -> Self {
+        if let Some(id) = Self::find(name) {
+            return id;
+        }
+        let mut interner = INTERNER.write().unwrap();
+        let interner = interner.get_or_insert_with(|| Interner {
+            ids: std::collections::HashMap::new(),
+            names: Vec::new(),
+        });
+        if let Some(id) = interner.ids.get(name) {
+            return Self(*id);
+        }
+        let name: std::sync::Arc<str> = name.into();
+        let id = interner.names.len() as u32;
+        interner.names.push(name.clone());
+        interner.ids.insert(name, id);
+        Self(id)
+    }
+
+    ///
+    /// Returns the name.
+    ///
+    pub fn name(&self) -> std::sync::Arc<str> {
+        INTERNER.read().unwrap().as_ref().unwrap().names[self.0 as usize].clone()
+    }
+
+    pub(super) fn find(name: &str) -> Option<Self> {
+        INTERNER
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|interner| interner.ids.get(name))
+            .map(|id| Self(*id))
+    }
+
+    pub(super) fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl From<&str> for UniformName {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
@@ -0,0 +1,203 @@
+use crate::core::*;
+
+///
+/// A type that can be a member of a uniform block with the `std140` layout.
+/// The alignment and size are the alignment and size of the type in the `std140` layout, which might differ from the layout of the Rust type.
+///
+/// # Safety
+/// The Rust type must only consist of 32 bit numbers, ie. it must not contain any padding, pointers or references.
+///
+pub unsafe trait Std140: Copy {
+    /// The alignment in bytes of this type in the `std140` layout.
+    const ALIGNMENT: usize;
+    /// The size in bytes of this type in the `std140` layout.
+    const SIZE: usize;
+}
+
+macro_rules! impl_std140 {
+    ($type:ty, $alignment:expr, $size:expr) => {
+        unsafe impl Std140 for $type {
+            const ALIGNMENT: usize = $alignment;
+            const SIZE: usize = $size;
+        }
+    };
+}
+
+impl_std140!(f32, 4, 4);
+impl_std140!(i32, 4, 4);
+impl_std140!(u32, 4, 4);
+impl_std140!(Vector2<f32>, 8, 8);
+impl_std140!(Vector2<i32>, 8, 8);
+impl_std140!(Vector2<u32>, 8, 8);
+impl_std140!(Vector3<f32>, 16, 12);
+impl_std140!(Vector3<i32>, 16, 12);
+impl_std140!(Vector3<u32>, 16, 12);
+impl_std140!(Vector4<f32>, 16, 16);
+impl_std140!(Vector4<i32>, 16, 16);
+impl_std140!(Vector4<u32>, 16, 16);
+impl_std140!(Matrix4<f32>, 16, 64);
+
+// The elements of an array are aligned to 16 bytes in the std140 layout
+unsafe impl<T: Std140, const N: usize> Std140 for [T; N] {
+    const ALIGNMENT: usize = 16;
+    const SIZE: usize = N * round_up(T::SIZE, 16);
+}
+
+///
+/// Describes a member of a [UniformBlock].
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniformBlockMember {
+    /// The name of the member.
+    pub name: &'static str,
+    /// The offset in bytes of the member in the Rust type.
+    pub offset: usize,
+    /// The size in bytes of the member in the Rust type.
+    pub size: usize,
+    /// The alignment in bytes of the member in the `std140` layout.
+    pub std140_alignment: usize,
+    /// The size in bytes of the member in the `std140` layout.
+    pub std140_size: usize,
+}
+
+///
+/// A Rust struct which is uploaded as is to a uniform block with the `std140` layout using a [UniformBlockBuffer].
+/// Implement this trait using the [uniform_block](crate::uniform_block) macro, which also makes it possible to check that the layout
+/// of the Rust struct matches the `std140` layout, see [UniformBlock::check_layout].
+///
+/// # Safety
+/// The type must be `#[repr(C)]`, only consist of [Std140] members and the members must be described correctly by [UniformBlock::members].
+///
+pub unsafe trait UniformBlock: Copy {
+    ///
+    /// Returns the members of the struct in the order they are declared.
+    ///
+    fn members() -> Vec<UniformBlockMember>;
+
+    ///
+    /// Checks that the layout of the Rust struct matches the `std140` layout of a uniform block with the same members in the same order,
+    /// otherwise returns an error which describes the first member that does not match.
+    /// If the layout does not match, it can usually be fixed by adding padding members, for example a `f32` after a [Vec3] which is followed by another [Vec3].
+    ///
+    fn check_layout() -> Result<(), CoreError> {
+        let mut std140_offset = 0;
+        for member in Self::members() {
+            std140_offset = round_up(std140_offset, member.std140_alignment);
+            if member.offset != std140_offset || member.size != member.std140_size {
+                return Err(CoreError::UniformBlockLayout(
+                    std::any::type_name::<Self>().to_owned(),
+                    format!(
+                        "the member {} has offset {} and size {} but offset {} and size {} is expected",
+                        member.name, member.offset, member.size, std140_offset, member.std140_size
+                    ),
+                ));
+            }
+            std140_offset += member.std140_size;
+        }
+        Ok(())
+    }
+}
+
+const fn round_up(value: usize, alignment: usize) -> usize {
+    value.div_ceil(alignment) * alignment
+}
+
+///
+/// Defines a `#[repr(C)]` struct which implements [UniformBlock](crate::core::UniformBlock),
+/// so that it can be uploaded to a uniform block with the `std140` layout using a [UniformBlockBuffer](crate::core::UniformBlockBuffer).
+/// The members must implement [Std140](crate::core::Std140) and the layout is checked when creating the buffer.
+///
+/// ```
+/// use three_d::*;
+///
+/// uniform_block! {
+///     /// The camera data which is the same for all programs in a frame.
+///     pub struct CameraBlock {
+///         pub view_projection: Mat4,
+///         pub position: Vec3,
+///         pub _padding: f32,
+///     }
+/// }
+/// assert!(CameraBlock::check_layout().is_ok());
+/// ```
+/// matches the following block in the shader:
+/// ```glsl
+/// layout (std140) uniform CameraBlock
+/// {
+///     mat4 viewProjection;
+///     vec3 position;
+/// };
+/// ```
+///
+#[macro_export]
+macro_rules! uniform_block {
+    (
+        $(#[$attribute:meta])*
+        $visibility:vis struct $name:ident {
+            $($(#[$member_attribute:meta])* $member_visibility:vis $member:ident : $type:ty),* $(,)?
+        }
+    ) => {
+        $(#[$attribute])*
+        #[derive(Clone, Copy)]
+        #[repr(C)]
+        $visibility struct $name {
+            $($(#[$member_attribute])* $member_visibility $member: $type),*
+        }
+
+        #[allow(unsafe_code)]
+        unsafe impl $crate::core::UniformBlock for $name {
+            fn members() -> Vec<$crate::core::UniformBlockMember> {
+                let block = ::std::mem::MaybeUninit::<Self>::uninit();
+                let base = block.as_ptr();
+                vec![$(
+                    $crate::core::UniformBlockMember {
+                        name: stringify!($member),
+                        // Only computes the address of the member, the uninitialized memory is never read
+                        offset: unsafe { ::std::ptr::addr_of!((*base).$member) } as usize - base as usize,
+                        size: ::std::mem::size_of::<$type>(),
+                        std140_alignment: <$type as $crate::core::Std140>::ALIGNMENT,
+                        std140_size: <$type as $crate::core::Std140>::SIZE,
+                    }
+                ),*]
+            }
+        }
+
+        // A struct is aligned to 16 bytes and its size is rounded up to a multiple of 16 bytes when nested inside a uniform block
+        #[allow(unsafe_code)]
+        unsafe impl $crate::core::Std140 for $name {
+            const ALIGNMENT: usize = 16;
+            const SIZE: usize = ::std::mem::size_of::<Self>().div_ceil(16) * 16;
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    uniform_block! {
+        #[allow(dead_code)]
+        struct Valid {
+            view_projection: Mat4,
+            position: Vec3,
+            intensity: f32,
+            colors: [Vec4; 2],
+            size: Vec2,
+        }
+    }
+
+    uniform_block! {
+        #[allow(dead_code)]
+        struct Invalid {
+            direction: Vec3,
+            position: Vec3,
+        }
+    }
+
+    #[test]
+    fn uniform_block_layout() {
+        assert!(Valid::check_layout().is_ok());
+        assert_eq!(Valid::members()[3].offset, 80);
+        assert!(Invalid::check_layout().is_err());
+    }
+}
@@ -15,6 +15,7 @@ pub struct GUI {
     output: RefCell<Option<egui::FullOutput>>,
     viewport: Viewport,
     modifiers: Modifiers,
+    context: Option<Context>,
 }
 
 impl GUI {
@@ -23,11 +24,16 @@ impl GUI {
     ///
     pub fn new(context: &Context) -> Self {
         use std::ops::Deref;
-        Self::from_gl_context(context.deref().clone())
+        Self {
+            context: Some(context.clone()),
+            ..Self::from_gl_context(context.deref().clone())
+        }
     }
 
     ///
     /// Creates a new GUI from a low-level graphics [Context](crate::context::Context).
+    /// If a mid-level [Context] is also used for rendering, call [Context::invalidate_state_cache] after rendering the GUI
+    /// or construct the GUI using [GUI::new] instead, which does that automatically.
     ///
     pub fn from_gl_context(context: std::sync::Arc<crate::context::Context>) -> Self {
         GUI {
@@ -36,6 +42,7 @@ impl GUI {
             output: RefCell::new(None),
             viewport: Viewport::new_at_origo(1, 1),
             modifiers: Modifiers::default(),
+            context: None,
         }
     }
 
@@ -251,9 +258,54 @@ impl GUI {
             use glow::HasContext as _;
             self.painter.borrow().gl().disable(glow::FRAMEBUFFER_SRGB);
         }
+        // The painter changes the graphics state behind the back of the context
+        if let Some(context) = &self.context {
+            context.invalidate_state_cache();
+        }
+    }
+}
+
+///
+/// Shows the CPU and GPU timings of the given profiler frame (see [Context::profiler_frame]) as a tree in the given [egui::Ui].
+///
+pub fn profiler_ui(ui: &mut egui::Ui, frame: &ProfileFrame) {
+    ui.label(format!(
+        "Frame {}: {} CPU, {} GPU",
+        frame.index,
+        format_time(Some(frame.cpu_time)),
+        format_time(frame.gpu_time())
+    ));
+    for (i, scope) in frame.scopes.iter().enumerate() {
+        profile_scope_ui(ui, scope, i.to_string());
     }
 }
 
+fn profile_scope_ui(ui: &mut egui::Ui, scope: &ProfileScope, id: String) {
+    let text = format!(
+        "{}: {} CPU, {} GPU",
+        scope.name,
+        format_time(Some(scope.cpu_time)),
+        format_time(scope.gpu_time)
+    );
+    if scope.children.is_empty() {
+        ui.label(text);
+    } else {
+        egui::CollapsingHeader::new(text)
+            .id_source(&id)
+            .default_open(true)
+            .show(ui, |ui| {
+                for (i, child) in scope.children.iter().enumerate() {
+                    profile_scope_ui(ui, child, format!("{}/{}", id, i));
+                }
+            });
+    }
+}
+
+fn format_time(time: Option<std::time::Duration>) -> String {
+    time.map(|time| format!("{:.2} ms", time.as_secs_f64() * 1000.0))
+        .unwrap_or_else(|| "-".to_owned())
+}
+
 impl From<&Key> for egui::Key {
     fn from(key: &Key) -> Self {
         use crate::control::Key::*;
@@ -29,6 +29,9 @@ pub enum RendererError {
     InvalidBufferLength(String, usize, usize),
     #[error("the material {0} is required by the geometry {1} but could not be found")]
     MissingMaterial(String, String),
+    #[cfg(feature = "text")]
+    #[error("failed parsing font: {0}")]
+    FontParsing(String),
 }
 
 pub mod material;
@@ -77,13 +80,61 @@ macro_rules! impl_render_target_extensions_body {
             objects: impl IntoIterator<Item = impl Object>,
             lights: &[&dyn Light],
         ) -> &Self {
+            self.render_partially_with_decals(scissor_box, camera, objects, lights, &[])
+        }
+
+        ///
+        /// Render the objects using the given camera and lights into this render target and project the decals onto the objects with a deferred material, see [Decal].
+        /// Use an empty array for the `lights` argument, if the objects does not require lights to be rendered.
+        /// Also, objects outside the camera frustum are not rendered and the objects are rendered in the order given by [cmp_render_order].
+        ///
+        pub fn render_with_decals(
+            &self,
+            camera: &Camera,
+            objects: impl IntoIterator<Item = impl Object>,
+            lights: &[&dyn Light],
+            decals: &[&Decal],
+        ) -> &Self {
+            self.render_partially_with_decals(self.scissor_box(), camera, objects, lights, decals)
+        }
+
+        ///
+        /// Render the objects using the given camera and lights into the part of this render target defined by the scissor box
+        /// and project the decals onto the objects with a deferred material, see [Decal].
+        /// Use an empty array for the `lights` argument, if the objects does not require lights to be rendered.
+        /// Also, objects outside the camera frustum are not rendered and the objects are rendered in the order given by [cmp_render_order].
+        ///
+        pub fn render_partially_with_decals(
+            &self,
+            scissor_box: ScissorBox,
+            camera: &Camera,
+            objects: impl IntoIterator<Item = impl Object>,
+            lights: &[&dyn Light],
+            decals: &[&Decal],
+        ) -> &Self {
+            let _scope = self.context.profile_scope("render");
             let (mut deferred_objects, mut forward_objects): (Vec<_>, Vec<_>) = objects
                 .into_iter()
                 .filter(|o| camera.in_frustum(&o.aabb()))
-                .partition(|o| o.material_type() == MaterialType::Deferred);
+                .partition(|o| {
+                    matches!(
+                        o.material_type(),
+                        MaterialType::Deferred | MaterialType::DeferredSubsurface
+                    )
+                });
 
             // Deferred
             if deferred_objects.len() > 0 {
+                // The subsurface scattering data is only written to the geometry pass texture and the subsurface scattering passes only run when needed
+                let subsurface_scattering = deferred_objects
+                    .iter()
+                    .any(|o| o.material_type() == MaterialType::DeferredSubsurface);
+                let gbuffer_layers: &[u32] = if subsurface_scattering {
+                    &[0, 1, 2, 3]
+                } else {
+                    &[0, 1, 2]
+                };
+
                 // Geometry pass
                 let mut geometry_pass_camera = camera.clone();
                 let viewport =
@@ -94,7 +145,7 @@ macro_rules! impl_render_target_extensions_body {
                     &self.context,
                     viewport.width,
                     viewport.height,
-                    3,
+                    gbuffer_layers.len() as u32,
                     Interpolation::Nearest,
                     Interpolation::Nearest,
                     None,
@@ -108,9 +159,9 @@ macro_rules! impl_render_target_extensions_body {
                     Wrapping::ClampToEdge,
                     Wrapping::ClampToEdge,
                 );
-                let gbuffer_layers = [0, 1, 2];
+                let geometry_pass_scope = self.context.profile_scope("geometry pass");
                 RenderTarget::new(
-                    geometry_pass_texture.as_color_target(&gbuffer_layers, None),
+                    geometry_pass_texture.as_color_target(gbuffer_layers, None),
                     geometry_pass_depth_texture.as_depth_target(),
                 )
                 .clear(ClearState::default())
@@ -119,23 +170,105 @@ macro_rules! impl_render_target_extensions_body {
                         object.render(&geometry_pass_camera, lights);
                     }
                 });
+                drop(geometry_pass_scope);
+
+                // Decal pass
+                let decal_pass_scope = self.context.profile_scope("decal pass");
+                Decal::apply_decals(
+                    &self.context,
+                    &geometry_pass_camera,
+                    decals,
+                    &mut geometry_pass_texture,
+                    &geometry_pass_depth_texture,
+                );
+                drop(decal_pass_scope);
 
                 // Lighting pass
-                self.write_partially(scissor_box, || {
-                    DeferredPhysicalMaterial::lighting_pass(
-                        &self.context,
-                        camera,
-                        ColorTexture::Array {
-                            texture: &geometry_pass_texture,
-                            layers: &gbuffer_layers,
-                        },
-                        DepthTexture::Single(&geometry_pass_depth_texture),
-                        lights,
-                    )
-                });
+                // The scattering is done in half float textures, without them the lighting pass only applies the light transmitted through thin surfaces,
+                // which is the same approximation as when the subsurface material is not deferred
+                let lighting_pass_scope = self.context.profile_scope("lighting pass");
+                if !subsurface_scattering || !self.context.is_half_float_render_target_supported() {
+                    self.write_partially(scissor_box, || {
+                        DeferredPhysicalMaterial::lighting_pass(
+                            &self.context,
+                            camera,
+                            ColorTexture::Array {
+                                texture: &geometry_pass_texture,
+                                layers: gbuffer_layers,
+                            },
+                            DepthTexture::Single(&geometry_pass_depth_texture),
+                            lights,
+                        )
+                    });
+                } else {
+                    // The linear lighting is rendered into a half float texture and then blurred before it is tone mapped
+                    let new_color_texture = || {
+                        Texture2D::new_empty::<[f16; 4]>(
+                            &self.context,
+                            viewport.width,
+                            viewport.height,
+                            Interpolation::Nearest,
+                            Interpolation::Nearest,
+                            None,
+                            Wrapping::ClampToEdge,
+                            Wrapping::ClampToEdge,
+                        )
+                    };
+                    let mut lighting_pass_texture = new_color_texture();
+                    lighting_pass_texture
+                        .as_color_target(None)
+                        .clear(ClearState::color(0.0, 0.0, 0.0, 0.0))
+                        .write(|| {
+                            DeferredPhysicalMaterial::lighting_pass_with_output(
+                                &self.context,
+                                &geometry_pass_camera,
+                                ColorTexture::Array {
+                                    texture: &geometry_pass_texture,
+                                    layers: gbuffer_layers,
+                                },
+                                DepthTexture::Single(&geometry_pass_depth_texture),
+                                lights,
+                                true,
+                            )
+                        });
+                    drop(lighting_pass_scope);
+
+                    // Subsurface scattering pass, blurring horizontally and then vertically
+                    let _scattering_pass_scope =
+                        self.context.profile_scope("subsurface scattering pass");
+                    let right = camera.right_direction();
+                    let up = right.cross(camera.view_direction()).normalize();
+                    let mut scattering_pass_texture = new_color_texture();
+                    scattering_pass_texture
+                        .as_color_target(None)
+                        .clear(ClearState::color(0.0, 0.0, 0.0, 0.0))
+                        .write(|| {
+                            SubsurfaceMaterial::scattering_pass(
+                                &self.context,
+                                &geometry_pass_camera,
+                                &lighting_pass_texture,
+                                &geometry_pass_depth_texture,
+                                &geometry_pass_texture,
+                                right,
+                                false,
+                            )
+                        });
+                    self.write_partially(scissor_box, || {
+                        SubsurfaceMaterial::scattering_pass(
+                            &self.context,
+                            camera,
+                            &scattering_pass_texture,
+                            &geometry_pass_depth_texture,
+                            &geometry_pass_texture,
+                            up,
+                            true,
+                        )
+                    });
+                }
             }
 
             // Forward
+            let _forward_pass_scope = self.context.profile_scope("forward pass");
             forward_objects.sort_by(|a, b| cmp_render_order(camera, a, b));
             self.write_partially(scissor_box, || {
                 for object in forward_objects {
@@ -177,6 +310,7 @@ macro_rules! impl_render_target_extensions_body {
             geometries: impl IntoIterator<Item = impl Geometry>,
             lights: &[&dyn Light],
         ) -> &Self {
+            let _scope = self.context.profile_scope("render with material");
             self.write_partially(scissor_box, || {
                 for object in geometries
                     .into_iter()
@@ -226,6 +360,7 @@ macro_rules! impl_render_target_extensions_body {
             color_texture: Option<ColorTexture>,
             depth_texture: Option<DepthTexture>,
         ) -> &Self {
+            let _scope = self.context.profile_scope("render with post material");
             self.write_partially(scissor_box, || {
                 for object in geometries
                     .into_iter()
@@ -333,6 +468,50 @@ pub fn cmp_render_order(
     }
 }
 
+///
+/// Compiles the shader programs needed to render the given objects with the given camera and lights by rendering them into a small offscreen render target.
+/// Call this during loading to avoid a hitch the first time the objects are rendered to the screen.
+/// If parallel shader compilation is enabled (see [Context::set_parallel_shader_compile]), the programs are compiled without blocking,
+/// so call this every frame until it returns `true`, which means that all of the programs are compiled and ready to use.
+///
+pub fn precompile(
+    context: &Context,
+    camera: &Camera,
+    objects: impl IntoIterator<Item = impl Object>,
+    lights: &[&dyn Light],
+) -> bool {
+    let viewport = Viewport::new_at_origo(1, 1);
+    let mut texture = Texture2D::new_empty::<[u8; 4]>(
+        context,
+        viewport.width,
+        viewport.height,
+        Interpolation::Nearest,
+        Interpolation::Nearest,
+        None,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+    );
+    let mut depth_texture = DepthTexture2D::new::<f32>(
+        context,
+        viewport.width,
+        viewport.height,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+    );
+    RenderTarget::new(
+        texture.as_color_target(None),
+        depth_texture.as_depth_target(),
+    )
+    .clear(ClearState::color_and_depth(0.0, 0.0, 0.0, 0.0, 1.0))
+    .write(|| {
+        // Not culled against the camera frustum, since the programs are needed no matter where the objects are
+        for object in objects {
+            object.render(camera, lights);
+        }
+    });
+    !context.is_compiling_programs()
+}
+
 ///
 /// Finds the closest intersection between a ray from the given camera in the given pixel coordinate and the given geometries.
 /// The pixel coordinate must be in physical pixels, where (viewport.x, viewport.y) indicate the bottom left corner of the viewport
@@ -356,6 +535,27 @@ pub fn pick(
     )
 }
 
+///
+/// Same as [pick] except that the result is not read back from the GPU immediately, which avoids stalling the pipeline.
+/// Use the returned [PendingIntersection] to retrieve the result a frame or two later, for example when picking on mouse hover.
+///
+pub fn pick_async(
+    context: &Context,
+    camera: &Camera,
+    pixel: (f32, f32),
+    geometries: impl IntoIterator<Item = impl Geometry>,
+) -> PendingIntersection {
+    let pos = camera.position_at_pixel(pixel);
+    let dir = camera.view_direction_at_pixel(pixel);
+    ray_intersect_async(
+        context,
+        pos + dir * camera.z_near(),
+        dir,
+        camera.z_far() - camera.z_near(),
+        geometries,
+    )
+}
+
 ///
 /// Finds the closest intersection between a ray starting at the given position in the given direction and the given geometries.
 /// Returns ```None``` if no geometry was hit before the given maximum depth.
@@ -367,6 +567,87 @@ pub fn ray_intersect(
     max_depth: f32,
     geometries: impl IntoIterator<Item = impl Geometry>,
 ) -> Option<Vec3> {
+    let depth = render_ray_depth(
+        context,
+        position,
+        direction,
+        max_depth,
+        geometries,
+        |target| target.read_color::<f32>()[0],
+    );
+    intersection_from_depth(position, direction, max_depth, depth)
+}
+
+///
+/// Same as [ray_intersect] except that the result is not read back from the GPU immediately, which avoids stalling the pipeline.
+/// Use the returned [PendingIntersection] to retrieve the result a frame or two later.
+///
+pub fn ray_intersect_async(
+    context: &Context,
+    position: Vec3,
+    direction: Vec3,
+    max_depth: f32,
+    geometries: impl IntoIterator<Item = impl Geometry>,
+) -> PendingIntersection {
+    let readback = render_ray_depth(
+        context,
+        position,
+        direction,
+        max_depth,
+        geometries,
+        |target| target.read_color_async::<f32>(),
+    );
+    PendingIntersection {
+        readback,
+        position,
+        direction,
+        max_depth,
+    }
+}
+
+///
+/// The result of [pick_async] or [ray_intersect_async] which is being read back from the GPU.
+///
+pub struct PendingIntersection {
+    readback: PixelReadback<f32>,
+    position: Vec3,
+    direction: Vec3,
+    max_depth: f32,
+}
+
+impl PendingIntersection {
+    ///
+    /// Returns `None` if the result is not yet available, otherwise the same result as the synchronous [pick] or [ray_intersect],
+    /// ie. the closest intersection or `None` if no geometry was hit.
+    ///
+    pub fn try_read(&self) -> Option<Option<Vec3>> {
+        self.readback.try_read().map(|depth| {
+            intersection_from_depth(self.position, self.direction, self.max_depth, depth[0])
+        })
+    }
+}
+
+fn intersection_from_depth(
+    position: Vec3,
+    direction: Vec3,
+    max_depth: f32,
+    depth: f32,
+) -> Option<Vec3> {
+    if depth < 1.0 {
+        Some(position + direction * depth * max_depth)
+    } else {
+        None
+    }
+}
+
+fn render_ray_depth<R>(
+    context: &Context,
+    position: Vec3,
+    direction: Vec3,
+    max_depth: f32,
+    geometries: impl IntoIterator<Item = impl Geometry>,
+    read: impl FnOnce(&RenderTarget) -> R,
+) -> R {
     use crate::core::*;
     let viewport = Viewport::new_at_origo(1, 1);
     let up = if direction.dot(vec3(1.0, 0.0, 0.0)).abs() > 0.99 {
@@ -410,20 +691,18 @@ pub fn ray_intersect(
         },
         ..Default::default()
     };
-    let depth = RenderTarget::new(
+    let target = RenderTarget::new(
         texture.as_color_target(None),
         depth_texture.as_depth_target(),
-    )
-    .clear(ClearState::color_and_depth(1.0, 1.0, 1.0, 1.0, 1.0))
-    .write(|| {
-        for geometry in geometries {
-            geometry.render_with_material(&depth_material, &camera, &[]);
-        }
-    })
-    .read_color()[0];
-    if depth < 1.0 {
-        Some(position + direction * depth * max_depth)
-    } else {
-        None
-    }
+    );
+    context.with_blocking_shader_compile(|| {
+        target
+            .clear(ClearState::color_and_depth(1.0, 1.0, 1.0, 1.0, 1.0))
+            .write(|| {
+                for geometry in geometries {
+                    geometry.render_with_material(&depth_material, &camera, &[]);
+                }
+            });
+    });
+    read(&target)
 }
@@ -35,13 +35,12 @@ impl FogEffect {
         camera: &Camera,
         depth_texture: DepthTexture,
     ) {
+        let _scope = context.profile_scope("fog effect");
         apply_effect(
             context,
             &format!(
-                "{}\n{}\n{}",
-                include_str!("../../core/shared.frag"),
+                "#include \"shared\"\n{}\n#include \"fog_effect\"\n",
                 depth_texture.fragment_shader_source(),
-                include_str!("shaders/fog_effect.frag")
             ),
             RenderStates {
                 write_mask: WriteMask::COLOR,
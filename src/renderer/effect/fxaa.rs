@@ -13,12 +13,12 @@ impl FxaaEffect {
     /// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
     ///
     pub fn apply(&self, context: &Context, color_texture: ColorTexture) {
+        let _scope = context.profile_scope("fxaa effect");
         apply_effect(
             context,
             &format!(
-                "{}\n{}",
+                "{}\n#include \"fxaa_effect\"\n",
                 color_texture.fragment_shader_source(),
-                include_str!("shaders/fxaa_effect.frag")
             ),
             RenderStates {
                 write_mask: WriteMask::COLOR,
@@ -36,6 +36,39 @@ mod circle;
 #[doc(inline)]
 pub use circle::*;
 
+mod skeleton;
+#[doc(inline)]
+pub use skeleton::*;
+
+mod morph;
+#[doc(inline)]
+pub use morph::*;
+
+mod animation;
+#[doc(inline)]
+pub use animation::*;
+
+mod instance_culling;
+#[doc(inline)]
+pub use instance_culling::*;
+
+mod lod;
+#[doc(inline)]
+pub use lod::*;
+
+mod polyline;
+#[doc(inline)]
+pub use polyline::*;
+
+pub mod mesh_builder;
+
+#[cfg(feature = "text")]
+#[cfg_attr(docsrs, doc(cfg(feature = "text")))]
+mod text;
+#[doc(inline)]
+#[cfg(feature = "text")]
+pub use text::*;
+
 use crate::core::*;
 use crate::renderer::*;
 
@@ -56,6 +89,7 @@ pub use three_d_asset::{
 /// - bitangent: `out vec3 bitang;`
 /// - uv coordinates: `out vec2 uvs;` (must be flipped in v compared to standard uv coordinates, ie. do `uvs = vec2(uvs.x, 1.0 - uvs.y);` in the vertex shader or do the flip before constructing the uv coordinates vertex buffer)
 /// - color: `out vec4 col;`
+/// - lightmap uv coordinates: `out vec2 lightmap_uvs;` (must be flipped in v in the same way as the uv coordinates)
 ///
 pub trait Geometry {
     ///
@@ -311,13 +345,32 @@ struct BaseMesh {
     normals: Option<VertexBuffer>,
     tangents: Option<VertexBuffer>,
     uvs: Option<VertexBuffer>,
+    lightmap_uvs: Option<VertexBuffer>,
     colors: Option<VertexBuffer>,
+    joint_indices: Option<VertexBuffer>,
+    joint_weights: Option<VertexBuffer>,
+    skin: Option<Skin>,
+    morph_targets: Option<MorphTargets>,
+    morph_animations: MorphAnimations,
+    topology: PrimitiveTopology,
+    point_size: f32,
 }
 
 impl BaseMesh {
     pub fn new(context: &Context, cpu_mesh: &CpuMesh) -> Self {
+        Self::new_with_topology(context, cpu_mesh, PrimitiveTopology::Triangles)
+    }
+
+    pub fn new_with_topology(
+        context: &Context,
+        cpu_mesh: &CpuMesh,
+        topology: PrimitiveTopology,
+    ) -> Self {
+        // The validation assumes a list of triangles
         #[cfg(debug_assertions)]
-        cpu_mesh.validate().expect("invalid cpu mesh");
+        if topology == PrimitiveTopology::Triangles {
+            cpu_mesh.validate().expect("invalid cpu mesh");
+        }
 
         Self {
             indices: match &cpu_mesh.indices {
@@ -344,13 +397,212 @@ impl BaseMesh {
                         .collect::<Vec<_>>(),
                 )
             }),
+            lightmap_uvs: None,
             colors: cpu_mesh
                 .colors
                 .as_ref()
                 .map(|data| VertexBuffer::new_with_data(context, data)),
+            joint_indices: None,
+            joint_weights: None,
+            skin: None,
+            morph_targets: None,
+            morph_animations: MorphAnimations::default(),
+            topology,
+            point_size: 1.0,
+        }
+    }
+
+    pub fn points_shader_source(&self) -> &'static str {
+        if self.topology == PrimitiveTopology::Points {
+            "#define USE_POINTS\n"
+        } else {
+            ""
+        }
+    }
+
+    pub fn set_lightmap_uvs(&mut self, context: &Context, lightmap_uvs: &[Vec2]) {
+        #[cfg(debug_assertions)]
+        if lightmap_uvs.len() != self.positions.vertex_count() as usize {
+            panic!(
+                "the number of lightmap uv coordinates ({}) does not match the number of vertices ({})",
+                lightmap_uvs.len(),
+                self.positions.vertex_count()
+            );
+        }
+        self.lightmap_uvs = Some(VertexBuffer::new_with_data(
+            context,
+            &lightmap_uvs
+                .iter()
+                .map(|uv| vec2(uv.x, 1.0 - uv.y))
+                .collect::<Vec<_>>(),
+        ));
+    }
+
+    pub fn update_positions(&mut self, offset: u32, positions: &[Vec3]) {
+        Self::update_buffer(&mut self.positions, offset, positions);
+    }
+
+    pub fn update_normals(&mut self, offset: u32, normals: &[Vec3]) {
+        Self::update_buffer(
+            self.normals
+                .as_mut()
+                .expect("cannot update the normals of a mesh without normals"),
+            offset,
+            normals,
+        );
+    }
+
+    pub fn update_colors(&mut self, offset: u32, colors: &[Color]) {
+        Self::update_buffer(
+            self.colors
+                .as_mut()
+                .expect("cannot update the colors of a mesh without colors"),
+            offset,
+            colors,
+        );
+    }
+
+    fn update_buffer<T: BufferDataType>(buffer: &mut VertexBuffer, offset: u32, data: &[T]) {
+        if offset == 0
+            && data.len() == buffer.vertex_count() as usize
+            && buffer.usage() != BufferUsage::Static
+        {
+            // All data of a buffer which is changed often is replaced, so orphan the old data instead of waiting for the GPU to finish using it
+            buffer.fill(data);
+        } else {
+            buffer.fill_subset(offset, data);
+        }
+    }
+
+    pub fn set_skin(
+        &mut self,
+        context: &Context,
+        skeleton: Skeleton,
+        joint_indices: &[[u16; 4]],
+        joint_weights: &[Vec4],
+    ) {
+        #[cfg(debug_assertions)]
+        if joint_indices.len() != self.positions.vertex_count() as usize
+            || joint_weights.len() != self.positions.vertex_count() as usize
+        {
+            panic!(
+                "the number of joint indices ({}) and joint weights ({}) does not match the number of vertices ({})",
+                joint_indices.len(),
+                joint_weights.len(),
+                self.positions.vertex_count()
+            );
+        }
+        #[cfg(debug_assertions)]
+        if let Some(index) = joint_indices
+            .iter()
+            .flatten()
+            .find(|i| **i as usize >= skeleton.joints.len())
+        {
+            panic!(
+                "the joint index {} is outside the skeleton with {} joints",
+                index,
+                skeleton.joints.len()
+            );
+        }
+        self.joint_indices = Some(VertexBuffer::new_with_data(
+            context,
+            &joint_indices
+                .iter()
+                .map(|i| vec4(i[0] as f32, i[1] as f32, i[2] as f32, i[3] as f32))
+                .collect::<Vec<_>>(),
+        ));
+        self.joint_weights = Some(VertexBuffer::new_with_data(context, joint_weights));
+        self.skin = Some(Skin::new(context, skeleton));
+    }
+
+    pub fn skeleton(&self) -> Option<&Skeleton> {
+        self.skin.as_ref().map(|skin| skin.skeleton())
+    }
+
+    pub fn choose_skeleton_animation(&mut self, animation_name: Option<&str>) {
+        if let Some(skin) = &mut self.skin {
+            skin.choose_animation(animation_name);
+        }
+    }
+
+    pub fn animate_skin(&mut self, time: f32) {
+        if let Some(skin) = &mut self.skin {
+            skin.animate(time);
+        }
+    }
+
+    pub fn skinning_shader_source(&self) -> String {
+        if self.skin.is_some() {
+            format!("#define USE_SKINNING\n#define MAX_JOINTS {}\n", MAX_JOINTS)
+        } else {
+            String::new()
+        }
+    }
+
+    pub fn set_morph_targets(&mut self, context: &Context, morph_targets: &[MorphTarget]) {
+        self.morph_targets = if morph_targets.is_empty() {
+            None
+        } else {
+            Some(MorphTargets::new(
+                context,
+                self.positions.vertex_count() as usize,
+                morph_targets,
+            ))
+        };
+    }
+
+    pub fn morph_weights(&self) -> &[f32] {
+        self.morph_targets
+            .as_ref()
+            .map(|morph_targets| morph_targets.weights())
+            .unwrap_or(&[])
+    }
+
+    pub fn set_morph_weights(&mut self, weights: &[f32]) {
+        self.morph_targets
+            .as_mut()
+            .expect("cannot set morph weights since no morph targets are defined")
+            .set_weights(weights);
+    }
+
+    pub fn morph_animations(&self) -> Vec<Option<String>> {
+        self.morph_animations.names()
+    }
+
+    pub fn set_morph_animations(&mut self, animations: Vec<KeyFrameAnimation>) {
+        self.morph_animations.set(animations);
+    }
+
+    pub fn choose_morph_animation(&mut self, animation_name: Option<&str>) {
+        self.morph_animations.choose(animation_name);
+    }
+
+    pub fn animate_morph_targets(&mut self, time: f32) {
+        if let Some(morph_targets) = &mut self.morph_targets {
+            if let Some(weights) = self.morph_animations.weights(time) {
+                morph_targets.set_animated_weights(weights);
+            }
+        }
+    }
+
+    pub fn animate_blended(&mut self, samples: &[AnimationSample]) {
+        if let Some(skin) = &mut self.skin {
+            skin.animate_blended(samples);
+        }
+        if let Some(morph_targets) = &mut self.morph_targets {
+            if let Some(weights) = self.morph_animations.blended_weights(samples) {
+                morph_targets.set_animated_weights(weights);
+            }
         }
     }
 
+    pub fn morph_targets_shader_source(&self) -> String {
+        self.morph_targets
+            .as_ref()
+            .map(|morph_targets| morph_targets.shader_source())
+            .unwrap_or_default()
+    }
+
     pub fn draw(
         &self,
         program: &Program,
@@ -360,11 +612,17 @@ impl BaseMesh {
     ) {
         self.use_attributes(program, attributes);
         if let Some(index_buffer) = &self.indices {
-            program.draw_elements(render_states, camera.viewport(), index_buffer)
+            program.draw_elements(
+                render_states,
+                camera.viewport(),
+                self.topology,
+                index_buffer,
+            )
         } else {
             program.draw_arrays(
                 render_states,
                 camera.viewport(),
+                self.topology,
                 self.positions.vertex_count(),
             )
         }
@@ -384,6 +642,7 @@ impl BaseMesh {
             program.draw_elements_instanced(
                 render_states,
                 camera.viewport(),
+                self.topology,
                 index_buffer,
                 instance_count,
             )
@@ -391,6 +650,7 @@ impl BaseMesh {
             program.draw_arrays_instanced(
                 render_states,
                 camera.viewport(),
+                self.topology,
                 self.positions.vertex_count(),
                 instance_count,
             )
@@ -399,6 +659,9 @@ impl BaseMesh {
 
     fn use_attributes(&self, program: &Program, attributes: FragmentAttributes) {
         program.use_vertex_attribute("position", &self.positions);
+        if self.topology == PrimitiveTopology::Points {
+            program.use_uniform("pointSize", self.point_size);
+        }
 
         if attributes.normal {
             program.use_vertex_attribute(
@@ -433,8 +696,31 @@ impl BaseMesh {
             );
         }
 
+        if attributes.lightmap_uv {
+            program.use_vertex_attribute(
+                "lightmap_uv_coordinates",
+                self.lightmap_uvs.as_ref().unwrap_or_else(|| {
+                    panic!(
+                        "the material requires lightmap uv coordinate attributes but the geometry did not provide it"
+                    )
+                }),
+            );
+        }
+
         if let Some(colors) = &self.colors {
             program.use_vertex_attribute("color", colors);
         }
+
+        if let (Some(skin), Some(joint_indices), Some(joint_weights)) =
+            (&self.skin, &self.joint_indices, &self.joint_weights)
+        {
+            program.use_vertex_attribute("joint_indices", joint_indices);
+            program.use_vertex_attribute("joint_weights", joint_weights);
+            skin.use_uniforms(program);
+        }
+
+        if let Some(morph_targets) = &self.morph_targets {
+            morph_targets.use_uniforms(program);
+        }
     }
 }
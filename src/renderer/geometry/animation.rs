@@ -0,0 +1,481 @@
+use crate::core::*;
+use std::collections::HashMap;
+
+///
+/// A sample of an animation clip, ie. the animation with the given name evaluated at the given time and weighted by the given weight.
+/// A list of samples is blended together when animating, see for example [Mesh::animate_blended].
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationSample {
+    /// The name of the animation.
+    pub name: Option<String>,
+    /// The time at which the animation is evaluated.
+    pub time: f32,
+    /// The weight of this sample relative to the other samples.
+    pub weight: f32,
+}
+
+///
+/// Defines how an [AnimationClip] is played.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaybackMode {
+    /// The clip starts over when it reaches the end.
+    Loop,
+    /// The clip stays at the end when it reaches the end.
+    Clamp,
+}
+
+///
+/// An animation clip which can be played by an [AnimationController].
+///
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+    /// The name of the animation, for example one of the names returned by [Model::animations].
+    pub name: Option<String>,
+    /// The duration of the animation.
+    pub duration: f32,
+    /// Whether the clip loops or clamps at the end.
+    pub mode: PlaybackMode,
+    /// The playback speed, where `1.0` is the original speed.
+    pub speed: f32,
+    /// A list of markers given as a time in the clip and an event name. An [AnimationEvent] is fired each time the playback passes a marker.
+    pub markers: Vec<(f32, String)>,
+}
+
+impl AnimationClip {
+    ///
+    /// Creates a new looping animation clip with the given name and duration which is played at the original speed.
+    ///
+    pub fn new(name: Option<&str>, duration: f32) -> Self {
+        Self {
+            name: name.map(|name| name.to_owned()),
+            duration,
+            mode: PlaybackMode::Loop,
+            speed: 1.0,
+            markers: Vec::new(),
+        }
+    }
+}
+
+///
+/// An event fired by an [AnimationController] when the playback of a clip passes one of the [AnimationClip::markers].
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationEvent {
+    /// The name of the clip.
+    pub clip: Option<String>,
+    /// The name of the marker.
+    pub name: String,
+}
+
+struct PlayingClip {
+    clip: usize,
+    time: f32,
+    weight: f32,
+    fade_speed: f32,
+    faded_out: bool,
+}
+
+///
+/// Plays a set of [AnimationClip]s with individual weights and cross-fades between them.
+/// Call [AnimationController::update] each frame and use [AnimationController::samples] to animate, for example using [Model::animate_with].
+///
+#[derive(Default)]
+pub struct AnimationController {
+    clips: Vec<AnimationClip>,
+    playing: Vec<PlayingClip>,
+    events: Vec<AnimationEvent>,
+    last_time: Option<f32>,
+}
+
+impl AnimationController {
+    ///
+    /// Creates a new animation controller with the given clips.
+    ///
+    pub fn new(clips: Vec<AnimationClip>) -> Self {
+        Self {
+            clips,
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// Returns the clip with the given name, if any.
+    ///
+    pub fn clip_mut(&mut self, name: Option<&str>) -> Option<&mut AnimationClip> {
+        self.clips.iter_mut().find(|c| c.name.as_deref() == name)
+    }
+
+    ///
+    /// Starts playing the clip with the given name from the start with the given weight without affecting the other playing clips.
+    /// If the clip is already playing, only the weight is changed. A clip with a weight of zero keeps playing but is not part of the [Self::samples].
+    ///
+    /// # Panic
+    /// Will panic if there is no clip with the given name.
+    ///
+    pub fn play(&mut self, name: Option<&str>, weight: f32) {
+        let clip = self.clip_index(name);
+        if let Some(playing) = self.playing.iter_mut().find(|p| p.clip == clip) {
+            playing.weight = weight;
+            playing.fade_speed = 0.0;
+        } else {
+            self.playing.push(PlayingClip {
+                clip,
+                time: 0.0,
+                weight,
+                fade_speed: 0.0,
+                faded_out: false,
+            });
+        }
+    }
+
+    ///
+    /// Stops playing the clip with the given name.
+    ///
+    pub fn stop(&mut self, name: Option<&str>) {
+        self.playing
+            .retain(|p| self.clips[p.clip].name.as_deref() != name);
+    }
+
+    ///
+    /// Sets the weight of the clip with the given name if it is playing.
+    ///
+    pub fn set_weight(&mut self, name: Option<&str>, weight: f32) {
+        let clips = &self.clips;
+        if let Some(playing) = self
+            .playing
+            .iter_mut()
+            .find(|p| clips[p.clip].name.as_deref() == name)
+        {
+            playing.weight = weight;
+            playing.fade_speed = 0.0;
+        }
+    }
+
+    ///
+    /// Fades in the clip with the given name and fades out all other playing clips over the given duration.
+    /// The clip is started from the start if it is not already playing.
+    /// A duration of zero switches to the clip instantly.
+    ///
+    /// # Panic
+    /// Will panic if there is no clip with the given name.
+    ///
+    pub fn cross_fade(&mut self, name: Option<&str>, duration: f32) {
+        let clip = self.clip_index(name);
+        if duration <= 0.0 {
+            self.playing.retain(|p| p.clip == clip);
+            self.play(name, 1.0);
+            return;
+        }
+        if !self.playing.iter().any(|p| p.clip == clip) {
+            self.playing.push(PlayingClip {
+                clip,
+                time: 0.0,
+                weight: 0.0,
+                fade_speed: 0.0,
+                faded_out: false,
+            });
+        }
+        for playing in self.playing.iter_mut() {
+            let target = if playing.clip == clip { 1.0 } else { 0.0 };
+            playing.fade_speed = (target - playing.weight) / duration;
+        }
+    }
+
+    ///
+    /// Advances the playing clips to the given time. The time parameter should be some continious time, for example the time since start.
+    /// Clips that have faded out are stopped.
+    ///
+    pub fn update(&mut self, time: f32) {
+        let elapsed = self.last_time.map(|t| (time - t).max(0.0)).unwrap_or(0.0);
+        self.last_time = Some(time);
+        for playing in self.playing.iter_mut() {
+            let clip = &self.clips[playing.clip];
+            let last = playing.time;
+            let mut end = last + elapsed * clip.speed;
+            if clip.duration > 0.0 && end > clip.duration && clip.mode == PlaybackMode::Clamp {
+                end = clip.duration;
+            }
+            if end > last {
+                for (marker_time, marker_name) in clip.markers.iter() {
+                    // The number of times the marker is passed in the interval (last, end], one for each loop
+                    let count = if clip.duration > 0.0 && clip.mode == PlaybackMode::Loop {
+                        ((end - marker_time) / clip.duration).floor()
+                            - ((last - marker_time) / clip.duration).floor()
+                    } else if *marker_time > last && *marker_time <= end {
+                        1.0
+                    } else {
+                        0.0
+                    };
+                    for _ in 0..count.max(0.0) as usize {
+                        self.events.push(AnimationEvent {
+                            clip: clip.name.clone(),
+                            name: marker_name.clone(),
+                        });
+                    }
+                }
+            }
+            playing.time = if clip.duration > 0.0 && clip.mode == PlaybackMode::Loop {
+                end % clip.duration
+            } else {
+                end
+            };
+
+            if playing.fade_speed != 0.0 {
+                playing.weight = (playing.weight + playing.fade_speed * elapsed).clamp(0.0, 1.0);
+                if playing.weight == 0.0 {
+                    playing.faded_out = true;
+                }
+                if playing.weight == 0.0 || playing.weight == 1.0 {
+                    playing.fade_speed = 0.0;
+                }
+            }
+        }
+        self.playing.retain(|p| !p.faded_out);
+    }
+
+    ///
+    /// Returns the events fired since the last call to this method.
+    ///
+    pub fn take_events(&mut self) -> Vec<AnimationEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    ///
+    /// Returns a sample for each playing clip with a weight above zero.
+    ///
+    pub fn samples(&self) -> Vec<AnimationSample> {
+        self.playing
+            .iter()
+            .filter(|p| p.weight > 0.0)
+            .map(|p| AnimationSample {
+                name: self.clips[p.clip].name.clone(),
+                time: p.time,
+                weight: p.weight,
+            })
+            .collect()
+    }
+
+    fn clip_index(&self, name: Option<&str>) -> usize {
+        self.clips
+            .iter()
+            .position(|c| c.name.as_deref() == name)
+            .unwrap_or_else(|| panic!("there is no animation clip with the name {:?}", name))
+    }
+}
+
+///
+/// The parameters used by the transition conditions in an [AnimationStateMachine], for example the speed of a character.
+///
+pub type AnimationParameters = HashMap<String, f32>;
+
+struct AnimationTransition {
+    from: String,
+    to: String,
+    duration: f32,
+    condition: Box<dyn Fn(&AnimationParameters) -> bool + Send + Sync>,
+}
+
+///
+/// A simple state machine on top of an [AnimationController] where each state plays an [AnimationClip] and
+/// transitions between states cross-fade between the clips when the condition of the transition is fulfilled.
+///
+pub struct AnimationStateMachine {
+    controller: AnimationController,
+    states: HashMap<String, Option<String>>,
+    transitions: Vec<AnimationTransition>,
+    current_state: String,
+    parameters: AnimationParameters,
+}
+
+impl AnimationStateMachine {
+    ///
+    /// Creates a new state machine with a single state with the given name which plays the clip with the given name.
+    ///
+    pub fn new(controller: AnimationController, state: &str, clip: Option<&str>) -> Self {
+        let mut state_machine = Self {
+            controller,
+            states: HashMap::new(),
+            transitions: Vec::new(),
+            current_state: state.to_owned(),
+            parameters: AnimationParameters::new(),
+        };
+        state_machine.add_state(state, clip);
+        state_machine.controller.cross_fade(clip, 0.0);
+        state_machine
+    }
+
+    ///
+    /// Adds a state with the given name which plays the clip with the given name.
+    ///
+    pub fn add_state(&mut self, state: &str, clip: Option<&str>) {
+        self.states
+            .insert(state.to_owned(), clip.map(|name| name.to_owned()));
+    }
+
+    ///
+    /// Adds a transition from one state to another which cross-fades over the given duration when the condition is fulfilled.
+    /// The transitions are checked in the order in which they are added.
+    ///
+    pub fn add_transition(
+        &mut self,
+        from: &str,
+        to: &str,
+        duration: f32,
+        condition: impl Fn(&AnimationParameters) -> bool + Send + Sync + 'static,
+    ) {
+        self.transitions.push(AnimationTransition {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            duration,
+            condition: Box::new(condition),
+        });
+    }
+
+    ///
+    /// Sets the parameter with the given name which is used by the transition conditions.
+    ///
+    pub fn set_parameter(&mut self, name: &str, value: f32) {
+        self.parameters.insert(name.to_owned(), value);
+    }
+
+    ///
+    /// Returns the name of the current state.
+    ///
+    pub fn current_state(&self) -> &str {
+        &self.current_state
+    }
+
+    ///
+    /// Returns the underlying [AnimationController].
+    ///
+    pub fn controller(&self) -> &AnimationController {
+        &self.controller
+    }
+
+    ///
+    /// Returns the underlying [AnimationController] mutably, for example to take the fired events.
+    ///
+    pub fn controller_mut(&mut self) -> &mut AnimationController {
+        &mut self.controller
+    }
+
+    ///
+    /// Checks the transitions from the current state and advances the playing clips to the given time.
+    /// The time parameter should be some continious time, for example the time since start.
+    ///
+    pub fn update(&mut self, time: f32) {
+        if let Some(transition) = self
+            .transitions
+            .iter()
+            .find(|t| t.from == self.current_state && (t.condition)(&self.parameters))
+        {
+            let clip = self
+                .states
+                .get(&transition.to)
+                .unwrap_or_else(|| panic!("there is no animation state named {}", transition.to));
+            self.controller
+                .cross_fade(clip.as_deref(), transition.duration);
+            self.current_state = transition.to.clone();
+        }
+        self.controller.update(time);
+    }
+}
+
+///
+/// Blends the given transformations weighted by the given weights by blending the translation, rotation and scale separately.
+/// Returns the identity if the sum of the weights is zero.
+///
+pub(crate) fn blend_transformations(transformations: &[(Mat4, f32)]) -> Mat4 {
+    let total_weight: f32 = transformations.iter().map(|(_, w)| *w).sum();
+    if total_weight <= 0.0 {
+        return Mat4::identity();
+    }
+    if transformations.len() == 1 {
+        return transformations[0].0;
+    }
+    let mut translation = vec3(0.0, 0.0, 0.0);
+    let mut scale = vec3(0.0, 0.0, 0.0);
+    let mut rotation = Quat::new(0.0, 0.0, 0.0, 0.0);
+    let mut first_rotation = None;
+    for (transformation, weight) in transformations {
+        let weight = weight / total_weight;
+        let x = transformation.x.truncate();
+        let y = transformation.y.truncate();
+        let z = transformation.z.truncate();
+        let s = vec3(x.magnitude(), y.magnitude(), z.magnitude());
+        let mut r = Quat::from(Mat3::from_cols(x / s.x, y / s.y, z / s.z));
+        let first = *first_rotation.get_or_insert(r);
+        if first.dot(r) < 0.0 {
+            r = -r;
+        }
+        translation += weight * transformation.w.truncate();
+        scale += weight * s;
+        rotation += weight * r;
+    }
+    Mat4::from_translation(translation)
+        * Mat4::from(rotation.normalize())
+        * Mat4::from_nonuniform_scale(scale.x, scale.y, scale.z)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cross_fade() {
+        let mut controller = AnimationController::new(vec![
+            AnimationClip::new(Some("idle"), 1.0),
+            AnimationClip::new(Some("walk"), 1.0),
+        ]);
+        controller.play(Some("idle"), 1.0);
+        controller.update(0.0);
+        controller.cross_fade(Some("walk"), 1.0);
+        controller.update(0.5);
+        let samples = controller.samples();
+        assert_eq!(samples.len(), 2);
+        assert!(samples.iter().all(|s| (s.weight - 0.5).abs() < 0.0001));
+        controller.update(1.0);
+        let samples = controller.samples();
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].name.as_deref(), Some("walk"));
+    }
+
+    #[test]
+    fn markers() {
+        let mut clip = AnimationClip::new(Some("walk"), 1.0);
+        clip.markers.push((0.25, "step".to_owned()));
+        let mut controller = AnimationController::new(vec![clip]);
+        controller.play(Some("walk"), 1.0);
+        controller.update(0.0);
+        controller.update(0.5);
+        assert_eq!(controller.take_events().len(), 1);
+        controller.update(1.1);
+        assert_eq!(controller.take_events().len(), 0);
+        controller.update(1.3);
+        assert_eq!(controller.take_events().len(), 1);
+        controller.update(4.3);
+        assert_eq!(controller.take_events().len(), 3);
+    }
+
+    #[test]
+    fn instant_switch() {
+        let mut controller = AnimationController::new(vec![
+            AnimationClip::new(Some("idle"), 1.0),
+            AnimationClip::new(Some("walk"), 1.0),
+        ]);
+        controller.play(Some("idle"), 1.0);
+        controller.play(Some("walk"), 0.0);
+        controller.update(0.0);
+        controller.update(0.5);
+        controller.set_weight(Some("walk"), 0.5);
+        assert_eq!(controller.samples().len(), 2);
+        controller.cross_fade(Some("walk"), 0.0);
+        controller.update(1.0);
+        let samples = controller.samples();
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].name.as_deref(), Some("walk"));
+        assert_eq!(samples[0].weight, 1.0);
+    }
+}
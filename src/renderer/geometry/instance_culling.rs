@@ -0,0 +1,180 @@
+use crate::renderer::*;
+use std::sync::Arc;
+
+use super::lod::LodLevel;
+
+/// The minimum number of instances before the culling is split between several threads.
+#[cfg(not(target_arch = "wasm32"))]
+const MIN_INSTANCES_PER_THREAD: usize = 4096;
+
+///
+/// Defines which instances of an [InstancedMesh] are culled, ie. not rendered, see [InstancedMesh::set_culling].
+/// The culling is done on the CPU each time the instanced mesh is rendered with a new camera and is split between several threads for large numbers of instances.
+/// Use [InstancedMesh::set_background_culling] to avoid waiting for the culling when rendering.
+///
+#[derive(Clone, Default)]
+pub enum InstanceCulling {
+    /// All instances are rendered.
+    #[default]
+    None,
+    /// Only instances inside the camera frustum are rendered.
+    Frustum,
+    /// Only instances inside the camera frustum which are not occluded according to the given [DepthPyramid] are rendered.
+    FrustumAndOcclusion(Arc<DepthPyramid>),
+}
+
+///
+/// A hierarchical depth buffer (Hi-Z) where each level contains the maximum depth of 2x2 pixels in the previous level.
+/// Used for occlusion culling of the instances of an [InstancedMesh], see [InstanceCulling::FrustumAndOcclusion].
+///
+/// The depth pyramid is usually constructed from the depth buffer of the previous frame which contains the occluders,
+/// which means that an instance appearing from behind an occluder is culled for a frame.
+///
+pub struct DepthPyramid {
+    view_projection: Mat4,
+    levels: Vec<(usize, usize, Vec<f32>)>,
+}
+
+impl DepthPyramid {
+    ///
+    /// Constructs a new depth pyramid from the given depth values, for example returned by [RenderTarget::read_depth],
+    /// with the given width and height and rendered with the given camera.
+    /// The depth values should be ordered row by row starting from the bottom row.
+    ///
+    pub fn new(depth: &[f32], width: u32, height: u32, camera: &Camera) -> Self {
+        let mut levels = vec![(width as usize, height as usize, depth.to_vec())];
+        while let Some((w, h, data)) = levels.last() {
+            if *w <= 1 && *h <= 1 {
+                break;
+            }
+            let (w, h) = (*w, *h);
+            let next_w = w.div_ceil(2);
+            let next_h = h.div_ceil(2);
+            let mut next = vec![0.0; next_w * next_h];
+            for y in 0..next_h {
+                for x in 0..next_w {
+                    let x0 = 2 * x;
+                    let y0 = 2 * y;
+                    let x1 = (x0 + 1).min(w - 1);
+                    let y1 = (y0 + 1).min(h - 1);
+                    next[y * next_w + x] = data[y0 * w + x0]
+                        .max(data[y0 * w + x1])
+                        .max(data[y1 * w + x0])
+                        .max(data[y1 * w + x1]);
+                }
+            }
+            levels.push((next_w, next_h, next));
+        }
+        Self {
+            view_projection: camera.projection() * camera.view(),
+            levels,
+        }
+    }
+
+    ///
+    /// Returns true if the given axis aligned bounding box is completely behind the depth values in this depth pyramid.
+    ///
+    pub fn is_occluded(&self, aabb: &AxisAlignedBoundingBox) -> bool {
+        if aabb.is_empty() {
+            return false;
+        }
+        let (min, max) = (aabb.min(), aabb.max());
+        let mut screen_min = vec3(f32::MAX, f32::MAX, f32::MAX);
+        let mut screen_max = vec3(f32::MIN, f32::MIN, f32::MIN);
+        for i in 0..8 {
+            let corner = vec3(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            );
+            let p = self.view_projection * corner.extend(1.0);
+            if p.w <= 0.0 {
+                // The box intersects the near plane
+                return false;
+            }
+            let p = p.truncate() / p.w * 0.5 + vec3(0.5, 0.5, 0.5);
+            screen_min = vec3(
+                screen_min.x.min(p.x),
+                screen_min.y.min(p.y),
+                screen_min.z.min(p.z),
+            );
+            screen_max = vec3(
+                screen_max.x.max(p.x),
+                screen_max.y.max(p.y),
+                screen_max.z.max(p.z),
+            );
+        }
+        let (width, height, _) = self.levels[0];
+        let x0 = (screen_min.x.max(0.0) * width as f32) as usize;
+        let y0 = (screen_min.y.max(0.0) * height as f32) as usize;
+        let x1 = ((screen_max.x.min(1.0) * width as f32) as usize).min(width - 1);
+        let y1 = ((screen_max.y.min(1.0) * height as f32) as usize).min(height - 1);
+        if x0 > x1 || y0 > y1 {
+            return false;
+        }
+
+        // Find the level where the box covers at most 2x2 pixels
+        let size = (x1 - x0).max(y1 - y0).max(1);
+        let level = ((usize::BITS - size.leading_zeros()) as usize).min(self.levels.len() - 1);
+        let (w, h, data) = &self.levels[level];
+        let mut max_depth: f32 = 0.0;
+        for y in (y0 >> level)..=(y1 >> level).min(h - 1) {
+            for x in (x0 >> level)..=(x1 >> level).min(w - 1) {
+                max_depth = max_depth.max(data[y * w + x]);
+            }
+        }
+        screen_min.z > max_depth
+    }
+}
+
+///
+/// Returns the indices of the instances up to the given instance count which are not culled and, if a level of detail is given, use that level of detail.
+///
+pub(super) fn visible_instances(
+    culling: &InstanceCulling,
+    lod_level: Option<&LodLevel>,
+    camera: &Camera,
+    aabb_local: &AxisAlignedBoundingBox,
+    transformation: &Mat4,
+    instance_transformations: &[Mat4],
+) -> Vec<usize> {
+    let is_visible = |i: usize| {
+        let mut aabb = *aabb_local;
+        aabb.transform(&(*transformation * instance_transformations[i]));
+        lod_level.map(|l| l.contains(camera, &aabb)).unwrap_or(true)
+            && match culling {
+                InstanceCulling::None => true,
+                InstanceCulling::Frustum => camera.in_frustum(&aabb),
+                InstanceCulling::FrustumAndOcclusion(depth_pyramid) => {
+                    camera.in_frustum(&aabb) && !depth_pyramid.is_occluded(&aabb)
+                }
+            }
+    };
+    let instance_count = instance_transformations.len();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if instance_count >= 2 * MIN_INSTANCES_PER_THREAD {
+        let thread_count = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(instance_count / MIN_INSTANCES_PER_THREAD);
+        let chunk_size = instance_count.div_ceil(thread_count);
+        return std::thread::scope(|scope| {
+            let handles = (0..thread_count)
+                .map(|t| {
+                    let is_visible = &is_visible;
+                    scope.spawn(move || {
+                        (t * chunk_size..((t + 1) * chunk_size).min(instance_count))
+                            .filter(|i| is_visible(*i))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("instance culling thread panicked"))
+                .collect()
+        });
+    }
+    (0..instance_count).filter(|i| is_visible(*i)).collect()
+}
@@ -1,24 +1,74 @@
 use crate::core::*;
 use crate::renderer::*;
 use std::collections::HashMap;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 
+use super::instance_culling::visible_instances;
+use super::lod::LodLevel;
 use super::BaseMesh;
 
+/// The maximum number of views, for example the camera and the shadow cameras, for which the culled instance buffers are cached.
+const MAX_CACHED_VIEWS: usize = 4;
+
+///
+/// The instance buffers containing the instances visible from one view, in the order they are rendered.
+///
+struct InstanceView {
+    /// The view-projection of the view if the instances are culled.
+    view_projection: Option<Mat4>,
+    /// The position the instances are ordered back to front from, if they are ordered.
+    ordering_position: Option<Vec3>,
+    /// The indices of the visible instances.
+    indices: Vec<usize>,
+    buffers: HashMap<String, InstanceBuffer>,
+    /// Whether the instances have changed since the buffers were filled.
+    dirty: bool,
+    /// Whether the instances or the view have changed since the culling was done.
+    cull_needed: bool,
+    last_used: u64,
+    #[cfg(not(target_arch = "wasm32"))]
+    pending: Option<std::thread::JoinHandle<Vec<usize>>>,
+}
+
+impl InstanceView {
+    fn new(view_projection: Option<Mat4>) -> Self {
+        Self {
+            view_projection,
+            ordering_position: None,
+            indices: Vec::new(),
+            buffers: HashMap::new(),
+            dirty: true,
+            cull_needed: true,
+            last_used: 0,
+            #[cfg(not(target_arch = "wasm32"))]
+            pending: None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct InstanceBufferCache {
+    views: Vec<InstanceView>,
+    frame: u64,
+}
+
 ///
 /// Similar to [Mesh], except it is possible to render many instances of the same mesh efficiently.
 ///
 pub struct InstancedMesh {
     context: Context,
     base_mesh: BaseMesh,
-    instance_buffers: RwLock<(HashMap<String, InstanceBuffer>, Vec3)>,
+    instance_buffers: RwLock<InstanceBufferCache>,
     aabb: AxisAlignedBoundingBox,
     aabb_local: AxisAlignedBoundingBox,
     transformation: Mat4,
     current_transformation: Mat4,
     animation: Option<Box<dyn Fn(f32) -> Mat4 + Send + Sync>>,
     instance_count: u32,
-    instances: Instances,
+    instances: Arc<Instances>,
+    culling: InstanceCulling,
+    background_culling: bool,
+    lod_level: Option<LodLevel>,
 }
 
 impl InstancedMesh {
@@ -28,23 +78,60 @@ impl InstancedMesh {
     /// The model is rendered in as many instances as there are attributes in [Instances] given as input.
     ///
     pub fn new(context: &Context, instances: &Instances, cpu_mesh: &CpuMesh) -> Self {
+        Self::new_with_topology(context, instances, cpu_mesh, PrimitiveTopology::Triangles)
+    }
+
+    ///
+    /// Creates a new instanced mesh from the given [CpuMesh] where the vertices are assembled into primitives using the given [PrimitiveTopology].
+    /// See [Mesh::new_with_topology] for more information.
+    ///
+    pub fn new_with_topology(
+        context: &Context,
+        instances: &Instances,
+        cpu_mesh: &CpuMesh,
+        topology: PrimitiveTopology,
+    ) -> Self {
         let aabb = cpu_mesh.compute_aabb();
         let mut instanced_mesh = Self {
             context: context.clone(),
-            base_mesh: BaseMesh::new(context, cpu_mesh),
-            instance_buffers: RwLock::new((Default::default(), vec3(0.0, 0.0, 0.0))),
+            base_mesh: BaseMesh::new_with_topology(context, cpu_mesh, topology),
+            instance_buffers: RwLock::new(InstanceBufferCache::default()),
             aabb,
             aabb_local: aabb,
             transformation: Mat4::identity(),
             current_transformation: Mat4::identity(),
             animation: None,
             instance_count: 0,
-            instances: instances.clone(),
+            instances: Arc::new(instances.clone()),
+            culling: InstanceCulling::None,
+            background_culling: false,
+            lod_level: None,
         };
         instanced_mesh.set_instances(instances);
         instanced_mesh
     }
 
+    ///
+    /// Returns the [PrimitiveTopology] used when rendering this mesh, ie. whether it consists of points, lines or triangles.
+    ///
+    pub fn topology(&self) -> PrimitiveTopology {
+        self.base_mesh.topology
+    }
+
+    ///
+    /// Returns the size in pixels of the points when the topology is [PrimitiveTopology::Points].
+    ///
+    pub fn point_size(&self) -> f32 {
+        self.base_mesh.point_size
+    }
+
+    ///
+    /// Sets the size in pixels of the points when the topology is [PrimitiveTopology::Points], the default is 1 pixel.
+    ///
+    pub fn set_point_size(&mut self, point_size: f32) {
+        self.base_mesh.point_size = point_size;
+    }
+
     ///
     /// Returns the local to world transformation applied to all instances.
     ///
@@ -70,35 +157,238 @@ impl InstancedMesh {
         self.animation = Some(Box::new(animation));
     }
 
+    ///
+    /// Sets a second set of uv coordinates, one for each vertex, which is used by materials that require lightmap uv coordinates, for example the [LightmapMaterial].
+    /// Lightmap uv coordinates are usually unique for each surface and therefore different from the uv coordinates in the [CpuMesh] which are often tiled or shared.
+    ///
+    pub fn set_lightmap_uvs(&mut self, lightmap_uvs: &[Vec2]) {
+        self.base_mesh.set_lightmap_uvs(&self.context, lightmap_uvs);
+    }
+
+    ///
+    /// Updates the positions of the vertices starting at the vertex with the given offset, without reallocating the vertex data on the GPU.
+    /// This is useful for geometry which changes every frame, for example a point cloud from a live sensor or a cloth simulation.
+    /// The number of vertices cannot be changed, construct a new [InstancedMesh] for that.
+    ///
+    /// # Panic
+    /// Will panic if the positions exceed the number of vertices.
+    ///
+    pub fn update_positions(&mut self, offset: u32, positions: &[Vec3]) {
+        self.base_mesh.update_positions(offset, positions);
+        if offset == 0 && positions.len() == self.base_mesh.positions.vertex_count() as usize {
+            self.aabb_local = AxisAlignedBoundingBox::new_with_positions(positions);
+        } else {
+            // The old positions are unknown, so the bounding box can only grow
+            self.aabb_local
+                .expand_with_aabb(&AxisAlignedBoundingBox::new_with_positions(positions));
+        }
+        self.update_aabb();
+    }
+
+    ///
+    /// Updates the normals of the vertices starting at the vertex with the given offset, without reallocating the vertex data on the GPU.
+    ///
+    /// # Panic
+    /// Will panic if the mesh was created without normals or if the normals exceed the number of vertices.
+    ///
+    pub fn update_normals(&mut self, offset: u32, normals: &[Vec3]) {
+        self.base_mesh.update_normals(offset, normals);
+    }
+
+    ///
+    /// Updates the colors of the vertices starting at the vertex with the given offset, without reallocating the vertex data on the GPU.
+    ///
+    /// # Panic
+    /// Will panic if the mesh was created without colors or if the colors exceed the number of vertices.
+    ///
+    pub fn update_colors(&mut self, offset: u32, colors: &[Color]) {
+        self.base_mesh.update_colors(offset, colors);
+    }
+
+    ///
+    /// Makes this instanced mesh a skinned mesh which is deformed by the joints of the given [Skeleton].
+    /// Each vertex is influenced by up to four joints given by the indices into [Skeleton::joints] in `joint_indices` and the corresponding weights in `joint_weights`.
+    /// The weights for each vertex should sum to one and unused joint indices should have a weight of zero.
+    /// The first animation of the skeleton is chosen by default, use [Self::choose_skeleton_animation] to choose another.
+    ///
+    /// Note that the [Geometry::aabb] of the instanced mesh is computed in the bind pose and is therefore not updated when the skeleton is animated.
+    ///
+    pub fn set_skin(
+        &mut self,
+        skeleton: Skeleton,
+        joint_indices: &[[u16; 4]],
+        joint_weights: &[Vec4],
+    ) {
+        self.base_mesh
+            .set_skin(&self.context, skeleton, joint_indices, joint_weights);
+    }
+
+    ///
+    /// Returns the [Skeleton] deforming this instanced mesh if it is a skinned mesh (see [Self::set_skin]).
+    ///
+    pub fn skeleton(&self) -> Option<&Skeleton> {
+        self.base_mesh.skeleton()
+    }
+
+    ///
+    /// Specifies the animation of the [Skeleton] to use when [Geometry::animate] is called. Use the [Skeleton::animations] method to get a list of possible animations.
+    /// Does nothing if this instanced mesh is not a skinned mesh (see [Self::set_skin]).
+    ///
+    pub fn choose_skeleton_animation(&mut self, animation_name: Option<&str>) {
+        self.base_mesh.choose_skeleton_animation(animation_name);
+    }
+
+    ///
+    /// Sets the morph targets (or blend shapes) of this instanced mesh, each containing a displacement for each vertex.
+    /// The displacements of the morph targets are weighted by the morph weights (see [Self::set_morph_weights]) and added to the vertex data of the instanced mesh.
+    /// All morph weights are initially zero. Removes the morph targets if the given list is empty.
+    ///
+    pub fn set_morph_targets(&mut self, morph_targets: &[MorphTarget]) {
+        self.base_mesh
+            .set_morph_targets(&self.context, morph_targets);
+    }
+
+    ///
+    /// Returns the current morph weights, one for each morph target (see [Self::set_morph_targets]).
+    ///
+    pub fn morph_weights(&self) -> &[f32] {
+        self.base_mesh.morph_weights()
+    }
+
+    ///
+    /// Sets the morph weights, one for each morph target (see [Self::set_morph_targets]).
+    /// The weights are overwritten by the chosen morph animation, if any, when [Geometry::animate] is called.
+    ///
+    /// # Panic
+    /// Will panic if no morph targets are defined or if the number of weights does not match the number of morph targets.
+    ///
+    pub fn set_morph_weights(&mut self, weights: &[f32]) {
+        self.base_mesh.set_morph_weights(weights);
+    }
+
+    ///
+    /// Sets a set of animations of the morph weights, for example the animations of a glTF primitive.
+    /// The morph weights are given by the first [KeyFrames] in each animation which contain morph weights.
+    /// The first animation is chosen by default, use [Self::choose_morph_animation] to choose another.
+    ///
+    pub fn set_morph_animations(&mut self, animations: Vec<KeyFrameAnimation>) {
+        self.base_mesh.set_morph_animations(animations);
+    }
+
+    ///
+    /// Returns a list of names of the morph animations (see [Self::set_morph_animations]).
+    ///
+    pub fn morph_animations(&self) -> Vec<Option<String>> {
+        self.base_mesh.morph_animations()
+    }
+
+    ///
+    /// Specifies the morph animation to use when [Geometry::animate] is called. Use the [Self::morph_animations] method to get a list of possible animations.
+    ///
+    pub fn choose_morph_animation(&mut self, animation_name: Option<&str>) {
+        self.base_mesh.choose_morph_animation(animation_name);
+    }
+
+    ///
+    /// Animates the skeleton (see [Self::set_skin]) and the morph weights (see [Self::set_morph_animations]) of this instanced mesh by blending the given animation samples,
+    /// for example the samples returned by [AnimationController::samples].
+    /// This is an alternative to [Geometry::animate] which only evaluates the chosen animations.
+    ///
+    pub fn animate_blended(&mut self, samples: &[AnimationSample]) {
+        self.base_mesh.animate_blended(samples);
+    }
+
+    pub(in crate::renderer) fn set_animation_transformation(
+        &mut self,
+        animation_transformation: Mat4,
+    ) {
+        self.current_transformation = self.transformation * animation_transformation;
+    }
+
     /// Returns the number of instances that is rendered.
     pub fn instance_count(&self) -> u32 {
         self.instance_count
     }
 
     /// Use this if you only want to render instance 0 through to instance `instance_count`.
-    /// This is the same as changing the instances using `set_instances`, except that it is faster since it doesn't update any buffers.
+    /// This is the same as changing the instances using `set_instances`, except that it is faster since it only updates the buffers if culling or depth ordering is needed.
     /// `instance_count` will be set to the number of instances when they are defined by `set_instances`, so all instanced are rendered by default.
     pub fn set_instance_count(&mut self, instance_count: u32) {
         self.instance_count = instance_count.min(self.instances.transformations.len() as u32);
         self.update_aabb();
+        for view in self
+            .instance_buffers
+            .get_mut()
+            .expect("failed acquiring write accesss")
+            .views
+            .iter_mut()
+        {
+            view.cull_needed = true;
+        }
+    }
+
+    ///
+    /// Returns the culling applied to the instances, see [Self::set_culling].
+    ///
+    pub fn culling(&self) -> &InstanceCulling {
+        &self.culling
+    }
+
+    ///
+    /// Specifies which instances are culled, ie. not rendered, when this instanced mesh is rendered.
+    /// When culling is enabled, the instance buffers only contain the visible instances and are updated when the instanced mesh is rendered with another camera
+    /// and the set of visible instances has changed, so culling is beneficial when most instances are outside the view and rarely when all instances are visible.
+    /// The instance buffers are cached for a few views, so rendering shadow maps using the shadow cameras does not invalidate the instance buffers for the camera.
+    ///
+    pub fn set_culling(&mut self, culling: InstanceCulling) {
+        self.culling = culling;
+        self.invalidate_instance_buffers();
+    }
+
+    ///
+    /// Specifies whether the culling (see [Self::set_culling]) is done on a background thread.
+    /// If enabled, rendering with a new camera does not wait for the culling to finish but uses the instances visible from the previous camera until the culling is done,
+    /// so instances entering the view might appear a few frames late. Not supported on web, where the culling is always done when rendering.
+    ///
+    pub fn set_background_culling(&mut self, background_culling: bool) {
+        self.background_culling = background_culling;
     }
 
     ///
     /// Update the instances.
+    /// The instance buffers on the GPU are reused if they exist, so this can be called every frame, for example to stream the instances of a simulation.
     ///
     pub fn set_instances(&mut self, instances: &Instances) {
         #[cfg(debug_assertions)]
         instances.validate().expect("invalid instances");
         self.instance_count = instances.count();
-        self.instances = instances.clone();
+        self.instances = Arc::new(instances.clone());
         self.update_aabb();
+        self.invalidate_instance_buffers();
+    }
+
+    ///
+    /// Only renders the instances which use the given level of detail, see [InstancedLodMesh].
+    ///
+    pub(super) fn set_lod_level(&mut self, lod_level: LodLevel) {
+        self.lod_level = Some(lod_level);
+        self.invalidate_instance_buffers();
+    }
+
+    fn is_culled(&self) -> bool {
+        !matches!(self.culling, InstanceCulling::None) || self.lod_level.is_some()
+    }
 
+    fn invalidate_instance_buffers(&mut self) {
+        for view in self
+            .instance_buffers
+            .get_mut()
+            .expect("failed acquiring write accesss")
+            .views
+            .iter_mut()
         {
-            let mut s = self
-                .instance_buffers
-                .write()
-                .expect("failed acquiring write accesss");
-            s.0.clear();
+            view.dirty = true;
+            view.cull_needed = true;
         }
     }
 
@@ -112,50 +402,146 @@ impl InstancedMesh {
         self.aabb = aabb;
     }
 
-    /// Update the instance buffers, if depth_ordering_pose is populated depth ordering is performed
-    /// using this position.
-    fn update_instance_buffers(&self, depth_ordering_pose: Option<Vec3>) {
-        let needs_update = {
-            let s = self
-                .instance_buffers
-                .read()
-                .expect("failed acquiring read accesss");
-
-            // Check if we need a reorder, this only applies to transparent materials.
-            let reorder_needed = if let Some(ref ordering_pose) = depth_ordering_pose {
-                let camera_changed = *ordering_pose != s.1;
-                let instance_count_changed = if let Some(v) = s.0.values().next() {
-                    v.instance_count() != self.instance_count
-                } else {
-                    false
-                };
-
-                camera_changed || instance_count_changed
-            } else {
-                false
-            };
+    ///
+    /// Update the instance buffers for the view of the given camera and returns the index of the view in the cache.
+    /// If depth_ordering_pose is populated depth ordering is performed using this position.
+    /// If culling is enabled, only the instances visible from the camera are added to the buffers.
+    ///
+    fn update_instance_buffers(&self, depth_ordering_pose: Option<Vec3>, camera: &Camera) -> usize {
+        let view_projection = self
+            .is_culled()
+            .then(|| camera.projection() * camera.view());
+        let mut cache = self
+            .instance_buffers
+            .write()
+            .expect("failed acquiring mutable access");
+        cache.frame += 1;
+        let frame = cache.frame;
+        let index = if let Some(index) = cache
+            .views
+            .iter()
+            .position(|v| v.view_projection == view_projection)
+        {
+            index
+        } else if cache.views.len() < MAX_CACHED_VIEWS {
+            cache.views.push(InstanceView::new(view_projection));
+            cache.views.len() - 1
+        } else {
+            // Reuse the least recently used view
+            let index = (0..cache.views.len())
+                .min_by_key(|i| cache.views[*i].last_used)
+                .unwrap();
+            cache.views[index].view_projection = view_projection;
+            cache.views[index].cull_needed = true;
+            index
+        };
+        let view = &mut cache.views[index];
+        view.last_used = frame;
 
-            // Update is always needed if the instance buffers is empty; Opaque materials only.
-            // Or, for transparent materials, if the camera moved or if the instance count changed.
-            s.0.is_empty() || reorder_needed
+        let instance_count = self.instance_count as usize;
+        let indices = if self.is_culled() {
+            self.culled_indices(view, camera)
+        } else {
+            (view.dirty
+                || view.indices.len() < instance_count
+                || (depth_ordering_pose.is_some() && view.indices.len() != instance_count))
+                .then(|| (0..instance_count).collect())
         };
+        if let Some(indices) = indices {
+            // Only refill the buffers if the set of visible instances or the instances have changed
+            if view.dirty || indices != view.indices {
+                view.indices = indices;
+                view.buffers = self.create_instance_buffers(
+                    depth_ordering_pose,
+                    &view.indices,
+                    std::mem::take(&mut view.buffers),
+                );
+                view.ordering_position = depth_ordering_pose;
+            }
+            view.dirty = false;
+        }
+        if depth_ordering_pose.is_some() && depth_ordering_pose != view.ordering_position {
+            view.buffers = self.create_instance_buffers(
+                depth_ordering_pose,
+                &view.indices,
+                std::mem::take(&mut view.buffers),
+            );
+            view.ordering_position = depth_ordering_pose;
+        }
+        index
+    }
 
-        if needs_update {
-            let mut s = self
-                .instance_buffers
-                .write()
-                .expect("failed acquiring mutable access");
-            s.0 = self.create_instance_buffers(depth_ordering_pose);
-            if let Some(ordering_pose) = depth_ordering_pose {
-                s.1 = ordering_pose;
+    ///
+    /// Returns the indices of the instances visible from the camera if they need to be updated.
+    /// With background culling, the culling is started on a background thread and the result is returned by a later call when the culling is finished.
+    ///
+    fn culled_indices(&self, view: &mut InstanceView, camera: &Camera) -> Option<Vec<usize>> {
+        let instance_count = self.instance_count as usize;
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.background_culling && (!view.buffers.is_empty() || view.pending.is_some()) {
+            let mut result = None;
+            if view.pending.as_ref().map(|p| p.is_finished()) == Some(true) {
+                let indices = view
+                    .pending
+                    .take()
+                    .unwrap()
+                    .join()
+                    .expect("instance culling thread panicked");
+                // The instances might have changed since the culling was started
+                result = Some(
+                    indices
+                        .into_iter()
+                        .filter(|i| *i < instance_count)
+                        .collect(),
+                );
+            }
+            if view.cull_needed && view.pending.is_none() {
+                view.cull_needed = false;
+                let culling = self.culling.clone();
+                let lod_level = self.lod_level.clone();
+                let camera = camera.clone();
+                let aabb_local = self.aabb_local;
+                let transformation = self.current_transformation;
+                let instances = self.instances.clone();
+                view.pending = Some(std::thread::spawn(move || {
+                    visible_instances(
+                        &culling,
+                        lod_level.as_ref(),
+                        &camera,
+                        &aabb_local,
+                        &transformation,
+                        &instances.transformations[..instance_count],
+                    )
+                }));
             }
+            if result.is_none() && view.dirty {
+                // Refill the buffers with the changed instances while waiting for the culling
+                result = Some(
+                    view.indices
+                        .iter()
+                        .cloned()
+                        .filter(|i| *i < instance_count)
+                        .collect(),
+                );
+            }
+            return result;
         }
+        view.cull_needed.then(|| {
+            view.cull_needed = false;
+            visible_instances(
+                &self.culling,
+                self.lod_level.as_ref(),
+                camera,
+                &self.aabb_local,
+                &self.current_transformation,
+                &self.instances.transformations[..instance_count],
+            )
+        })
     }
 
-    /// Sort function to order the indices up to instance count by their distance, back to front.
-    fn ordered_indices_back_to_front(instance_count: usize, distances: &[f32]) -> Vec<usize> {
+    /// Sort function to order the given indices by their distance, back to front.
+    fn order_back_to_front(mut indices: Vec<usize>, distances: &[f32]) -> Vec<usize> {
         // Then, we can sort the indices based on those distances.
-        let mut indices = (0..instance_count).collect::<Vec<usize>>();
         indices.sort_by(|a, b| {
             distances[*b]
                 .partial_cmp(&distances[*a])
@@ -165,11 +551,14 @@ impl InstancedMesh {
     }
 
     ///
-    /// This function creates the instance buffers, ordering them by distance to the camera
+    /// This function creates the instance buffers containing the instances with the given indices, ordering them by distance to the camera.
+    /// The old instance buffers are refilled instead of allocating new buffers when possible.
     ///
     fn create_instance_buffers(
         &self,
         depth_ordering: Option<Vec3>,
+        visible_indices: &[usize],
+        mut old_instance_buffers: HashMap<String, InstanceBuffer>,
     ) -> HashMap<String, InstanceBuffer> {
         let indices = if let Some(position) = depth_ordering {
             // Need to order by using the position.
@@ -179,10 +568,10 @@ impl InstancedMesh {
                 .iter()
                 .map(|m| (self.transformation * m).w.truncate().distance2(position))
                 .collect::<Vec<_>>();
-            Self::ordered_indices_back_to_front(self.instance_count as usize, &distances)
+            Self::order_back_to_front(visible_indices.to_vec(), &distances)
         } else {
             // No need to order, just return the indices as is.
-            (0..self.instances.transformations.len()).collect::<Vec<usize>>()
+            visible_indices.to_vec()
         };
 
         // Next, we can compute the instance buffers with that ordering.
@@ -195,8 +584,9 @@ impl InstancedMesh {
         {
             instance_buffers.insert(
                 "instance_translation".to_string(),
-                InstanceBuffer::new_with_data(
-                    &self.context,
+                self.reuse_instance_buffer(
+                    &mut old_instance_buffers,
+                    "instance_translation",
                     &indices
                         .iter()
                         .map(|i| self.instances.transformations[*i])
@@ -216,15 +606,15 @@ impl InstancedMesh {
 
             instance_buffers.insert(
                 "row1".to_string(),
-                InstanceBuffer::new_with_data(&self.context, &row1),
+                self.reuse_instance_buffer(&mut old_instance_buffers, "row1", &row1),
             );
             instance_buffers.insert(
                 "row2".to_string(),
-                InstanceBuffer::new_with_data(&self.context, &row2),
+                self.reuse_instance_buffer(&mut old_instance_buffers, "row2", &row2),
             );
             instance_buffers.insert(
                 "row3".to_string(),
-                InstanceBuffer::new_with_data(&self.context, &row3),
+                self.reuse_instance_buffer(&mut old_instance_buffers, "row3", &row3),
             );
         }
 
@@ -245,11 +635,19 @@ impl InstancedMesh {
             }
             instance_buffers.insert(
                 "tex_transform_row1".to_string(),
-                InstanceBuffer::new_with_data(&self.context, &instance_tex_transform1),
+                self.reuse_instance_buffer(
+                    &mut old_instance_buffers,
+                    "tex_transform_row1",
+                    &instance_tex_transform1,
+                ),
             );
             instance_buffers.insert(
                 "tex_transform_row2".to_string(),
-                InstanceBuffer::new_with_data(&self.context, &instance_tex_transform2),
+                self.reuse_instance_buffer(
+                    &mut old_instance_buffers,
+                    "tex_transform_row2",
+                    &instance_tex_transform2,
+                ),
             );
         }
         if let Some(instance_colors) = &self.instances.colors {
@@ -260,12 +658,32 @@ impl InstancedMesh {
                 .collect::<Vec<Color>>();
             instance_buffers.insert(
                 "instance_color".to_string(),
-                InstanceBuffer::new_with_data(&self.context, &ordered_instance_colors),
+                self.reuse_instance_buffer(
+                    &mut old_instance_buffers,
+                    "instance_color",
+                    &ordered_instance_colors,
+                ),
             );
         }
         instance_buffers
     }
 
+    fn reuse_instance_buffer<T: BufferDataType>(
+        &self,
+        old_instance_buffers: &mut HashMap<String, InstanceBuffer>,
+        name: &str,
+        data: &[T],
+    ) -> InstanceBuffer {
+        if let Some(mut instance_buffer) = old_instance_buffers.remove(name) {
+            // Orphan the old data instead of waiting for the GPU to finish using it
+            instance_buffer.set_usage(BufferUsage::Stream);
+            instance_buffer.fill(data);
+            instance_buffer
+        } else {
+            InstanceBuffer::new_with_data(&self.context, data)
+        }
+    }
+
     fn draw(
         &self,
         program: &Program,
@@ -273,7 +691,11 @@ impl InstancedMesh {
         camera: &Camera,
         attributes: FragmentAttributes,
         instance_buffers: &HashMap<String, InstanceBuffer>,
+        instance_count: u32,
     ) {
+        if instance_count == 0 {
+            return;
+        }
         if attributes.normal && instance_buffers.contains_key("instance_translation") {
             if let Some(inverse) = self.current_transformation.invert() {
                 program.use_uniform("normalMatrix", inverse.transpose());
@@ -282,7 +704,7 @@ impl InstancedMesh {
                 return;
             }
         }
-        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_camera_block(camera);
         program.use_uniform("modelMatrix", self.current_transformation);
 
         for attribute_name in [
@@ -302,13 +724,8 @@ impl InstancedMesh {
                 );
             }
         }
-        self.base_mesh.draw_instanced(
-            program,
-            render_states,
-            camera,
-            attributes,
-            self.instance_count,
-        );
+        self.base_mesh
+            .draw_instanced(program, render_states, camera, attributes, instance_count);
     }
 
     fn vertex_shader_source(
@@ -317,7 +734,7 @@ impl InstancedMesh {
         instance_buffers: &HashMap<String, InstanceBuffer>,
     ) -> String {
         format!(
-            "{}{}{}{}{}{}{}{}",
+            "{}{}{}{}{}{}{}{}{}{}{}{}",
             if instance_buffers.contains_key("instance_translation") {
                 "#define USE_INSTANCE_TRANSLATIONS\n"
             } else {
@@ -338,6 +755,11 @@ impl InstancedMesh {
             } else {
                 ""
             },
+            if required_attributes.lightmap_uv {
+                "#define USE_LIGHTMAP_UVS\n"
+            } else {
+                ""
+            },
             if instance_buffers.contains_key("instance_color") && self.base_mesh.colors.is_some() {
                 "#define USE_VERTEX_COLORS\n#define USE_INSTANCE_COLORS\n"
             } else if instance_buffers.contains_key("instance_color") {
@@ -352,6 +774,9 @@ impl InstancedMesh {
             } else {
                 ""
             },
+            self.base_mesh.points_shader_source(),
+            self.base_mesh.skinning_shader_source(),
+            self.base_mesh.morph_targets_shader_source(),
             include_str!("../../core/shared.frag"),
             include_str!("shaders/mesh.vert"),
         )
@@ -378,6 +803,8 @@ impl Geometry for InstancedMesh {
         if let Some(animation) = &self.animation {
             self.current_transformation = self.transformation * animation(time);
         }
+        self.base_mesh.animate_skin(time);
+        self.base_mesh.animate_morph_targets(time);
     }
 
     fn render_with_material(
@@ -393,12 +820,18 @@ impl Geometry for InstancedMesh {
             None
         };
 
-        self.update_instance_buffers(update_pose);
-        let instance_buffers = &self
+        let view = self.update_instance_buffers(update_pose, camera);
+        let cache = self
             .instance_buffers
             .read()
-            .expect("failed to acquire read access")
-            .0;
+            .expect("failed to acquire read access");
+        let instance_buffers = &cache.views[view].buffers;
+        let instance_count = if self.is_culled() {
+            cache.views[view].indices.len() as u32
+        } else {
+            self.instance_count
+                .min(cache.views[view].indices.len() as u32)
+        };
 
         let fragment_shader = material.fragment_shader(lights);
         let vertex_shader_source =
@@ -412,6 +845,7 @@ impl Geometry for InstancedMesh {
                     camera,
                     fragment_shader.attributes,
                     instance_buffers,
+                    instance_count,
                 );
             })
             .expect("Failed compiling shader");
@@ -432,12 +866,18 @@ impl Geometry for InstancedMesh {
             None
         };
 
-        self.update_instance_buffers(update_pose);
-        let instance_buffers = &self
+        let view = self.update_instance_buffers(update_pose, camera);
+        let cache = self
             .instance_buffers
             .read()
-            .expect("failed to acquire read access")
-            .0;
+            .expect("failed to acquire read access");
+        let instance_buffers = &cache.views[view].buffers;
+        let instance_count = if self.is_culled() {
+            cache.views[view].indices.len() as u32
+        } else {
+            self.instance_count
+                .min(cache.views[view].indices.len() as u32)
+        };
 
         let fragment_shader = material.fragment_shader(lights, color_texture, depth_texture);
         let vertex_shader_source =
@@ -451,6 +891,7 @@ impl Geometry for InstancedMesh {
                     camera,
                     fragment_shader.attributes,
                     instance_buffers,
+                    instance_count,
                 );
             })
             .expect("Failed compiling shader");
@@ -530,7 +971,7 @@ mod test {
     #[test]
     fn test_ordered_indices_back_to_front() {
         let distances = vec![10.0, 5.0, 5.1, 3.0, 1.0];
-        let res = InstancedMesh::ordered_indices_back_to_front(distances.len(), &distances);
+        let res = InstancedMesh::order_back_to_front((0..distances.len()).collect(), &distances);
         assert_eq!(res, vec![0, 2, 1, 3, 4]);
     }
 }
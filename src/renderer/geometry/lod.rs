@@ -0,0 +1,630 @@
+use crate::core::*;
+use crate::renderer::*;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use three_d_asset::ProjectionType;
+
+///
+/// Simplifies the given indexed triangle mesh to approximately the given number of triangles using quadric error metrics.
+/// Vertices are merged by collapsing edges into one of the edge vertices, so the vertex attributes (normals, uv coordinates etc.) of the remaining vertices are unchanged.
+/// Borders, including seams where vertices are split because of differing attributes, are preserved as far as possible.
+/// A mesh without indices is returned unchanged.
+///
+pub fn simplify(cpu_mesh: &CpuMesh, target_triangle_count: usize) -> CpuMesh {
+    let indices: Vec<usize> = match &cpu_mesh.indices {
+        Indices::U8(ind) => ind.iter().map(|i| *i as usize).collect(),
+        Indices::U16(ind) => ind.iter().map(|i| *i as usize).collect(),
+        Indices::U32(ind) => ind.iter().map(|i| *i as usize).collect(),
+        Indices::None => return cpu_mesh.clone(),
+    };
+    let positions = cpu_mesh.positions.to_f32();
+    let mut triangles = indices
+        .chunks_exact(3)
+        .map(|t| [t[0], t[1], t[2]])
+        .collect::<Vec<_>>();
+    let mut alive = vec![true; triangles.len()];
+    let mut triangle_count = triangles.len();
+    let mut vertex_triangles = vec![Vec::new(); positions.len()];
+    for (i, t) in triangles.iter().enumerate() {
+        for v in t {
+            vertex_triangles[*v].push(i);
+        }
+    }
+
+    // Compute the quadrics
+    let mut quadrics = vec![Quadric::default(); positions.len()];
+    let mut edge_count: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+    for (i, t) in triangles.iter().enumerate() {
+        let (p0, p1, p2) = (positions[t[0]], positions[t[1]], positions[t[2]]);
+        let cross = (p1 - p0).cross(p2 - p0);
+        let area = cross.magnitude();
+        if area > 0.0 {
+            let q = Quadric::from_plane(cross / area, p0, area);
+            for v in t {
+                quadrics[*v].add(&q);
+            }
+        }
+        for k in 0..3 {
+            let (a, b) = (t[k], t[(k + 1) % 3]);
+            edge_count.entry((a.min(b), a.max(b))).or_insert((0, i)).0 += 1;
+        }
+    }
+    for ((a, b), (count, triangle)) in edge_count.iter() {
+        if *count == 1 {
+            // Border edge, add a plane perpendicular to the triangle through the edge to preserve the border
+            let t = triangles[*triangle];
+            let normal = (positions[t[1]] - positions[t[0]])
+                .cross(positions[t[2]] - positions[t[0]])
+                .normalize();
+            let edge = positions[*b] - positions[*a];
+            let plane_normal = edge.cross(normal);
+            if plane_normal.magnitude2() > 0.0 {
+                let q = Quadric::from_plane(
+                    plane_normal.normalize(),
+                    positions[*a],
+                    1000.0 * edge.magnitude2(),
+                );
+                quadrics[*a].add(&q);
+                quadrics[*b].add(&q);
+            }
+        }
+    }
+
+    // Collapse edges in the order of increasing error
+    let mut versions = vec![0u32; positions.len()];
+    let mut heap = BinaryHeap::new();
+    let push_collapses = |heap: &mut BinaryHeap<Collapse>,
+                          quadrics: &[Quadric],
+                          versions: &[u32],
+                          triangles: &[[usize; 3]],
+                          vertex_triangles: &[usize],
+                          alive: &[bool],
+                          v: usize| {
+        for t in vertex_triangles.iter().filter(|t| alive[**t]) {
+            for w in triangles[*t] {
+                if w != v {
+                    for (from, to) in [(v, w), (w, v)] {
+                        let mut q = quadrics[from].clone();
+                        q.add(&quadrics[to]);
+                        heap.push(Collapse {
+                            cost: q.error(positions[to]),
+                            from,
+                            to,
+                            from_version: versions[from],
+                            to_version: versions[to],
+                        });
+                    }
+                }
+            }
+        }
+    };
+    for (v, triangles_of_vertex) in vertex_triangles.iter().enumerate() {
+        push_collapses(
+            &mut heap,
+            &quadrics,
+            &versions,
+            &triangles,
+            triangles_of_vertex,
+            &alive,
+            v,
+        );
+    }
+
+    while triangle_count > target_triangle_count {
+        let collapse = if let Some(collapse) = heap.pop() {
+            collapse
+        } else {
+            break;
+        };
+        let (from, to) = (collapse.from, collapse.to);
+        if versions[from] != collapse.from_version || versions[to] != collapse.to_version {
+            continue;
+        }
+
+        // Reject the collapse if it flips any of the remaining triangles
+        let flips = vertex_triangles[from]
+            .iter()
+            .filter(|t| alive[**t] && !triangles[**t].contains(&to))
+            .any(|t| {
+                let tri = triangles[*t];
+                let p = |v: usize| positions[if v == from { to } else { v }];
+                let old_normal = (positions[tri[1]] - positions[tri[0]])
+                    .cross(positions[tri[2]] - positions[tri[0]]);
+                let new_normal = (p(tri[1]) - p(tri[0])).cross(p(tri[2]) - p(tri[0]));
+                old_normal.dot(new_normal) <= 0.0
+            });
+        if flips {
+            continue;
+        }
+
+        for t in std::mem::take(&mut vertex_triangles[from]) {
+            if !alive[t] {
+                continue;
+            }
+            if triangles[t].contains(&to) {
+                alive[t] = false;
+                triangle_count -= 1;
+            } else {
+                for v in triangles[t].iter_mut() {
+                    if *v == from {
+                        *v = to;
+                    }
+                }
+                vertex_triangles[to].push(t);
+            }
+        }
+        let q = quadrics[from].clone();
+        quadrics[to].add(&q);
+        versions[from] += 1;
+        versions[to] += 1;
+        vertex_triangles[to].retain(|t| alive[*t]);
+        push_collapses(
+            &mut heap,
+            &quadrics,
+            &versions,
+            &triangles,
+            &vertex_triangles[to],
+            &alive,
+            to,
+        );
+    }
+
+    // Construct the simplified mesh from the remaining triangles and vertices
+    let mut vertex_map = vec![None; positions.len()];
+    let mut kept_vertices = Vec::new();
+    let mut new_indices = Vec::with_capacity(triangle_count * 3);
+    for (t, _) in triangles.iter().zip(alive.iter()).filter(|(_, a)| **a) {
+        for v in t {
+            let index = *vertex_map[*v].get_or_insert_with(|| {
+                kept_vertices.push(*v);
+                kept_vertices.len() - 1
+            });
+            new_indices.push(index as u32);
+        }
+    }
+    let mut mesh = cpu_mesh.clone();
+    mesh.positions = Positions::F32(kept_vertices.iter().map(|v| positions[*v]).collect());
+    mesh.indices = Indices::U32(new_indices);
+    mesh.normals = cpu_mesh
+        .normals
+        .as_ref()
+        .map(|data| kept_vertices.iter().map(|v| data[*v]).collect());
+    mesh.tangents = cpu_mesh
+        .tangents
+        .as_ref()
+        .map(|data| kept_vertices.iter().map(|v| data[*v]).collect());
+    mesh.uvs = cpu_mesh
+        .uvs
+        .as_ref()
+        .map(|data| kept_vertices.iter().map(|v| data[*v]).collect());
+    mesh.colors = cpu_mesh
+        .colors
+        .as_ref()
+        .map(|data| kept_vertices.iter().map(|v| data[*v]).collect());
+    mesh
+}
+
+///
+/// Generates the given number of levels of detail of the given mesh using [simplify], where the first level is the given mesh
+/// and each of the following levels has approximately half the number of triangles of the previous level.
+///
+pub fn generate_lods(cpu_mesh: &CpuMesh, level_count: usize) -> Vec<CpuMesh> {
+    let mut levels = vec![cpu_mesh.clone()];
+    while levels.len() < level_count {
+        let previous = levels.last().unwrap();
+        let triangle_count = triangle_count(previous);
+        levels.push(simplify(previous, triangle_count / 2));
+    }
+    levels
+}
+
+fn triangle_count(cpu_mesh: &CpuMesh) -> usize {
+    match &cpu_mesh.indices {
+        Indices::U8(ind) => ind.len() / 3,
+        Indices::U16(ind) => ind.len() / 3,
+        Indices::U32(ind) => ind.len() / 3,
+        Indices::None => cpu_mesh.positions.to_f32().len() / 3,
+    }
+}
+
+#[derive(Clone, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn from_plane(normal: Vec3, point: Vec3, weight: f32) -> Self {
+        let (a, b, c) = (normal.x as f64, normal.y as f64, normal.z as f64);
+        let d = -normal.dot(point) as f64;
+        let w = weight as f64;
+        Self([
+            w * a * a,
+            w * a * b,
+            w * a * c,
+            w * a * d,
+            w * b * b,
+            w * b * c,
+            w * b * d,
+            w * c * c,
+            w * c * d,
+            w * d * d,
+        ])
+    }
+
+    fn add(&mut self, other: &Self) {
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a += b;
+        }
+    }
+
+    fn error(&self, p: Vec3) -> f64 {
+        let (x, y, z) = (p.x as f64, p.y as f64, p.z as f64);
+        let q = &self.0;
+        q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9]
+    }
+}
+
+struct Collapse {
+    cost: f64,
+    from: usize,
+    to: usize,
+    from_version: u32,
+    to_version: u32,
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed, such that the collapse with the lowest cost is at the top of the heap
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+///
+/// Returns the size of the bounding sphere of the given bounding box projected onto the screen of the given camera
+/// relative to the height of the screen, ie. `1.0` means that the sphere covers the height of the screen.
+///
+fn screen_size(camera: &Camera, aabb: &AxisAlignedBoundingBox) -> f32 {
+    if aabb.is_empty() {
+        return 0.0;
+    }
+    let radius = 0.5 * aabb.size().magnitude();
+    match camera.projection_type() {
+        ProjectionType::Orthographic { height } => radius * 2.0 / height,
+        ProjectionType::Perspective { .. } => {
+            let projection = camera.projection();
+            let w = (projection * camera.view() * aabb.center().extend(1.0)).w;
+            if w <= radius {
+                // The camera is inside or close to the bounding sphere
+                f32::MAX
+            } else {
+                radius * projection.y.y / w
+            }
+        }
+    }
+}
+
+///
+/// Specifies that only the instances of an [InstancedMesh] using the given level of detail are rendered, see [InstancedLodMesh].
+///
+#[derive(Clone)]
+pub(super) struct LodLevel {
+    screen_sizes: Vec<f32>,
+    level: usize,
+}
+
+impl LodLevel {
+    pub fn contains(&self, camera: &Camera, aabb: &AxisAlignedBoundingBox) -> bool {
+        level_index(&self.screen_sizes, screen_size(camera, aabb)) == self.level
+    }
+}
+
+///
+/// Returns the index of the level of detail to use for the given screen size (see [LodMesh::set_screen_sizes]).
+///
+fn level_index(screen_sizes: &[f32], screen_size: f32) -> usize {
+    screen_sizes
+        .iter()
+        .position(|s| screen_size >= *s)
+        .unwrap_or(screen_sizes.len().saturating_sub(1))
+}
+
+fn default_screen_sizes(level_count: usize) -> Vec<f32> {
+    (0..level_count)
+        .map(|i| 0.5 / (1 << (2 * i)) as f32)
+        .collect()
+}
+
+///
+/// A triangle mesh [Geometry] with several levels of detail, for example generated by [generate_lods].
+/// The level of detail is chosen each time the mesh is rendered based on the projected size of the mesh on the screen of the camera.
+///
+pub struct LodMesh {
+    levels: Vec<Mesh>,
+    screen_sizes: Vec<f32>,
+}
+
+impl LodMesh {
+    ///
+    /// Creates a new mesh with the given levels of detail, ordered from the most detailed to the least detailed.
+    ///
+    pub fn new(context: &Context, cpu_meshes: &[CpuMesh]) -> Self {
+        if cpu_meshes.is_empty() {
+            panic!("a lod mesh needs at least one level of detail");
+        }
+        Self {
+            levels: cpu_meshes.iter().map(|m| Mesh::new(context, m)).collect(),
+            screen_sizes: default_screen_sizes(cpu_meshes.len()),
+        }
+    }
+
+    ///
+    /// Returns the levels of detail.
+    ///
+    pub fn levels(&self) -> &[Mesh] {
+        &self.levels
+    }
+
+    ///
+    /// Returns the levels of detail mutably, for example to set lightmap uv coordinates on each level.
+    ///
+    pub fn levels_mut(&mut self) -> &mut [Mesh] {
+        &mut self.levels
+    }
+
+    ///
+    /// Sets the minimum screen size for each level of detail, where the screen size is the projected diameter of the bounding sphere relative to the height of the screen.
+    /// The first level where the screen size of the mesh is above the minimum is rendered, and the last level is rendered if the mesh is smaller than all minimums.
+    /// The default is `0.5` for the first level and a quarter of the previous for each of the following levels.
+    ///
+    pub fn set_screen_sizes(&mut self, screen_sizes: &[f32]) {
+        if screen_sizes.len() != self.levels.len() {
+            panic!(
+                "{} screen sizes are given but there are {} levels of detail",
+                screen_sizes.len(),
+                self.levels.len()
+            );
+        }
+        self.screen_sizes = screen_sizes.to_vec();
+    }
+
+    ///
+    /// Returns the local to world transformation applied to this mesh.
+    ///
+    pub fn transformation(&self) -> Mat4 {
+        self.levels[0].transformation()
+    }
+
+    ///
+    /// Set the local to world transformation applied to this mesh.
+    ///
+    pub fn set_transformation(&mut self, transformation: Mat4) {
+        for level in self.levels.iter_mut() {
+            level.set_transformation(transformation);
+        }
+    }
+
+    ///
+    /// Returns the level of detail used when rendering with the given camera.
+    ///
+    pub fn level(&self, camera: &Camera) -> &Mesh {
+        &self.levels[level_index(&self.screen_sizes, screen_size(camera, &self.aabb()))]
+    }
+}
+
+impl<'a> IntoIterator for &'a LodMesh {
+    type Item = &'a dyn Geometry;
+    type IntoIter = std::iter::Once<&'a dyn Geometry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        std::iter::once(self)
+    }
+}
+
+impl Geometry for LodMesh {
+    fn aabb(&self) -> AxisAlignedBoundingBox {
+        self.levels[0].aabb()
+    }
+
+    fn animate(&mut self, time: f32) {
+        for level in self.levels.iter_mut() {
+            level.animate(time);
+        }
+    }
+
+    fn render_with_material(
+        &self,
+        material: &dyn Material,
+        camera: &Camera,
+        lights: &[&dyn Light],
+    ) {
+        self.level(camera)
+            .render_with_material(material, camera, lights)
+    }
+
+    fn render_with_post_material(
+        &self,
+        material: &dyn PostMaterial,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        self.level(camera).render_with_post_material(
+            material,
+            camera,
+            lights,
+            color_texture,
+            depth_texture,
+        )
+    }
+}
+
+///
+/// Similar to [LodMesh], except that it renders many instances of the mesh where the level of detail is chosen for each instance.
+/// The instances are distributed between the levels when the mesh is rendered with another camera and the instance buffers of each level
+/// are cached for a few cameras and only updated when the instances using that level change, see [InstancedMesh::set_culling].
+///
+pub struct InstancedLodMesh {
+    levels: Vec<InstancedMesh>,
+}
+
+impl InstancedLodMesh {
+    ///
+    /// Creates a new instanced mesh with the given levels of detail, ordered from the most detailed to the least detailed.
+    ///
+    pub fn new(context: &Context, instances: &Instances, cpu_meshes: &[CpuMesh]) -> Self {
+        if cpu_meshes.is_empty() {
+            panic!("a lod mesh needs at least one level of detail");
+        }
+        let mut lod_mesh = Self {
+            levels: cpu_meshes
+                .iter()
+                .map(|m| InstancedMesh::new(context, instances, m))
+                .collect(),
+        };
+        lod_mesh.set_screen_sizes(&default_screen_sizes(cpu_meshes.len()));
+        lod_mesh
+    }
+
+    ///
+    /// Sets the minimum screen size for each level of detail, see [LodMesh::set_screen_sizes].
+    ///
+    pub fn set_screen_sizes(&mut self, screen_sizes: &[f32]) {
+        if screen_sizes.len() != self.levels.len() {
+            panic!(
+                "{} screen sizes are given but there are {} levels of detail",
+                screen_sizes.len(),
+                self.levels.len()
+            );
+        }
+        for (level, mesh) in self.levels.iter_mut().enumerate() {
+            mesh.set_lod_level(LodLevel {
+                screen_sizes: screen_sizes.to_vec(),
+                level,
+            });
+        }
+    }
+
+    ///
+    /// Update the instances.
+    ///
+    pub fn set_instances(&mut self, instances: &Instances) {
+        for mesh in self.levels.iter_mut() {
+            mesh.set_instances(instances);
+        }
+    }
+
+    ///
+    /// Set the local to world transformation applied to all instances.
+    ///
+    pub fn set_transformation(&mut self, transformation: Mat4) {
+        for mesh in self.levels.iter_mut() {
+            mesh.set_transformation(transformation);
+        }
+    }
+
+    ///
+    /// Specifies which instances are culled in addition to the instances using another level of detail, see [InstancedMesh::set_culling].
+    ///
+    pub fn set_culling(&mut self, culling: InstanceCulling) {
+        for mesh in self.levels.iter_mut() {
+            mesh.set_culling(culling.clone());
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a InstancedLodMesh {
+    type Item = &'a dyn Geometry;
+    type IntoIter = std::iter::Once<&'a dyn Geometry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        std::iter::once(self)
+    }
+}
+
+impl Geometry for InstancedLodMesh {
+    fn aabb(&self) -> AxisAlignedBoundingBox {
+        self.levels[0].aabb()
+    }
+
+    fn render_with_material(
+        &self,
+        material: &dyn Material,
+        camera: &Camera,
+        lights: &[&dyn Light],
+    ) {
+        for level in self.levels.iter() {
+            level.render_with_material(material, camera, lights);
+        }
+    }
+
+    fn render_with_post_material(
+        &self,
+        material: &dyn PostMaterial,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        for level in self.levels.iter() {
+            level.render_with_post_material(material, camera, lights, color_texture, depth_texture);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn simplify_sphere() {
+        let sphere = CpuMesh::sphere(16);
+        let original_count = triangle_count(&sphere);
+        let simplified = simplify(&sphere, original_count / 4);
+        let simplified_count = triangle_count(&simplified);
+        assert!(simplified_count <= original_count / 4);
+        assert!(simplified_count > 0);
+        assert!(simplified.validate().is_ok());
+    }
+
+    #[test]
+    fn orthographic_screen_size() {
+        let camera = Camera::new_orthographic(
+            Viewport::new_at_origo(100, 100),
+            vec3(0.0, 0.0, 10.0),
+            vec3(0.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            20.0,
+            0.1,
+            100.0,
+        );
+        let aabb = AxisAlignedBoundingBox::new_with_positions(&[
+            vec3(-2.0, -2.0, -2.0),
+            vec3(2.0, 2.0, 2.0),
+        ]);
+        let size = screen_size(&camera, &aabb);
+        assert!((size - 0.5 * aabb.size().magnitude() / 10.0).abs() < 0.0001);
+        assert_eq!(level_index(&default_screen_sizes(3), size), 1);
+    }
+}
@@ -4,7 +4,7 @@ use crate::renderer::*;
 use super::BaseMesh;
 
 ///
-/// A triangle mesh [Geometry].
+/// A triangle mesh [Geometry], or a point or line mesh when constructed with [Mesh::new_with_topology].
 ///
 pub struct Mesh {
     base_mesh: BaseMesh,
@@ -21,10 +21,23 @@ impl Mesh {
     /// All data in the [CpuMesh] is transfered to the GPU, so make sure to remove all unnecessary data from the [CpuMesh] before calling this method.
     ///
     pub fn new(context: &Context, cpu_mesh: &CpuMesh) -> Self {
+        Self::new_with_topology(context, cpu_mesh, PrimitiveTopology::Triangles)
+    }
+
+    ///
+    /// Creates a new mesh from the given [CpuMesh] where the vertices are assembled into primitives using the given [PrimitiveTopology].
+    /// For example, use [PrimitiveTopology::Points] to render a point cloud or [PrimitiveTopology::Lines] to render a wireframe,
+    /// in which case the indices or positions in the [CpuMesh] define points or pairs of line end points instead of triangles.
+    ///
+    pub fn new_with_topology(
+        context: &Context,
+        cpu_mesh: &CpuMesh,
+        topology: PrimitiveTopology,
+    ) -> Self {
         let aabb = cpu_mesh.compute_aabb();
         Self {
             context: context.clone(),
-            base_mesh: BaseMesh::new(context, cpu_mesh),
+            base_mesh: BaseMesh::new_with_topology(context, cpu_mesh, topology),
             aabb,
             transformation: Mat4::identity(),
             current_transformation: Mat4::identity(),
@@ -32,6 +45,27 @@ impl Mesh {
         }
     }
 
+    ///
+    /// Returns the [PrimitiveTopology] used when rendering this mesh, ie. whether it consists of points, lines or triangles.
+    ///
+    pub fn topology(&self) -> PrimitiveTopology {
+        self.base_mesh.topology
+    }
+
+    ///
+    /// Returns the size in pixels of the points when the topology is [PrimitiveTopology::Points].
+    ///
+    pub fn point_size(&self) -> f32 {
+        self.base_mesh.point_size
+    }
+
+    ///
+    /// Sets the size in pixels of the points when the topology is [PrimitiveTopology::Points], the default is 1 pixel.
+    ///
+    pub fn set_point_size(&mut self, point_size: f32) {
+        self.base_mesh.point_size = point_size;
+    }
+
     pub(in crate::renderer) fn set_transformation_2d(&mut self, transformation: Mat3) {
         self.set_transformation(Mat4::new(
             transformation.x.x,
@@ -78,6 +112,153 @@ impl Mesh {
         self.animation = Some(Box::new(animation));
     }
 
+    ///
+    /// Sets a second set of uv coordinates, one for each vertex, which is used by materials that require lightmap uv coordinates, for example the [LightmapMaterial].
+    /// Lightmap uv coordinates are usually unique for each surface and therefore different from the uv coordinates in the [CpuMesh] which are often tiled or shared.
+    ///
+    pub fn set_lightmap_uvs(&mut self, lightmap_uvs: &[Vec2]) {
+        self.base_mesh.set_lightmap_uvs(&self.context, lightmap_uvs);
+    }
+
+    ///
+    /// Updates the positions of the vertices starting at the vertex with the given offset, without reallocating the vertex data on the GPU.
+    /// This is useful for geometry which changes every frame, for example a point cloud from a live sensor or a cloth simulation.
+    /// The number of vertices cannot be changed, construct a new [Mesh] for that.
+    ///
+    /// # Panic
+    /// Will panic if the positions exceed the number of vertices.
+    ///
+    pub fn update_positions(&mut self, offset: u32, positions: &[Vec3]) {
+        self.base_mesh.update_positions(offset, positions);
+        if offset == 0 && positions.len() == self.base_mesh.positions.vertex_count() as usize {
+            self.aabb = AxisAlignedBoundingBox::new_with_positions(positions);
+        } else {
+            // The old positions are unknown, so the bounding box can only grow
+            self.aabb
+                .expand_with_aabb(&AxisAlignedBoundingBox::new_with_positions(positions));
+        }
+    }
+
+    ///
+    /// Updates the normals of the vertices starting at the vertex with the given offset, without reallocating the vertex data on the GPU.
+    ///
+    /// # Panic
+    /// Will panic if the mesh was created without normals or if the normals exceed the number of vertices.
+    ///
+    pub fn update_normals(&mut self, offset: u32, normals: &[Vec3]) {
+        self.base_mesh.update_normals(offset, normals);
+    }
+
+    ///
+    /// Updates the colors of the vertices starting at the vertex with the given offset, without reallocating the vertex data on the GPU.
+    ///
+    /// # Panic
+    /// Will panic if the mesh was created without colors or if the colors exceed the number of vertices.
+    ///
+    pub fn update_colors(&mut self, offset: u32, colors: &[Color]) {
+        self.base_mesh.update_colors(offset, colors);
+    }
+
+    ///
+    /// Makes this mesh a skinned mesh which is deformed by the joints of the given [Skeleton].
+    /// Each vertex is influenced by up to four joints given by the indices into [Skeleton::joints] in `joint_indices` and the corresponding weights in `joint_weights`.
+    /// The weights for each vertex should sum to one and unused joint indices should have a weight of zero.
+    /// The first animation of the skeleton is chosen by default, use [Self::choose_skeleton_animation] to choose another.
+    ///
+    /// Note that the [Geometry::aabb] of the mesh is computed in the bind pose and is therefore not updated when the skeleton is animated.
+    ///
+    pub fn set_skin(
+        &mut self,
+        skeleton: Skeleton,
+        joint_indices: &[[u16; 4]],
+        joint_weights: &[Vec4],
+    ) {
+        self.base_mesh
+            .set_skin(&self.context, skeleton, joint_indices, joint_weights);
+    }
+
+    ///
+    /// Returns the [Skeleton] deforming this mesh if it is a skinned mesh (see [Self::set_skin]).
+    ///
+    pub fn skeleton(&self) -> Option<&Skeleton> {
+        self.base_mesh.skeleton()
+    }
+
+    ///
+    /// Specifies the animation of the [Skeleton] to use when [Geometry::animate] is called. Use the [Skeleton::animations] method to get a list of possible animations.
+    /// Does nothing if this mesh is not a skinned mesh (see [Self::set_skin]).
+    ///
+    pub fn choose_skeleton_animation(&mut self, animation_name: Option<&str>) {
+        self.base_mesh.choose_skeleton_animation(animation_name);
+    }
+
+    ///
+    /// Sets the morph targets (or blend shapes) of this mesh, each containing a displacement for each vertex.
+    /// The displacements of the morph targets are weighted by the morph weights (see [Self::set_morph_weights]) and added to the vertex data of the mesh.
+    /// All morph weights are initially zero. Removes the morph targets if the given list is empty.
+    ///
+    pub fn set_morph_targets(&mut self, morph_targets: &[MorphTarget]) {
+        self.base_mesh
+            .set_morph_targets(&self.context, morph_targets);
+    }
+
+    ///
+    /// Returns the current morph weights, one for each morph target (see [Self::set_morph_targets]).
+    ///
+    pub fn morph_weights(&self) -> &[f32] {
+        self.base_mesh.morph_weights()
+    }
+
+    ///
+    /// Sets the morph weights, one for each morph target (see [Self::set_morph_targets]).
+    /// The weights are overwritten by the chosen morph animation, if any, when [Geometry::animate] is called.
+    ///
+    /// # Panic
+    /// Will panic if no morph targets are defined or if the number of weights does not match the number of morph targets.
+    ///
+    pub fn set_morph_weights(&mut self, weights: &[f32]) {
+        self.base_mesh.set_morph_weights(weights);
+    }
+
+    ///
+    /// Sets a set of animations of the morph weights, for example the animations of a glTF primitive.
+    /// The morph weights are given by the first [KeyFrames] in each animation which contain morph weights.
+    /// The first animation is chosen by default, use [Self::choose_morph_animation] to choose another.
+    ///
+    pub fn set_morph_animations(&mut self, animations: Vec<KeyFrameAnimation>) {
+        self.base_mesh.set_morph_animations(animations);
+    }
+
+    ///
+    /// Returns a list of names of the morph animations (see [Self::set_morph_animations]).
+    ///
+    pub fn morph_animations(&self) -> Vec<Option<String>> {
+        self.base_mesh.morph_animations()
+    }
+
+    ///
+    /// Specifies the morph animation to use when [Geometry::animate] is called. Use the [Self::morph_animations] method to get a list of possible animations.
+    ///
+    pub fn choose_morph_animation(&mut self, animation_name: Option<&str>) {
+        self.base_mesh.choose_morph_animation(animation_name);
+    }
+
+    ///
+    /// Animates the skeleton (see [Self::set_skin]) and the morph weights (see [Self::set_morph_animations]) of this mesh by blending the given animation samples,
+    /// for example the samples returned by [AnimationController::samples].
+    /// This is an alternative to [Geometry::animate] which only evaluates the chosen animations.
+    ///
+    pub fn animate_blended(&mut self, samples: &[AnimationSample]) {
+        self.base_mesh.animate_blended(samples);
+    }
+
+    pub(in crate::renderer) fn set_animation_transformation(
+        &mut self,
+        animation_transformation: Mat4,
+    ) {
+        self.current_transformation = self.transformation * animation_transformation;
+    }
+
     fn draw(
         &self,
         program: &Program,
@@ -94,7 +275,7 @@ impl Mesh {
             }
         }
 
-        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_camera_block(camera);
         program.use_uniform("modelMatrix", self.current_transformation);
 
         self.base_mesh
@@ -103,7 +284,7 @@ impl Mesh {
 
     fn vertex_shader_source(&self, required_attributes: FragmentAttributes) -> String {
         format!(
-            "{}{}{}{}{}{}",
+            "{}{}{}{}{}{}{}{}{}{}",
             if required_attributes.normal {
                 "#define USE_NORMALS\n"
             } else {
@@ -119,11 +300,19 @@ impl Mesh {
             } else {
                 ""
             },
+            if required_attributes.lightmap_uv {
+                "#define USE_LIGHTMAP_UVS\n"
+            } else {
+                ""
+            },
             if self.base_mesh.colors.is_some() {
                 "#define USE_VERTEX_COLORS\n"
             } else {
                 ""
             },
+            self.base_mesh.points_shader_source(),
+            self.base_mesh.skinning_shader_source(),
+            self.base_mesh.morph_targets_shader_source(),
             include_str!("../../core/shared.frag"),
             include_str!("shaders/mesh.vert"),
         )
@@ -150,6 +339,8 @@ impl Geometry for Mesh {
         if let Some(animation) = &self.animation {
             self.current_transformation = self.transformation * animation(time);
         }
+        self.base_mesh.animate_skin(time);
+        self.base_mesh.animate_morph_targets(time);
     }
 
     fn render_with_material(
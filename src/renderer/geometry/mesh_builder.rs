@@ -0,0 +1,918 @@
+//!
+//! Functions for building [CpuMesh]es procedurally, for example from 2D sketches, and for combining meshes using boolean operations (constructive solid geometry).
+//! All meshes returned from these functions have normals, tangents and uv coordinates.
+//!
+
+use crate::core::*;
+use crate::renderer::*;
+
+/// The maximum angle in radians between two neighbouring edges of a contour where the normals are smoothed across the shared vertex.
+const SMOOTHING_ANGLE: f32 = std::f32::consts::PI / 6.0;
+
+/// The tolerance used when classifying points relative to planes in the boolean operations.
+const EPSILON: f32 = 0.00001;
+
+///
+/// Triangulates the polygon with the given outline and holes using ear clipping.
+/// Returns the indices of the triangles, three for each triangle, into the list of points consisting of the points of the outline followed by the points of each of the holes.
+/// The outline and holes can be given in either clockwise or counterclockwise order, but the triangles are always counterclockwise.
+///
+pub fn triangulate(outline: &[Vec2], holes: &[Vec<Vec2>]) -> Vec<u32> {
+    let mut points = outline.to_vec();
+    let mut polygon = (0..outline.len()).collect::<Vec<_>>();
+    if signed_area(outline) < 0.0 {
+        polygon.reverse();
+    }
+
+    // Connect each hole to the polygon with a bridge, starting with the hole furthest to the right
+    let mut hole_indices = Vec::new();
+    for hole in holes.iter().filter(|h| h.len() > 2) {
+        let offset = points.len();
+        points.extend_from_slice(hole);
+        let mut indices = (offset..offset + hole.len()).collect::<Vec<_>>();
+        if signed_area(hole) > 0.0 {
+            indices.reverse();
+        }
+        hole_indices.push(indices);
+    }
+    hole_indices.sort_by(|a, b| {
+        let max_x = |h: &Vec<usize>| h.iter().map(|i| points[*i].x).fold(f32::MIN, f32::max);
+        max_x(b)
+            .partial_cmp(&max_x(a))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    for h in 0..hole_indices.len() {
+        let hole = &hole_indices[h];
+        let (m, &hole_vertex) = hole
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| {
+                points[**a]
+                    .x
+                    .partial_cmp(&points[**b].x)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .unwrap();
+        let p = points[hole_vertex];
+
+        // Find the closest vertex of the polygon which is visible from the hole vertex
+        let mut candidates = (0..polygon.len()).collect::<Vec<_>>();
+        candidates.sort_by(|a, b| {
+            points[polygon[*a]]
+                .distance2(p)
+                .partial_cmp(&points[polygon[*b]].distance2(p))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let edges = polygon
+            .iter()
+            .zip(polygon.iter().cycle().skip(1))
+            .chain(
+                hole_indices[h..]
+                    .iter()
+                    .flat_map(|hole| hole.iter().zip(hole.iter().cycle().skip(1))),
+            )
+            .map(|(a, b)| (*a, *b))
+            .collect::<Vec<_>>();
+        let bridge = candidates
+            .iter()
+            .find(|c| {
+                let q = points[polygon[**c]];
+                !edges.iter().any(|(a, b)| {
+                    ![polygon[**c], hole_vertex].contains(a)
+                        && ![polygon[**c], hole_vertex].contains(b)
+                        && segments_intersect(p, q, points[*a], points[*b])
+                })
+            })
+            .cloned()
+            .unwrap_or(candidates[0]);
+
+        let mut new_polygon = polygon[..=bridge].to_vec();
+        new_polygon.extend(hole[m..].iter().chain(hole[..=m].iter()));
+        new_polygon.extend_from_slice(&polygon[bridge..]);
+        polygon = new_polygon;
+    }
+
+    // Clip ears until only a single triangle is left
+    let mut triangles = Vec::new();
+    while polygon.len() > 3 {
+        let n = polygon.len();
+        let is_ear = |i: usize| {
+            let (a, b, c) = (
+                points[polygon[(i + n - 1) % n]],
+                points[polygon[i]],
+                points[polygon[(i + 1) % n]],
+            );
+            if cross_2d(b - a, c - b) <= 0.0 {
+                return false;
+            }
+            !polygon.iter().any(|j| {
+                let q = points[*j];
+                q != a && q != b && q != c && point_in_triangle(q, a, b, c)
+            })
+        };
+        let ear = (0..n)
+            .find(|i| is_ear(*i))
+            .or_else(|| {
+                // The polygon is degenerate, clip any convex or flat vertex to make progress
+                (0..n).find(|i| {
+                    let (a, b, c) = (
+                        points[polygon[(i + n - 1) % n]],
+                        points[polygon[*i]],
+                        points[polygon[(i + 1) % n]],
+                    );
+                    cross_2d(b - a, c - b) >= 0.0
+                })
+            })
+            .unwrap_or(0);
+        triangles.extend_from_slice(&[
+            polygon[(ear + n - 1) % n] as u32,
+            polygon[ear] as u32,
+            polygon[(ear + 1) % n] as u32,
+        ]);
+        polygon.remove(ear);
+    }
+    if polygon.len() == 3 {
+        triangles.extend(polygon.iter().map(|i| *i as u32));
+    }
+    triangles
+}
+
+///
+/// Returns a flat mesh in the xy-plane, facing the positive z-direction, of the polygon with the given outline and holes (see [triangulate]).
+/// The uv coordinates map the bounding box of the outline to the unit square.
+///
+pub fn polygon(outline: &[Vec2], holes: &[Vec<Vec2>]) -> CpuMesh {
+    let points = outline
+        .iter()
+        .chain(holes.iter().flatten())
+        .cloned()
+        .collect::<Vec<_>>();
+    let (min, max) = bounds(outline);
+    let size = max - min;
+    let mut mesh = CpuMesh {
+        positions: Positions::F32(points.iter().map(|p| vec3(p.x, p.y, 0.0)).collect()),
+        indices: Indices::U32(triangulate(outline, holes)),
+        normals: Some(vec![vec3(0.0, 0.0, 1.0); points.len()]),
+        uvs: Some(
+            points
+                .iter()
+                .map(|p| {
+                    vec2(
+                        (p.x - min.x) / size.x.max(f32::EPSILON),
+                        (p.y - min.y) / size.y.max(f32::EPSILON),
+                    )
+                })
+                .collect(),
+        ),
+        ..Default::default()
+    };
+    mesh.compute_tangents();
+    mesh
+}
+
+///
+/// Extrudes the polygon with the given outline and holes from the xy-plane along the positive z-direction to the given depth.
+/// The ends are closed by the triangulated polygon.
+/// The normals of the sides are smoothed across vertices where the angle between the neighbouring edges is small,
+/// so curved outlines appear smooth while corners stay sharp.
+///
+pub fn extrude(outline: &[Vec2], holes: &[Vec<Vec2>], depth: f32) -> CpuMesh {
+    let transformations = [
+        Mat4::identity(),
+        Mat4::from_translation(vec3(0.0, 0.0, depth)),
+    ];
+    sweep_transformations(outline, holes, &transformations, true)
+}
+
+///
+/// Revolves the given profile, where the x-coordinate is the distance to the y-axis and the y-coordinate is the height, 360 degrees around the y-axis.
+/// The profile should be ordered from the bottom to the top to make the surface face outwards.
+/// The number of segments determines the number of steps around the y-axis.
+///
+pub fn lathe(profile: &[Vec2], segments: u32) -> CpuMesh {
+    let segments = segments.max(3);
+    let n = profile.len();
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let profile_normals = (0..n)
+        .map(|j| {
+            let d = profile[(j + 1).min(n - 1)] - profile[j.saturating_sub(1)];
+            vec2(d.y, -d.x).normalize()
+        })
+        .collect::<Vec<_>>();
+    let lengths = arc_lengths(profile);
+    let total_length = lengths.last().cloned().unwrap_or(0.0).max(f32::EPSILON);
+    for i in 0..=segments {
+        let angle = 2.0 * std::f32::consts::PI * i as f32 / segments as f32;
+        let (sin, cos) = angle.sin_cos();
+        for j in 0..n {
+            let (p, normal) = (profile[j], profile_normals[j]);
+            positions.push(vec3(p.x * cos, p.y, p.x * sin));
+            normals.push(vec3(normal.x * cos, normal.y, normal.x * sin));
+            uvs.push(vec2(i as f32 / segments as f32, lengths[j] / total_length));
+        }
+    }
+    let mut indices = Vec::new();
+    for i in 0..segments {
+        for j in 0..n.saturating_sub(1) as u32 {
+            let v = |i: u32, j: u32| i * n as u32 + j;
+            indices.extend_from_slice(&[
+                v(i, j),
+                v(i, j + 1),
+                v(i + 1, j),
+                v(i + 1, j),
+                v(i, j + 1),
+                v(i + 1, j + 1),
+            ]);
+        }
+    }
+    let mut mesh = CpuMesh {
+        positions: Positions::F32(positions),
+        indices: Indices::U32(indices),
+        normals: Some(normals),
+        uvs: Some(uvs),
+        ..Default::default()
+    };
+    mesh.compute_tangents();
+    mesh
+}
+
+///
+/// Sweeps the given closed 2D shape along the given path, for example to create a pipe from a circular shape.
+/// The shape is placed in the plane perpendicular to the path at each point of the path and is rotated as little as possible between the points (rotation minimizing frames).
+/// If `closed_ends` is true, the ends of the sweep are closed by the triangulated shape.
+///
+pub fn sweep(shape: &[Vec2], path: &[Vec3], closed_ends: bool) -> CpuMesh {
+    if path.len() < 2 {
+        return CpuMesh::default();
+    }
+    let n = path.len();
+    let tangents = (0..n)
+        .map(|i| (path[(i + 1).min(n - 1)] - path[i.saturating_sub(1)]).normalize())
+        .collect::<Vec<_>>();
+    let t = tangents[0];
+    let mut normal = if t.x.abs() < 0.9 {
+        vec3(1.0, 0.0, 0.0)
+    } else {
+        vec3(0.0, 1.0, 0.0)
+    };
+    let mut transformations = Vec::new();
+    for i in 0..n {
+        let t = tangents[i];
+        normal = (normal - t * normal.dot(t)).normalize();
+        let binormal = t.cross(normal);
+        transformations.push(Mat4::from_cols(
+            normal.extend(0.0),
+            binormal.extend(0.0),
+            t.extend(0.0),
+            path[i].extend(1.0),
+        ));
+    }
+    sweep_transformations(shape, &[], &transformations, closed_ends)
+}
+
+///
+/// Returns the union of the two closed meshes, ie. the volume inside either of the meshes.
+///
+pub fn union(a: &CpuMesh, b: &CpuMesh) -> CpuMesh {
+    let mut a = BspNode::new(mesh_to_polygons(a));
+    let mut b = BspNode::new(mesh_to_polygons(b));
+    a.clip_to(&b);
+    b.clip_to(&a);
+    b.invert();
+    b.clip_to(&a);
+    b.invert();
+    a.build(b.all_polygons());
+    polygons_to_mesh(a.all_polygons())
+}
+
+///
+/// Returns the difference between the two closed meshes, ie. the volume inside the first mesh but outside the second mesh.
+///
+pub fn difference(a: &CpuMesh, b: &CpuMesh) -> CpuMesh {
+    let mut a = BspNode::new(mesh_to_polygons(a));
+    let mut b = BspNode::new(mesh_to_polygons(b));
+    a.invert();
+    a.clip_to(&b);
+    b.clip_to(&a);
+    b.invert();
+    b.clip_to(&a);
+    b.invert();
+    a.build(b.all_polygons());
+    a.invert();
+    polygons_to_mesh(a.all_polygons())
+}
+
+///
+/// Returns the intersection of the two closed meshes, ie. the volume inside both of the meshes.
+///
+pub fn intersection(a: &CpuMesh, b: &CpuMesh) -> CpuMesh {
+    let mut a = BspNode::new(mesh_to_polygons(a));
+    let mut b = BspNode::new(mesh_to_polygons(b));
+    a.invert();
+    b.clip_to(&a);
+    b.invert();
+    a.clip_to(&b);
+    b.clip_to(&a);
+    a.build(b.all_polygons());
+    a.invert();
+    polygons_to_mesh(a.all_polygons())
+}
+
+///
+/// Creates the sides of the contours of the given outline and holes transformed by each of the transformations, connecting consecutive transformations,
+/// and optionally closes the ends with the triangulated polygon.
+///
+fn sweep_transformations(
+    outline: &[Vec2],
+    holes: &[Vec<Vec2>],
+    transformations: &[Mat4],
+    closed_ends: bool,
+) -> CpuMesh {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices = Vec::new();
+
+    // Sides
+    let path_lengths = arc_lengths(
+        &transformations
+            .iter()
+            .map(|t| t.w.truncate())
+            .collect::<Vec<_>>(),
+    );
+    let path_length = path_lengths
+        .last()
+        .cloned()
+        .unwrap_or(0.0)
+        .max(f32::EPSILON);
+    let outer = if signed_area(outline) < 0.0 {
+        outline.iter().rev().cloned().collect::<Vec<_>>()
+    } else {
+        outline.to_vec()
+    };
+    let inner = holes.iter().map(|hole| {
+        if signed_area(hole) > 0.0 {
+            hole.iter().rev().cloned().collect::<Vec<_>>()
+        } else {
+            hole.clone()
+        }
+    });
+    for contour in std::iter::once(outer).chain(inner) {
+        let m = contour.len();
+        if m < 2 {
+            continue;
+        }
+        let mut closed_contour = contour.clone();
+        closed_contour.push(contour[0]);
+        let contour_lengths = arc_lengths(&closed_contour);
+        let perimeter = contour_lengths[m].max(f32::EPSILON);
+        let edge_normals = (0..m)
+            .map(|k| {
+                let d = contour[(k + 1) % m] - contour[k];
+                vec2(d.y, -d.x).normalize()
+            })
+            .collect::<Vec<_>>();
+        let vertex_normal = |k: usize, edge: usize| {
+            let (n0, n1) = (edge_normals[(k + m - 1) % m], edge_normals[k % m]);
+            if n0.dot(n1) >= SMOOTHING_ANGLE.cos() {
+                (n0 + n1).normalize()
+            } else {
+                edge_normals[edge]
+            }
+        };
+        for k in 0..m {
+            let (a, b) = (contour[k], contour[(k + 1) % m]);
+            let (na, nb) = (vertex_normal(k, k), vertex_normal(k + 1, k));
+            let (ua, ub) = (
+                contour_lengths[k] / perimeter,
+                contour_lengths[k + 1] / perimeter,
+            );
+            for (i, transformation) in transformations.iter().enumerate() {
+                let offset = positions.len() as u32;
+                for (p, normal, u) in [(a, na, ua), (b, nb, ub)] {
+                    positions.push((*transformation * vec4(p.x, p.y, 0.0, 1.0)).truncate());
+                    normals.push(
+                        (*transformation * vec4(normal.x, normal.y, 0.0, 0.0))
+                            .truncate()
+                            .normalize(),
+                    );
+                    uvs.push(vec2(u, path_lengths[i] / path_length));
+                }
+                if i > 0 {
+                    // Connect to the previous ring, where a0 and b0 are on the previous ring
+                    let (a0, b0, a1, b1) = (offset - 2, offset - 1, offset, offset + 1);
+                    indices.extend_from_slice(&[a0, b0, b1, a0, b1, a1]);
+                }
+            }
+        }
+    }
+
+    // Ends
+    if closed_ends {
+        let end = polygon(outline, holes);
+        let end_positions = end.positions.to_f32();
+        let end_uvs = end.uvs.unwrap();
+        let end_indices = match end.indices {
+            Indices::U32(ind) => ind,
+            _ => unreachable!(),
+        };
+        for (transformation, flip) in [
+            (transformations[0], true),
+            (transformations[transformations.len() - 1], false),
+        ] {
+            let offset = positions.len() as u32;
+            let normal = (transformation * vec4(0.0, 0.0, if flip { -1.0 } else { 1.0 }, 0.0))
+                .truncate()
+                .normalize();
+            for p in end_positions.iter() {
+                positions.push((transformation * p.extend(1.0)).truncate());
+                normals.push(normal);
+            }
+            uvs.extend_from_slice(&end_uvs);
+            for t in end_indices.chunks_exact(3) {
+                if flip {
+                    indices.extend_from_slice(&[offset + t[0], offset + t[2], offset + t[1]]);
+                } else {
+                    indices.extend_from_slice(&[offset + t[0], offset + t[1], offset + t[2]]);
+                }
+            }
+        }
+    }
+
+    let mut mesh = CpuMesh {
+        positions: Positions::F32(positions),
+        indices: Indices::U32(indices),
+        normals: Some(normals),
+        uvs: Some(uvs),
+        ..Default::default()
+    };
+    mesh.compute_tangents();
+    mesh
+}
+
+fn signed_area(points: &[Vec2]) -> f32 {
+    0.5 * (0..points.len())
+        .map(|i| cross_2d(points[i], points[(i + 1) % points.len()]))
+        .sum::<f32>()
+}
+
+fn cross_2d(a: Vec2, b: Vec2) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+fn point_in_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    cross_2d(b - a, p - a) >= 0.0 && cross_2d(c - b, p - b) >= 0.0 && cross_2d(a - c, p - c) >= 0.0
+}
+
+fn segments_intersect(p0: Vec2, p1: Vec2, q0: Vec2, q1: Vec2) -> bool {
+    let d0 = cross_2d(p1 - p0, q0 - p0);
+    let d1 = cross_2d(p1 - p0, q1 - p0);
+    let d2 = cross_2d(q1 - q0, p0 - q0);
+    let d3 = cross_2d(q1 - q0, p1 - q0);
+    d0 * d1 < 0.0 && d2 * d3 < 0.0
+}
+
+fn bounds(points: &[Vec2]) -> (Vec2, Vec2) {
+    points.iter().fold(
+        (vec2(f32::MAX, f32::MAX), vec2(f32::MIN, f32::MIN)),
+        |(min, max), p| {
+            (
+                vec2(min.x.min(p.x), min.y.min(p.y)),
+                vec2(max.x.max(p.x), max.y.max(p.y)),
+            )
+        },
+    )
+}
+
+///
+/// Returns the accumulated length along the given points, starting with zero at the first point.
+///
+fn arc_lengths<V: MetricSpace<Metric = f32> + Copy>(points: &[V]) -> Vec<f32> {
+    let mut lengths = Vec::with_capacity(points.len());
+    let mut length = 0.0;
+    for i in 0..points.len() {
+        if i > 0 {
+            length += points[i - 1].distance(points[i]);
+        }
+        lengths.push(length);
+    }
+    lengths
+}
+
+#[derive(Clone, Copy)]
+struct CsgVertex {
+    position: Vec3,
+    normal: Vec3,
+    uv: Vec2,
+}
+
+impl CsgVertex {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Self {
+            position: self.position + t * (other.position - self.position),
+            normal: self.normal + t * (other.normal - self.normal),
+            uv: self.uv + t * (other.uv - self.uv),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Plane {
+    normal: Vec3,
+    w: f32,
+}
+
+impl Plane {
+    fn from_points(a: Vec3, b: Vec3, c: Vec3) -> Option<Self> {
+        let normal = (b - a).cross(c - a);
+        if normal.magnitude2() < f32::EPSILON * f32::EPSILON {
+            return None;
+        }
+        let normal = normal.normalize();
+        Some(Self {
+            normal,
+            w: normal.dot(a),
+        })
+    }
+
+    fn flip(&mut self) {
+        self.normal = -self.normal;
+        self.w = -self.w;
+    }
+
+    ///
+    /// Splits the polygon by this plane and adds the parts to the relevant lists.
+    /// Polygons in this plane are added to either the coplanar front or coplanar back list depending on their orientation.
+    ///
+    fn split_polygon(
+        &self,
+        polygon: &CsgPolygon,
+        coplanar_front: &mut Vec<CsgPolygon>,
+        coplanar_back: &mut Vec<CsgPolygon>,
+        front: &mut Vec<CsgPolygon>,
+        back: &mut Vec<CsgPolygon>,
+    ) {
+        const COPLANAR: u8 = 0;
+        const FRONT: u8 = 1;
+        const BACK: u8 = 2;
+        const SPANNING: u8 = 3;
+        let mut polygon_type = COPLANAR;
+        let types = polygon
+            .vertices
+            .iter()
+            .map(|v| {
+                let t = self.normal.dot(v.position) - self.w;
+                let vertex_type = if t < -EPSILON {
+                    BACK
+                } else if t > EPSILON {
+                    FRONT
+                } else {
+                    COPLANAR
+                };
+                polygon_type |= vertex_type;
+                vertex_type
+            })
+            .collect::<Vec<_>>();
+        match polygon_type {
+            COPLANAR => {
+                if self.normal.dot(polygon.plane.normal) > 0.0 {
+                    coplanar_front.push(polygon.clone());
+                } else {
+                    coplanar_back.push(polygon.clone());
+                }
+            }
+            FRONT => front.push(polygon.clone()),
+            BACK => back.push(polygon.clone()),
+            _ => {
+                let mut f = Vec::new();
+                let mut b = Vec::new();
+                let n = polygon.vertices.len();
+                for i in 0..n {
+                    let j = (i + 1) % n;
+                    let (ti, tj) = (types[i], types[j]);
+                    let (vi, vj) = (&polygon.vertices[i], &polygon.vertices[j]);
+                    if ti != BACK {
+                        f.push(*vi);
+                    }
+                    if ti != FRONT {
+                        b.push(*vi);
+                    }
+                    if (ti | tj) == SPANNING {
+                        let t = (self.w - self.normal.dot(vi.position))
+                            / self.normal.dot(vj.position - vi.position);
+                        let v = vi.interpolate(vj, t);
+                        f.push(v);
+                        b.push(v);
+                    }
+                }
+                if f.len() >= 3 {
+                    front.push(CsgPolygon {
+                        vertices: f,
+                        plane: polygon.plane,
+                    });
+                }
+                if b.len() >= 3 {
+                    back.push(CsgPolygon {
+                        vertices: b,
+                        plane: polygon.plane,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct CsgPolygon {
+    vertices: Vec<CsgVertex>,
+    plane: Plane,
+}
+
+impl CsgPolygon {
+    fn flip(&mut self) {
+        self.vertices.reverse();
+        for v in self.vertices.iter_mut() {
+            v.normal = -v.normal;
+        }
+        self.plane.flip();
+    }
+}
+
+///
+/// A node in a binary space partitioning tree of polygons used for the boolean operations.
+///
+#[derive(Default)]
+struct BspNode {
+    plane: Option<Plane>,
+    front: Option<Box<BspNode>>,
+    back: Option<Box<BspNode>>,
+    polygons: Vec<CsgPolygon>,
+}
+
+impl BspNode {
+    fn new(polygons: Vec<CsgPolygon>) -> Self {
+        let mut node = Self::default();
+        node.build(polygons);
+        node
+    }
+
+    /// Converts solid space to empty space and empty space to solid space.
+    fn invert(&mut self) {
+        for polygon in self.polygons.iter_mut() {
+            polygon.flip();
+        }
+        if let Some(plane) = &mut self.plane {
+            plane.flip();
+        }
+        if let Some(front) = &mut self.front {
+            front.invert();
+        }
+        if let Some(back) = &mut self.back {
+            back.invert();
+        }
+        std::mem::swap(&mut self.front, &mut self.back);
+    }
+
+    /// Removes all polygons in the given list that are inside this tree.
+    fn clip_polygons(&self, polygons: Vec<CsgPolygon>) -> Vec<CsgPolygon> {
+        let plane = match &self.plane {
+            Some(plane) => plane,
+            None => return polygons,
+        };
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        for polygon in polygons.iter() {
+            let mut coplanar_front = Vec::new();
+            let mut coplanar_back = Vec::new();
+            plane.split_polygon(
+                polygon,
+                &mut coplanar_front,
+                &mut coplanar_back,
+                &mut front,
+                &mut back,
+            );
+            front.append(&mut coplanar_front);
+            back.append(&mut coplanar_back);
+        }
+        let mut front = match &self.front {
+            Some(node) => node.clip_polygons(front),
+            None => front,
+        };
+        let back = match &self.back {
+            Some(node) => node.clip_polygons(back),
+            None => Vec::new(),
+        };
+        front.extend(back);
+        front
+    }
+
+    /// Removes all polygons in this tree that are inside the other tree.
+    fn clip_to(&mut self, other: &BspNode) {
+        self.polygons = other.clip_polygons(std::mem::take(&mut self.polygons));
+        if let Some(front) = &mut self.front {
+            front.clip_to(other);
+        }
+        if let Some(back) = &mut self.back {
+            back.clip_to(other);
+        }
+    }
+
+    fn all_polygons(&self) -> Vec<CsgPolygon> {
+        let mut polygons = self.polygons.clone();
+        if let Some(front) = &self.front {
+            polygons.extend(front.all_polygons());
+        }
+        if let Some(back) = &self.back {
+            polygons.extend(back.all_polygons());
+        }
+        polygons
+    }
+
+    fn build(&mut self, polygons: Vec<CsgPolygon>) {
+        if polygons.is_empty() {
+            return;
+        }
+        let plane = *self.plane.get_or_insert(polygons[0].plane);
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        for polygon in polygons.iter() {
+            let mut coplanar_front = Vec::new();
+            let mut coplanar_back = Vec::new();
+            plane.split_polygon(
+                polygon,
+                &mut coplanar_front,
+                &mut coplanar_back,
+                &mut front,
+                &mut back,
+            );
+            self.polygons.append(&mut coplanar_front);
+            self.polygons.append(&mut coplanar_back);
+        }
+        if !front.is_empty() {
+            self.front.get_or_insert_with(Default::default).build(front);
+        }
+        if !back.is_empty() {
+            self.back.get_or_insert_with(Default::default).build(back);
+        }
+    }
+}
+
+fn mesh_to_polygons(cpu_mesh: &CpuMesh) -> Vec<CsgPolygon> {
+    let mut cpu_mesh = cpu_mesh.clone();
+    if cpu_mesh.normals.is_none() {
+        cpu_mesh.compute_normals();
+    }
+    let positions = cpu_mesh.positions.to_f32();
+    let normals = cpu_mesh.normals.unwrap();
+    let uvs = cpu_mesh
+        .uvs
+        .unwrap_or_else(|| vec![vec2(0.0, 0.0); positions.len()]);
+    let indices: Vec<usize> = match &cpu_mesh.indices {
+        Indices::U8(ind) => ind.iter().map(|i| *i as usize).collect(),
+        Indices::U16(ind) => ind.iter().map(|i| *i as usize).collect(),
+        Indices::U32(ind) => ind.iter().map(|i| *i as usize).collect(),
+        Indices::None => (0..positions.len()).collect(),
+    };
+    indices
+        .chunks_exact(3)
+        .filter_map(|t| {
+            let plane = Plane::from_points(positions[t[0]], positions[t[1]], positions[t[2]])?;
+            Some(CsgPolygon {
+                vertices: t
+                    .iter()
+                    .map(|i| CsgVertex {
+                        position: positions[*i],
+                        normal: normals[*i],
+                        uv: uvs[*i],
+                    })
+                    .collect(),
+                plane,
+            })
+        })
+        .collect()
+}
+
+fn polygons_to_mesh(polygons: Vec<CsgPolygon>) -> CpuMesh {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices = Vec::new();
+    for polygon in polygons {
+        let offset = positions.len() as u32;
+        for v in polygon.vertices.iter() {
+            positions.push(v.position);
+            normals.push(v.normal.normalize());
+            uvs.push(v.uv);
+        }
+        // The polygons are convex, so they can be triangulated as a fan
+        for i in 1..polygon.vertices.len() as u32 - 1 {
+            indices.extend_from_slice(&[offset, offset + i, offset + i + 1]);
+        }
+    }
+    let is_empty = positions.is_empty();
+    let mut mesh = CpuMesh {
+        positions: Positions::F32(positions),
+        indices: Indices::U32(indices),
+        normals: Some(normals),
+        uvs: Some(uvs),
+        ..Default::default()
+    };
+    if !is_empty {
+        mesh.compute_tangents();
+    }
+    mesh
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn triangle_area(points: &[Vec2], indices: &[u32]) -> f32 {
+        indices
+            .chunks_exact(3)
+            .map(|t| {
+                let (a, b, c) = (
+                    points[t[0] as usize],
+                    points[t[1] as usize],
+                    points[t[2] as usize],
+                );
+                0.5 * cross_2d(b - a, c - a)
+            })
+            .sum()
+    }
+
+    #[test]
+    fn triangulate_square_with_hole() {
+        let outline = vec![
+            vec2(0.0, 0.0),
+            vec2(4.0, 0.0),
+            vec2(4.0, 4.0),
+            vec2(0.0, 4.0),
+        ];
+        let hole = vec![
+            vec2(1.0, 1.0),
+            vec2(3.0, 1.0),
+            vec2(3.0, 3.0),
+            vec2(1.0, 3.0),
+        ];
+        let indices = triangulate(&outline, std::slice::from_ref(&hole));
+        let points = outline
+            .iter()
+            .chain(hole.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(indices.len() / 3, 8);
+        assert!((triangle_area(&points, &indices) - 12.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn union_of_disjoint_cubes() {
+        let a = CpuMesh::cube();
+        let mut b = CpuMesh::cube();
+        b.transform(&Mat4::from_translation(vec3(3.0, 0.0, 0.0)))
+            .unwrap();
+        let mesh = union(&a, &b);
+        let aabb = mesh.compute_aabb();
+        assert_eq!(aabb.min(), vec3(-1.0, -1.0, -1.0));
+        assert_eq!(aabb.max(), vec3(4.0, 1.0, 1.0));
+    }
+
+    fn volume(mesh: &CpuMesh) -> f32 {
+        let positions = mesh.positions.to_f32();
+        let mut volume = 0.0;
+        mesh.for_each_triangle(|a, b, c| {
+            volume += positions[a].dot(positions[b].cross(positions[c])) / 6.0;
+        });
+        volume
+    }
+
+    fn overlapping_cubes() -> (CpuMesh, CpuMesh) {
+        let a = CpuMesh::cube();
+        let mut b = CpuMesh::cube();
+        b.transform(&Mat4::from_translation(vec3(1.0, 0.0, 0.0)))
+            .unwrap();
+        (a, b)
+    }
+
+    #[test]
+    fn difference_of_overlapping_cubes() {
+        let (a, b) = overlapping_cubes();
+        let mesh = difference(&a, &b);
+        assert!((volume(&mesh) - 4.0).abs() < 0.0001);
+        let aabb = mesh.compute_aabb();
+        assert_eq!(aabb.min(), vec3(-1.0, -1.0, -1.0));
+        assert_eq!(aabb.max(), vec3(0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn intersection_of_overlapping_cubes() {
+        let (a, b) = overlapping_cubes();
+        let mesh = intersection(&a, &b);
+        assert!((volume(&mesh) - 4.0).abs() < 0.0001);
+        let aabb = mesh.compute_aabb();
+        assert_eq!(aabb.min(), vec3(0.0, -1.0, -1.0));
+        assert_eq!(aabb.max(), vec3(1.0, 1.0, 1.0));
+    }
+}
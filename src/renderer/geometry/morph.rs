@@ -0,0 +1,243 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// The maximum number of [MorphTarget]s that can be used for a [Mesh] or [InstancedMesh].
+///
+pub const MAX_MORPH_TARGETS: usize = 32;
+
+/// The maximum width of the texture containing the morph target data.
+const MAX_TEXTURE_WIDTH: usize = 4096;
+
+///
+/// A morph target (or blend shape) which, weighted by a morph weight, is added to the vertex data of a [Mesh] or [InstancedMesh] (see [Mesh::set_morph_targets] and [InstancedMesh::set_morph_targets]).
+/// All data is given as displacements relative to the vertex data of the mesh, one displacement for each vertex.
+///
+#[derive(Clone, Debug, Default)]
+pub struct MorphTarget {
+    /// The displacement of the position of each vertex.
+    pub positions: Vec<Vec3>,
+    /// The displacement of the normal of each vertex.
+    pub normals: Option<Vec<Vec3>>,
+    /// The displacement of the tangent of each vertex.
+    pub tangents: Option<Vec<Vec3>>,
+}
+
+///
+/// Returns the morph weights at the given time of the first key frames in the animation that contain morph weights,
+/// or `None` if there are no such key frames or if the number of weights does not match the number of key frame times.
+///
+fn key_frame_weights(animation: &KeyFrameAnimation, time: f32) -> Option<Vec<f32>> {
+    animation
+        .key_frames
+        .iter()
+        .map(|(_, key_frames)| key_frames)
+        .find(|key_frames| key_frames.weights.is_some())
+        .filter(|key_frames| {
+            let weights = key_frames.weights.as_ref().unwrap();
+            !key_frames.times.is_empty()
+                && weights.len() == key_frames.times.len()
+                && weights.iter().all(|w| w.len() == weights[0].len())
+        })
+        .and_then(|key_frames| key_frames.weights(time))
+}
+
+#[derive(Default)]
+pub(super) struct MorphAnimations {
+    animations: Vec<KeyFrameAnimation>,
+    animation: Option<String>,
+}
+
+impl MorphAnimations {
+    pub fn names(&self) -> Vec<Option<String>> {
+        self.animations.iter().map(|a| a.name.clone()).collect()
+    }
+
+    pub fn set(&mut self, animations: Vec<KeyFrameAnimation>) {
+        self.animation = animations.first().and_then(|a| a.name.clone());
+        self.animations = animations;
+    }
+
+    pub fn choose(&mut self, animation_name: Option<&str>) {
+        self.animation = animation_name.map(|name| name.to_owned());
+    }
+
+    pub fn weights(&self, time: f32) -> Option<Vec<f32>> {
+        self.animations
+            .iter()
+            .find(|a| self.animation.as_deref() == a.name.as_deref())
+            .and_then(|animation| key_frame_weights(animation, time))
+    }
+
+    pub fn blended_weights(&self, samples: &[AnimationSample]) -> Option<Vec<f32>> {
+        let mut weights = Vec::new();
+        let mut total_weight = 0.0;
+        for sample in samples {
+            if let Some(w) = self
+                .animations
+                .iter()
+                .find(|a| sample.name == a.name)
+                .and_then(|animation| key_frame_weights(animation, sample.time))
+            {
+                if weights.len() < w.len() {
+                    weights.resize(w.len(), 0.0);
+                }
+                for (weight, w) in weights.iter_mut().zip(w) {
+                    *weight += sample.weight * w;
+                }
+                total_weight += sample.weight;
+            }
+        }
+        if total_weight > 0.0 {
+            Some(weights.into_iter().map(|w| w / total_weight).collect())
+        } else {
+            None
+        }
+    }
+}
+
+pub(super) struct MorphTargets {
+    texture: Texture2DArray,
+    count: usize,
+    normals: bool,
+    tangents: bool,
+    weights: Vec<f32>,
+}
+
+impl MorphTargets {
+    pub fn new(context: &Context, vertex_count: usize, morph_targets: &[MorphTarget]) -> Self {
+        if morph_targets.len() > MAX_MORPH_TARGETS {
+            panic!(
+                "{} morph targets are given but at most {} morph targets are supported",
+                morph_targets.len(),
+                MAX_MORPH_TARGETS
+            );
+        }
+        let normals = morph_targets.iter().all(|t| t.normals.is_some());
+        let tangents = morph_targets.iter().all(|t| t.tangents.is_some());
+        let width = vertex_count.clamp(1, MAX_TEXTURE_WIDTH);
+        let height = vertex_count.div_ceil(width);
+        let layer = |data: &[Vec3]| {
+            #[cfg(debug_assertions)]
+            if data.len() != vertex_count {
+                panic!(
+                    "the number of morph target displacements ({}) does not match the number of vertices ({})",
+                    data.len(),
+                    vertex_count
+                );
+            }
+            let mut layer = data.to_vec();
+            layer.resize(width * height.max(1), vec3(0.0, 0.0, 0.0));
+            layer
+        };
+        let mut layers = Vec::new();
+        for morph_target in morph_targets {
+            layers.push(layer(&morph_target.positions));
+            if normals {
+                layers.push(layer(morph_target.normals.as_ref().unwrap()));
+            }
+            if tangents {
+                layers.push(layer(morph_target.tangents.as_ref().unwrap()));
+            }
+        }
+        let mut texture = Texture2DArray::new_empty::<Vec3>(
+            context,
+            width as u32,
+            height.max(1) as u32,
+            layers.len().max(1) as u32,
+            Interpolation::Nearest,
+            Interpolation::Nearest,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        texture.fill(&layers.iter().map(|l| l.as_slice()).collect::<Vec<_>>());
+        Self {
+            texture,
+            count: morph_targets.len(),
+            normals,
+            tangents,
+            weights: vec![0.0; morph_targets.len()],
+        }
+    }
+
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    pub fn set_weights(&mut self, weights: &[f32]) {
+        if weights.len() != self.count {
+            panic!(
+                "{} morph weights are given but there are {} morph targets",
+                weights.len(),
+                self.count
+            );
+        }
+        self.weights = weights.to_vec();
+    }
+
+    pub fn set_animated_weights(&mut self, mut weights: Vec<f32>) {
+        weights.resize(self.count, 0.0);
+        self.weights = weights;
+    }
+
+    pub fn shader_source(&self) -> String {
+        format!(
+            "#define USE_MORPH_TARGETS\n#define MAX_MORPH_TARGETS {}\n#define MORPH_STRIDE {}\n{}{}",
+            MAX_MORPH_TARGETS,
+            1 + self.normals as usize + self.tangents as usize,
+            if self.normals {
+                "#define USE_MORPH_NORMALS\n"
+            } else {
+                ""
+            },
+            if self.tangents {
+                "#define USE_MORPH_TANGENTS\n"
+            } else {
+                ""
+            },
+        )
+    }
+
+    pub fn use_uniforms(&self, program: &Program) {
+        let mut weights = self.weights.clone();
+        weights.resize(MAX_MORPH_TARGETS, 0.0);
+        program.use_texture_array("morphTargets", &self.texture);
+        program.use_uniform_array("morphWeights", &weights);
+        program.use_uniform("morphTargetCount", self.count as i32);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn animation(times: Vec<f32>, weights: Vec<Vec<f32>>) -> KeyFrameAnimation {
+        KeyFrameAnimation {
+            name: None,
+            key_frames: vec![(
+                Mat4::identity(),
+                std::sync::Arc::new(KeyFrames {
+                    loop_time: Some(2.0),
+                    times,
+                    weights: Some(weights),
+                    ..Default::default()
+                }),
+            )],
+        }
+    }
+
+    #[test]
+    fn morph_animation_weights() {
+        let animation = animation(vec![0.0, 1.0], vec![vec![0.0, 1.0], vec![1.0, 0.0]]);
+        assert_eq!(key_frame_weights(&animation, 0.5), Some(vec![0.5, 0.5]));
+        assert_eq!(key_frame_weights(&animation, 1.5), Some(vec![1.0, 0.0]));
+        assert_eq!(key_frame_weights(&animation, 2.25), Some(vec![0.25, 0.75]));
+    }
+
+    #[test]
+    fn morph_animation_with_mismatched_weights() {
+        let animation = animation(vec![0.0, 1.0], vec![vec![0.0, 1.0]]);
+        assert_eq!(key_frame_weights(&animation, 0.5), None);
+    }
+}
@@ -185,7 +185,7 @@ impl ParticleSystem {
                 return;
             }
         }
-        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_camera_block(camera);
         program.use_uniform("modelMatrix", self.transformation);
         program.use_uniform("acceleration", self.acceleration);
         program.use_uniform("time", self.time);
@@ -0,0 +1,487 @@
+use crate::core::*;
+use crate::renderer::*;
+
+/// The number of triangles in a round join.
+const ROUND_JOIN_TRIANGLES: usize = 16;
+
+///
+/// Defines how two connected segments of a [Polyline3D] are joined.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum LineJoin {
+    /// The segments are not joined, which leaves a small gap at the outside of sharp corners for wide lines.
+    None,
+    /// The outer edges of the segments are extended until they meet. Very sharp corners fall back to [LineJoin::None].
+    #[default]
+    Miter,
+    /// The segments are joined by a circle which also gives the ends of the lines round caps.
+    Round,
+}
+
+///
+/// A set of 3D polylines, ie. connected line segments, rendered with a constant width in pixels and with the correct depth.
+/// A polyline where the first and last point are equal is closed and joined at that point.
+/// The material color is multiplied by the vertex colors, if any, and the uv coordinates run along the segments in the u direction and across in the v direction.
+///
+pub struct Polyline3D {
+    context: Context,
+    segment_positions: VertexBuffer,
+    join_positions: VertexBuffer,
+    starts: InstanceBuffer,
+    ends: InstanceBuffer,
+    previous: InstanceBuffer,
+    nexts: InstanceBuffer,
+    start_colors: InstanceBuffer,
+    end_colors: InstanceBuffer,
+    points: InstanceBuffer,
+    point_colors: InstanceBuffer,
+    lines: Vec<Vec<Vec3>>,
+    colors: Option<Vec<Vec<Color>>>,
+    width: f32,
+    join: LineJoin,
+    dash_pattern: Option<(f32, f32)>,
+    aabb: AxisAlignedBoundingBox,
+    transformation: Mat4,
+}
+
+impl Polyline3D {
+    ///
+    /// Creates a new set of polylines, each given as a list of connected points, with the given width in pixels.
+    /// If colors are given, there must be one color for each point.
+    ///
+    pub fn new(
+        context: &Context,
+        lines: &[Vec<Vec3>],
+        colors: Option<&[Vec<Color>]>,
+        width: f32,
+    ) -> Self {
+        let segment_positions = VertexBuffer::new_with_data(
+            context,
+            &[
+                vec3(0.0, -1.0, 0.0),
+                vec3(1.0, -1.0, 0.0),
+                vec3(1.0, 1.0, 0.0),
+                vec3(1.0, 1.0, 0.0),
+                vec3(0.0, 1.0, 0.0),
+                vec3(0.0, -1.0, 0.0),
+            ],
+        );
+        let mut join_positions = Vec::new();
+        for i in 0..ROUND_JOIN_TRIANGLES {
+            let a0 = 2.0 * std::f32::consts::PI * i as f32 / ROUND_JOIN_TRIANGLES as f32;
+            let a1 = 2.0 * std::f32::consts::PI * (i + 1) as f32 / ROUND_JOIN_TRIANGLES as f32;
+            join_positions.push(vec3(0.0, 0.0, 0.0));
+            join_positions.push(vec3(0.0, a0.cos(), a0.sin()));
+            join_positions.push(vec3(0.0, a1.cos(), a1.sin()));
+        }
+        let mut polyline = Self {
+            context: context.clone(),
+            segment_positions,
+            join_positions: VertexBuffer::new_with_data(context, &join_positions),
+            starts: InstanceBuffer::new(context),
+            ends: InstanceBuffer::new(context),
+            previous: InstanceBuffer::new(context),
+            nexts: InstanceBuffer::new(context),
+            start_colors: InstanceBuffer::new(context),
+            end_colors: InstanceBuffer::new(context),
+            points: InstanceBuffer::new(context),
+            point_colors: InstanceBuffer::new(context),
+            lines: Vec::new(),
+            colors: None,
+            width,
+            join: LineJoin::default(),
+            dash_pattern: None,
+            aabb: AxisAlignedBoundingBox::EMPTY,
+            transformation: Mat4::identity(),
+        };
+        polyline.set_lines(lines, colors);
+        polyline
+    }
+
+    ///
+    /// Sets the polylines, each given as a list of connected points.
+    /// If colors are given, there must be one color for each point.
+    ///
+    pub fn set_lines(&mut self, lines: &[Vec<Vec3>], colors: Option<&[Vec<Color>]>) {
+        #[cfg(debug_assertions)]
+        if let Some(colors) = colors {
+            if colors.len() != lines.len()
+                || colors.iter().zip(lines).any(|(c, l)| c.len() != l.len())
+            {
+                panic!("the number of colors does not match the number of points");
+            }
+        }
+        self.lines = lines.to_vec();
+        self.colors = colors.map(|colors| colors.to_vec());
+        self.aabb = AxisAlignedBoundingBox::new_with_positions(
+            &lines.iter().flatten().cloned().collect::<Vec<_>>(),
+        );
+        self.update_buffers();
+    }
+
+    ///
+    /// Returns the width of the lines in pixels.
+    ///
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    ///
+    /// Sets the width of the lines in pixels.
+    ///
+    pub fn set_width(&mut self, width: f32) {
+        self.width = width;
+    }
+
+    ///
+    /// Returns how connected segments are joined.
+    ///
+    pub fn join(&self) -> LineJoin {
+        self.join
+    }
+
+    ///
+    /// Sets how connected segments are joined.
+    ///
+    pub fn set_join(&mut self, join: LineJoin) {
+        self.join = join;
+    }
+
+    ///
+    /// Returns the length of the dashes and the gaps between them, if the lines are dashed.
+    ///
+    pub fn dash_pattern(&self) -> Option<(f32, f32)> {
+        self.dash_pattern
+    }
+
+    ///
+    /// Makes the lines dashed with the given length of the dashes and the gaps between them or solid if `None`.
+    /// The lengths are measured along the lines in the local coordinate system of the lines, ie. before the transformation is applied.
+    ///
+    pub fn set_dash_pattern(&mut self, dash_pattern: Option<(f32, f32)>) {
+        self.dash_pattern = dash_pattern;
+        self.update_buffers();
+    }
+
+    ///
+    /// Returns the local to world transformation applied to the lines.
+    ///
+    pub fn transformation(&self) -> Mat4 {
+        self.transformation
+    }
+
+    ///
+    /// Set the local to world transformation applied to the lines.
+    ///
+    pub fn set_transformation(&mut self, transformation: Mat4) {
+        self.transformation = transformation;
+    }
+
+    fn update_buffers(&mut self) {
+        let mut lines = Vec::new();
+        for (i, points) in self.lines.iter().enumerate() {
+            let colors = self
+                .colors
+                .as_ref()
+                .map(|colors| colors[i].clone())
+                .unwrap_or_else(|| vec![Color::WHITE; points.len()]);
+            match self.dash_pattern {
+                Some((dash, gap)) if dash > 0.0 && gap > 0.0 => {
+                    lines.extend(dash_line(points, &colors, dash, gap))
+                }
+                _ => lines.push((points.clone(), colors)),
+            }
+        }
+
+        let mut starts = Vec::new();
+        let mut ends = Vec::new();
+        let mut previous = Vec::new();
+        let mut nexts = Vec::new();
+        let mut start_colors = Vec::new();
+        let mut end_colors = Vec::new();
+        let mut points = Vec::new();
+        let mut point_colors = Vec::new();
+        for (line, colors) in lines.iter().filter(|(line, _)| line.len() > 1) {
+            let n = line.len();
+            let closed = n > 2 && line[0] == line[n - 1];
+            for i in 0..n - 1 {
+                starts.push(line[i]);
+                ends.push(line[i + 1]);
+                previous.push(if i > 0 {
+                    line[i - 1]
+                } else if closed {
+                    line[n - 2]
+                } else {
+                    line[i]
+                });
+                nexts.push(if i + 2 < n {
+                    line[i + 2]
+                } else if closed {
+                    line[1]
+                } else {
+                    line[i + 1]
+                });
+                start_colors.push(colors[i]);
+                end_colors.push(colors[i + 1]);
+            }
+            points.extend_from_slice(line);
+            point_colors.extend_from_slice(colors);
+        }
+        self.starts.fill(&starts);
+        self.ends.fill(&ends);
+        self.previous.fill(&previous);
+        self.nexts.fill(&nexts);
+        self.start_colors.fill(&start_colors);
+        self.end_colors.fill(&end_colors);
+        self.points.fill(&points);
+        self.point_colors.fill(&point_colors);
+    }
+
+    fn vertex_shader_source(&self, join: bool, attributes: FragmentAttributes) -> String {
+        format!(
+            "{}{}{}",
+            if join {
+                "#define JOIN\n"
+            } else if self.join == LineJoin::Miter {
+                "#define USE_MITER\n"
+            } else {
+                ""
+            },
+            if attributes.color {
+                "#define USE_COLORS\n"
+            } else {
+                ""
+            },
+            include_str!("shaders/polyline.vert")
+        )
+    }
+
+    fn draw(
+        &self,
+        program: &Program,
+        render_states: RenderStates,
+        camera: &Camera,
+        join: bool,
+        attributes: FragmentAttributes,
+    ) {
+        let viewport = camera.viewport();
+        program.use_camera_block(camera);
+        program.use_uniform("modelMatrix", self.transformation);
+        program.use_uniform_if_required("eye", camera.position());
+        program.use_uniform(
+            "viewportSize",
+            vec2(viewport.width as f32, viewport.height as f32),
+        );
+        program.use_uniform("halfWidth", 0.5 * self.width);
+        if join {
+            program.use_vertex_attribute("position", &self.join_positions);
+            program.use_instance_attribute("point", &self.points);
+            if attributes.color {
+                program.use_instance_attribute("point_color", &self.point_colors);
+            }
+            program.draw_arrays_instanced(
+                render_states,
+                viewport,
+                PrimitiveTopology::Triangles,
+                self.join_positions.vertex_count(),
+                self.points.instance_count(),
+            );
+        } else {
+            program.use_vertex_attribute("position", &self.segment_positions);
+            program.use_instance_attribute("segment_start", &self.starts);
+            program.use_instance_attribute("segment_end", &self.ends);
+            if self.join == LineJoin::Miter {
+                program.use_instance_attribute("segment_previous", &self.previous);
+                program.use_instance_attribute("segment_next", &self.nexts);
+            }
+            if attributes.color {
+                program.use_instance_attribute("start_color", &self.start_colors);
+                program.use_instance_attribute("end_color", &self.end_colors);
+            }
+            program.draw_arrays_instanced(
+                render_states,
+                viewport,
+                PrimitiveTopology::Triangles,
+                self.segment_positions.vertex_count(),
+                self.starts.instance_count(),
+            );
+        }
+    }
+
+    fn passes(&self) -> &'static [bool] {
+        if self.join == LineJoin::Round {
+            &[false, true]
+        } else {
+            &[false]
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Polyline3D {
+    type Item = &'a dyn Geometry;
+    type IntoIter = std::iter::Once<&'a dyn Geometry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        std::iter::once(self)
+    }
+}
+
+impl Geometry for Polyline3D {
+    fn render_with_material(
+        &self,
+        material: &dyn Material,
+        camera: &Camera,
+        lights: &[&dyn Light],
+    ) {
+        if self.starts.instance_count() == 0 {
+            return;
+        }
+        let fragment_shader = material.fragment_shader(lights);
+        if fragment_shader.attributes.tangents {
+            panic!("a Polyline3D cannot be rendered with a material that requires tangents");
+        }
+        for join in self.passes() {
+            self.context
+                .program(
+                    self.vertex_shader_source(*join, fragment_shader.attributes),
+                    fragment_shader.source.clone(),
+                    |program| {
+                        material.use_uniforms(program, camera, lights);
+                        self.draw(
+                            program,
+                            material.render_states(),
+                            camera,
+                            *join,
+                            fragment_shader.attributes,
+                        );
+                    },
+                )
+                .expect("Failed compiling shader")
+        }
+    }
+
+    fn render_with_post_material(
+        &self,
+        material: &dyn PostMaterial,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        if self.starts.instance_count() == 0 {
+            return;
+        }
+        let fragment_shader = material.fragment_shader(lights, color_texture, depth_texture);
+        if fragment_shader.attributes.tangents {
+            panic!("a Polyline3D cannot be rendered with a material that requires tangents");
+        }
+        for join in self.passes() {
+            self.context
+                .program(
+                    self.vertex_shader_source(*join, fragment_shader.attributes),
+                    fragment_shader.source.clone(),
+                    |program| {
+                        material.use_uniforms(
+                            program,
+                            camera,
+                            lights,
+                            color_texture,
+                            depth_texture,
+                        );
+                        self.draw(
+                            program,
+                            material.render_states(),
+                            camera,
+                            *join,
+                            fragment_shader.attributes,
+                        );
+                    },
+                )
+                .expect("Failed compiling shader")
+        }
+    }
+
+    fn aabb(&self) -> AxisAlignedBoundingBox {
+        let mut aabb = self.aabb;
+        aabb.transform(&self.transformation);
+        aabb
+    }
+}
+
+///
+/// Splits the given line into dashes with the given length and gaps with the given length between them.
+///
+fn dash_line(
+    points: &[Vec3],
+    colors: &[Color],
+    dash: f32,
+    gap: f32,
+) -> Vec<(Vec<Vec3>, Vec<Color>)> {
+    let lerp_color = |c0: Color, c1: Color, t: f32| {
+        let lerp = |a: u8, b: u8| (a as f32 + t * (b as f32 - a as f32)).round() as u8;
+        Color::new(
+            lerp(c0.r, c1.r),
+            lerp(c0.g, c1.g),
+            lerp(c0.b, c1.b),
+            lerp(c0.a, c1.a),
+        )
+    };
+    let mut dashes = Vec::new();
+    let mut current: Option<(Vec<Vec3>, Vec<Color>)> = None;
+    // The distance left of the current dash or gap
+    let mut remaining = dash;
+    let mut in_dash = true;
+    for i in 0..points.len().saturating_sub(1) {
+        let (p0, p1) = (points[i], points[i + 1]);
+        let length = p0.distance(p1);
+        let mut distance = 0.0;
+        if in_dash && current.is_none() {
+            current = Some((vec![p0], vec![colors[i]]));
+        }
+        while length - distance > remaining {
+            distance += remaining;
+            let t = distance / length;
+            let p = p0 + t * (p1 - p0);
+            let c = lerp_color(colors[i], colors[i + 1], t);
+            if in_dash {
+                let (mut line, mut line_colors) = current.take().unwrap();
+                line.push(p);
+                line_colors.push(c);
+                dashes.push((line, line_colors));
+                remaining = gap;
+            } else {
+                current = Some((vec![p], vec![c]));
+                remaining = dash;
+            }
+            in_dash = !in_dash;
+        }
+        remaining -= length - distance;
+        if let Some((line, line_colors)) = &mut current {
+            line.push(p1);
+            line_colors.push(colors[i + 1]);
+        }
+    }
+    if let Some(dash) = current {
+        dashes.push(dash);
+    }
+    dashes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dash_line_splits_into_dashes() {
+        let points = vec![
+            vec3(0.0, 0.0, 0.0),
+            vec3(2.0, 0.0, 0.0),
+            vec3(2.0, 2.0, 0.0),
+        ];
+        let colors = vec![Color::WHITE; 3];
+        let dashes = dash_line(&points, &colors, 1.5, 0.5);
+        assert_eq!(dashes.len(), 2);
+        assert_eq!(dashes[0].0, vec![vec3(0.0, 0.0, 0.0), vec3(1.5, 0.0, 0.0)]);
+        assert_eq!(dashes[1].0, vec![vec3(2.0, 0.0, 0.0), vec3(2.0, 1.5, 0.0)]);
+    }
+}
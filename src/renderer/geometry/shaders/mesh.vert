@@ -1,8 +1,12 @@
 
-uniform mat4 viewProjection;
+#include "camera"
 uniform mat4 modelMatrix;
 in vec3 position;
 
+#ifdef USE_POINTS
+uniform float pointSize;
+#endif
+
 #ifdef PARTICLES
 in vec3 start_position;
 in vec3 start_velocity;
@@ -20,6 +24,28 @@ in vec4 row2;
 in vec4 row3;
 #endif
 
+#ifdef USE_SKINNING
+in vec4 joint_indices;
+in vec4 joint_weights;
+layout (std140) uniform JointMatrices
+{
+    mat4 jointMatrices[MAX_JOINTS];
+};
+#endif
+
+#ifdef USE_MORPH_TARGETS
+uniform sampler2DArray morphTargets;
+uniform float morphWeights[MAX_MORPH_TARGETS];
+uniform int morphTargetCount;
+
+vec3 morph_displacement(int layer)
+{
+    ivec3 size = textureSize(morphTargets, 0);
+    ivec3 coords = ivec3(gl_VertexID % size.x, size.y - 1 - gl_VertexID / size.x, layer);
+    return texelFetch(morphTargets, coords, 0).xyz;
+}
+#endif
+
 out vec3 pos;
 
 #ifdef USE_NORMALS 
@@ -45,6 +71,11 @@ in vec2 uv_coordinates;
 out vec2 uvs;
 #endif
 
+#ifdef USE_LIGHTMAP_UVS
+in vec2 lightmap_uv_coordinates;
+out vec2 lightmap_uvs;
+#endif
+
 #ifdef USE_VERTEX_COLORS 
 in vec4 color;
 #endif
@@ -57,6 +88,12 @@ out vec4 col;
 void main()
 {
     // *** POSITION ***
+    vec3 localPosition = position;
+#ifdef USE_MORPH_TARGETS
+    for (int i = 0; i < morphTargetCount; i++) {
+        localPosition += morphWeights[i] * morph_displacement(i * MORPH_STRIDE);
+    }
+#endif
     mat4 local2World = modelMatrix;
     
 #ifdef USE_INSTANCE_TRANSFORMS
@@ -68,7 +105,15 @@ void main()
     local2World *= transform;
 #endif
 
-    vec4 worldPosition = local2World * vec4(position, 1.);
+#ifdef USE_SKINNING
+    mat4 skinMatrix = joint_weights.x * jointMatrices[int(joint_indices.x)] +
+        joint_weights.y * jointMatrices[int(joint_indices.y)] +
+        joint_weights.z * jointMatrices[int(joint_indices.z)] +
+        joint_weights.w * jointMatrices[int(joint_indices.w)];
+    vec4 worldPosition = local2World * skinMatrix * vec4(localPosition, 1.);
+#else
+    vec4 worldPosition = local2World * vec4(localPosition, 1.);
+#endif
     worldPosition /= worldPosition.w;
 #ifdef PARTICLES
     worldPosition.xyz += start_position + start_velocity * time + 0.5 * acceleration * time * time;
@@ -77,6 +122,9 @@ void main()
     worldPosition.xyz += instance_translation;
 #endif
     gl_Position = viewProjection * worldPosition;
+#ifdef USE_POINTS
+    gl_PointSize = pointSize;
+#endif
 
     pos = worldPosition.xyz;
 
@@ -87,10 +135,30 @@ void main()
 #else
     mat3 normalMat = mat3(normalMatrix);
 #endif
-    nor = normalize(normalMat * normal);
+#ifdef USE_SKINNING
+    normalMat *= mat3(skinMatrix);
+#endif
+    vec3 localNormal = normal;
+#ifdef USE_MORPH_NORMALS
+    for (int i = 0; i < morphTargetCount; i++) {
+        localNormal += morphWeights[i] * morph_displacement(i * MORPH_STRIDE + 1);
+    }
+#endif
+    nor = normalize(normalMat * localNormal);
 
 #ifdef USE_TANGENTS 
-    tang = normalize(normalMat * tangent.xyz);
+    vec3 localTangent = tangent.xyz;
+#ifdef USE_MORPH_TANGENTS
+#ifdef USE_MORPH_NORMALS
+    int tangentOffset = 2;
+#else
+    int tangentOffset = 1;
+#endif
+    for (int i = 0; i < morphTargetCount; i++) {
+        localTangent += morphWeights[i] * morph_displacement(i * MORPH_STRIDE + tangentOffset);
+    }
+#endif
+    tang = normalize(normalMat * localTangent);
     bitang = normalize(cross(nor, tang) * tangent.w);
 #endif
 
@@ -107,6 +175,9 @@ void main()
 #else
     uvs = uv_coordinates;
 #endif
+#endif
+#ifdef USE_LIGHTMAP_UVS
+    lightmap_uvs = lightmap_uv_coordinates;
 #endif
 
     // *** COLOR ***
@@ -0,0 +1,120 @@
+#include "camera"
+uniform mat4 modelMatrix;
+uniform vec3 eye;
+uniform vec2 viewportSize;
+uniform float halfWidth;
+
+in vec3 position;
+
+#ifdef JOIN
+in vec3 point;
+#ifdef USE_COLORS
+in vec4 point_color;
+#endif
+#else
+in vec3 segment_start;
+in vec3 segment_end;
+#ifdef USE_MITER
+in vec3 segment_previous;
+in vec3 segment_next;
+#endif
+#ifdef USE_COLORS
+in vec4 start_color;
+in vec4 end_color;
+#endif
+#endif
+
+out vec3 pos;
+out vec3 nor;
+out vec2 uvs;
+out vec4 col;
+
+// The smallest w coordinate of a point in clip space in front of the camera
+const float NEAR_W = 0.00001;
+
+// The maximum length of a miter relative to the half width before the join falls back to no join
+const float MITER_LIMIT = 4.0;
+
+vec2 to_screen(vec4 c) {
+    return c.xy / c.w * 0.5 * viewportSize;
+}
+
+vec4 from_screen(vec2 s, vec4 c) {
+    return vec4(s / (0.5 * viewportSize) * c.w, c.z, c.w);
+}
+
+void main()
+{
+#ifdef JOIN
+    vec4 world = modelMatrix * vec4(point, 1.0);
+    vec4 c = viewProjection * world;
+    if (c.w < NEAR_W) {
+        // Behind the camera
+        gl_Position = vec4(2.0, 2.0, 2.0, 1.0);
+        return;
+    }
+    gl_Position = from_screen(to_screen(c) + position.yz * halfWidth, c);
+    pos = world.xyz / world.w;
+    uvs = vec2(0.5, 0.5) + 0.5 * position.yz;
+    col = vec4(1.0);
+#ifdef USE_COLORS
+    col = point_color / 255.0;
+#endif
+#else
+    vec4 w0 = modelMatrix * vec4(segment_start, 1.0);
+    vec4 w1 = modelMatrix * vec4(segment_end, 1.0);
+    vec4 c0 = viewProjection * w0;
+    vec4 c1 = viewProjection * w1;
+    if (c0.w < NEAR_W && c1.w < NEAR_W) {
+        // Behind the camera
+        gl_Position = vec4(2.0, 2.0, 2.0, 1.0);
+        return;
+    }
+
+    // Clip the segment against the near plane
+    bool is_end = position.x > 0.5;
+    vec4 c_this = is_end ? c1 : c0;
+    vec4 c_other = is_end ? c0 : c1;
+    bool clipped = c_this.w < NEAR_W;
+    float t = position.x;
+    if (clipped) {
+        float f = (NEAR_W - c_this.w) / (c_other.w - c_this.w);
+        c_this = mix(c_this, c_other, f);
+        t = is_end ? 1.0 - f : f;
+    }
+    if (c_other.w < NEAR_W) {
+        c_other = mix(c_other, c_this, (NEAR_W - c_other.w) / (c_this.w - c_other.w));
+    }
+
+    vec2 s = to_screen(c_this);
+    vec2 dir = is_end ? s - to_screen(c_other) : to_screen(c_other) - s;
+    dir = length(dir) > 0.0 ? normalize(dir) : vec2(1.0, 0.0);
+    vec2 normal = vec2(-dir.y, dir.x);
+    vec2 offset = normal * halfWidth;
+
+#ifdef USE_MITER
+    vec4 c_neighbour = viewProjection * modelMatrix * vec4(is_end ? segment_next : segment_previous, 1.0);
+    if (!clipped && c_neighbour.w >= NEAR_W) {
+        vec2 d = is_end ? to_screen(c_neighbour) - s : s - to_screen(c_neighbour);
+        if (length(d) > 0.0) {
+            vec2 tangent = normalize(normalize(d) + dir);
+            vec2 miter = vec2(-tangent.y, tangent.x);
+            float miter_length = halfWidth / max(dot(miter, normal), 0.0001);
+            if (miter_length < MITER_LIMIT * halfWidth) {
+                offset = miter * miter_length;
+            }
+        }
+    }
+#endif
+
+    gl_Position = from_screen(s + position.y * offset, c_this);
+    vec4 world = mix(w0, w1, t);
+    pos = world.xyz / world.w;
+    uvs = vec2(t, 0.5 + 0.5 * position.y);
+    col = vec4(1.0);
+#ifdef USE_COLORS
+    col = mix(start_color, end_color, t) / 255.0;
+#endif
+#endif
+    nor = normalize(eye - pos);
+}
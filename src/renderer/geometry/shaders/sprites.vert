@@ -1,4 +1,4 @@
-uniform mat4 viewProjection;
+#include "camera"
 uniform vec3 eye;
 uniform mat4 transformation;
 uniform vec3 direction;
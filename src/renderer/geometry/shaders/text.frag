@@ -0,0 +1,30 @@
+uniform vec4 surfaceColor;
+uniform sampler2D sdfAtlas;
+
+#ifdef USE_OUTLINE
+uniform vec4 outlineColor;
+uniform float outlineWidth;
+#endif
+
+in vec2 uvs;
+in vec4 col;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    float distance = texture(sdfAtlas, uvs).r;
+    float smoothing = max(0.75 * fwidth(distance), 0.0001);
+    float alpha = smoothstep(0.5 - smoothing, 0.5 + smoothing, distance);
+    vec4 color = surfaceColor * col;
+
+    #ifdef USE_OUTLINE
+    color = mix(outlineColor, color, alpha);
+    alpha = smoothstep(0.5 - outlineWidth - smoothing, 0.5 - outlineWidth + smoothing, distance);
+    #endif
+
+    if (alpha * color.a < 0.004) {
+        discard;
+    }
+    outColor = vec4(srgb_from_rgb(color.rgb), color.a * alpha);
+}
@@ -0,0 +1,45 @@
+#include "camera"
+uniform mat4 modelMatrix;
+uniform vec3 eye;
+uniform float textSize;
+
+#if defined(USE_BILLBOARD) || defined(USE_LABEL)
+uniform vec3 anchor;
+#endif
+#ifdef USE_BILLBOARD
+uniform vec3 cameraRight;
+uniform vec3 cameraUp;
+#endif
+#ifdef USE_LABEL
+uniform vec2 viewportSize;
+#endif
+
+in vec2 position;
+in vec2 uv_coordinates;
+
+out vec3 pos;
+out vec3 nor;
+out vec2 uvs;
+out vec4 col;
+
+void main()
+{
+    vec2 p = textSize * position;
+#if defined(USE_BILLBOARD)
+    vec4 world_anchor = modelMatrix * vec4(anchor, 1.0);
+    pos = world_anchor.xyz / world_anchor.w + p.x * cameraRight + p.y * cameraUp;
+    gl_Position = viewProjection * vec4(pos, 1.0);
+#elif defined(USE_LABEL)
+    vec4 world_anchor = modelMatrix * vec4(anchor, 1.0);
+    pos = world_anchor.xyz / world_anchor.w;
+    vec4 clip = viewProjection * world_anchor;
+    gl_Position = clip + vec4(2.0 * p / viewportSize * clip.w, 0.0, 0.0);
+#else
+    vec4 world = modelMatrix * vec4(p, 0.0, 1.0);
+    pos = world.xyz / world.w;
+    gl_Position = viewProjection * world;
+#endif
+    nor = normalize(eye - pos);
+    uvs = uv_coordinates;
+    col = vec4(1.0);
+}
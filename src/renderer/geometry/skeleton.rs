@@ -0,0 +1,233 @@
+use crate::core::*;
+use crate::renderer::*;
+
+use super::animation::blend_transformations;
+
+///
+/// The maximum number of joints in a [Skeleton] that can be used for skinning a [Mesh] or [InstancedMesh].
+///
+pub const MAX_JOINTS: usize = 128;
+
+///
+/// A joint (or bone) in a [Skeleton].
+///
+#[derive(Clone, Debug)]
+pub struct Joint {
+    /// The name of the joint.
+    pub name: Option<String>,
+    /// The index of the parent joint in [Skeleton::joints] or `None` if this is a root joint.
+    /// The parent must be placed before the child in [Skeleton::joints].
+    pub parent: Option<usize>,
+    /// The transformation of this joint relative to the parent joint when no animation is applied.
+    /// For root joints, the transformation is relative to the local space of the skinned mesh.
+    pub transformation: Mat4,
+    /// The inverse of the global transformation of this joint in the pose in which the mesh is bound to the skeleton, the bind pose.
+    pub inverse_bind_matrix: Mat4,
+    /// A set of animations of this joint.
+    /// The chosen animation replaces [Joint::transformation] while animating.
+    pub animations: Vec<KeyFrameAnimation>,
+}
+
+impl Default for Joint {
+    fn default() -> Self {
+        Self {
+            name: None,
+            parent: None,
+            transformation: Mat4::identity(),
+            inverse_bind_matrix: Mat4::identity(),
+            animations: Vec::new(),
+        }
+    }
+}
+
+///
+/// A hierarchy of [Joint]s which deforms a skinned [Mesh] or [InstancedMesh] (see [Mesh::set_skin] and [InstancedMesh::set_skin]).
+///
+/// The skins of a glTF file are not available in a [CpuModel], so a [Model] is rendered in the bind pose
+/// and the skeleton, joint indices and joint weights must be constructed and set on the meshes manually.
+///
+#[derive(Clone, Debug, Default)]
+pub struct Skeleton {
+    /// The joints of the skeleton, ordered such that a parent joint is placed before its children.
+    pub joints: Vec<Joint>,
+}
+
+impl Skeleton {
+    ///
+    /// Returns a list of unique names for the animations of the joints in this skeleton.
+    ///
+    pub fn animations(&self) -> Vec<Option<String>> {
+        let mut names = Vec::new();
+        for joint in self.joints.iter() {
+            for animation in joint.animations.iter() {
+                if !names.contains(&animation.name) {
+                    names.push(animation.name.clone());
+                }
+            }
+        }
+        names
+    }
+
+    ///
+    /// Returns the joint matrices, one for each joint, at the given time using the animation with the given name.
+    /// A joint matrix transforms a vertex from the bind pose to the animated pose in the local space of the skinned mesh.
+    /// Joints without an animation with the given name use their [Joint::transformation].
+    ///
+    pub fn joint_matrices(&self, animation_name: Option<&str>, time: f32) -> Vec<Mat4> {
+        self.blended_joint_matrices(&[AnimationSample {
+            name: animation_name.map(|name| name.to_owned()),
+            time,
+            weight: 1.0,
+        }])
+    }
+
+    ///
+    /// Returns the joint matrices, one for each joint, where the local transformation of each joint is blended from the given animation samples.
+    /// Joints without an animation with the name of a sample use their [Joint::transformation] for that sample.
+    ///
+    pub fn blended_joint_matrices(&self, samples: &[AnimationSample]) -> Vec<Mat4> {
+        let mut global_transformations: Vec<Mat4> = Vec::with_capacity(self.joints.len());
+        for (i, joint) in self.joints.iter().enumerate() {
+            let local_transformation = if samples.is_empty() {
+                joint.transformation
+            } else {
+                blend_transformations(
+                    &samples
+                        .iter()
+                        .map(|sample| {
+                            (
+                                joint
+                                    .animations
+                                    .iter()
+                                    .find(|a| sample.name == a.name)
+                                    .map(|a| a.transformation(sample.time))
+                                    .unwrap_or(joint.transformation),
+                                sample.weight,
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            };
+            let global_transformation = match joint.parent {
+                Some(parent) => {
+                    if parent >= i {
+                        panic!(
+                            "the parent ({}) of joint {} must be placed before the joint",
+                            parent, i
+                        );
+                    }
+                    global_transformations[parent] * local_transformation
+                }
+                None => local_transformation,
+            };
+            global_transformations.push(global_transformation);
+        }
+        global_transformations
+            .into_iter()
+            .zip(self.joints.iter())
+            .map(|(global_transformation, joint)| global_transformation * joint.inverse_bind_matrix)
+            .collect()
+    }
+}
+
+crate::uniform_block! {
+    struct JointMatrices {
+        matrices: [Mat4; MAX_JOINTS],
+    }
+}
+
+pub(super) struct Skin {
+    skeleton: Skeleton,
+    animation: Option<String>,
+    joint_matrices: UniformBlockBuffer<JointMatrices>,
+}
+
+impl Skin {
+    pub fn new(context: &Context, skeleton: Skeleton) -> Self {
+        if skeleton.joints.len() > MAX_JOINTS {
+            panic!(
+                "the skeleton has {} joints but at most {} joints are supported",
+                skeleton.joints.len(),
+                MAX_JOINTS
+            );
+        }
+        let animation = skeleton.animations().first().cloned().flatten();
+        let mut skin = Self {
+            skeleton,
+            animation,
+            joint_matrices: UniformBlockBuffer::new(
+                context,
+                JointMatrices {
+                    matrices: [Mat4::identity(); MAX_JOINTS],
+                },
+            ),
+        };
+        skin.animate(0.0);
+        skin
+    }
+
+    pub fn skeleton(&self) -> &Skeleton {
+        &self.skeleton
+    }
+
+    pub fn choose_animation(&mut self, animation_name: Option<&str>) {
+        self.animation = animation_name.map(|name| name.to_owned());
+    }
+
+    pub fn animate(&mut self, time: f32) {
+        self.animate_blended(&[AnimationSample {
+            name: self.animation.clone(),
+            time,
+            weight: 1.0,
+        }]);
+    }
+
+    pub fn animate_blended(&mut self, samples: &[AnimationSample]) {
+        let mut matrices = [Mat4::identity(); MAX_JOINTS];
+        for (matrix, joint_matrix) in matrices
+            .iter_mut()
+            .zip(self.skeleton.blended_joint_matrices(samples))
+        {
+            *matrix = joint_matrix;
+        }
+        self.joint_matrices.update(JointMatrices { matrices });
+    }
+
+    pub fn use_uniforms(&self, program: &Program) {
+        program.use_uniform_block_buffer("JointMatrices", &self.joint_matrices);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn joint_matrices_in_bind_pose_are_identity() {
+        let root = Mat4::from_translation(vec3(0.0, 1.0, 0.0));
+        let child = Mat4::from_angle_z(degrees(90.0));
+        let skeleton = Skeleton {
+            joints: vec![
+                Joint {
+                    transformation: root,
+                    inverse_bind_matrix: root.invert().unwrap(),
+                    ..Default::default()
+                },
+                Joint {
+                    parent: Some(0),
+                    transformation: child,
+                    inverse_bind_matrix: (root * child).invert().unwrap(),
+                    ..Default::default()
+                },
+            ],
+        };
+        for matrix in skeleton.joint_matrices(None, 0.0) {
+            for i in 0..4 {
+                for j in 0..4 {
+                    let expected = if i == j { 1.0 } else { 0.0 };
+                    assert!((matrix[i][j] - expected).abs() < 0.0001);
+                }
+            }
+        }
+    }
+}
@@ -85,7 +85,7 @@ impl Sprites {
 
     fn draw(&self, program: &Program, render_states: RenderStates, camera: &Camera) {
         program.use_uniform("eye", camera.position());
-        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_camera_block(camera);
         program.use_uniform("transformation", self.transformation);
         program.use_vertex_attribute("position", &self.position_buffer);
         program.use_vertex_attribute("uv_coordinate", &self.uv_buffer);
@@ -94,6 +94,7 @@ impl Sprites {
         program.draw_arrays_instanced(
             render_states,
             camera.viewport(),
+            PrimitiveTopology::Triangles,
             6,
             self.center_buffer.instance_count(),
         )
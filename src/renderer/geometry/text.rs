@@ -0,0 +1,798 @@
+use crate::core::*;
+use crate::renderer::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The size of an em in the signed distance field atlas in pixels.
+const GLYPH_SIZE: f32 = 48.0;
+
+/// The distance in pixels from the outline of a glyph where the signed distance field is saturated.
+const SPREAD: f32 = 6.0;
+
+/// The width of the signed distance field atlas in pixels.
+const ATLAS_WIDTH: usize = 1024;
+
+/// The number of line segments each curve in the outline of a glyph is divided into.
+const CURVE_SEGMENTS: usize = 8;
+
+#[derive(Clone, Copy, Debug)]
+struct Glyph {
+    /// The bottom left corner of the glyph quad relative to the pen position in ems.
+    min: Vec2,
+    /// The top right corner of the glyph quad relative to the pen position in ems.
+    max: Vec2,
+    uv_min: Vec2,
+    uv_max: Vec2,
+    /// The distance to move the pen after the glyph in ems.
+    advance: f32,
+    /// Whether the glyph has an outline, a space for example does not.
+    visible: bool,
+}
+
+///
+/// A font where the glyphs are rasterised into a signed distance field atlas which makes it possible to render [Text] sharply at any size.
+///
+pub struct Font {
+    atlas: Texture2D,
+    glyphs: HashMap<char, Glyph>,
+    kerning: HashMap<(char, char), f32>,
+    line_height: f32,
+}
+
+impl Font {
+    ///
+    /// Parses the given TTF or OTF font data and rasterises the glyphs for the given characters into a signed distance field atlas.
+    /// Characters not in the font and characters not given here are skipped when laying out text.
+    ///
+    /// ```no_run
+    /// # use three_d::*;
+    /// # let context: Context = unimplemented!();
+    /// # let font_data: Vec<u8> = unimplemented!();
+    /// let ascii = (' '..='~').collect::<String>();
+    /// let font = Font::new(&context, &font_data, &ascii).unwrap();
+    /// ```
+    ///
+    pub fn new(
+        context: &Context,
+        font_data: &[u8],
+        characters: &str,
+    ) -> Result<Self, RendererError> {
+        let face = ttf_parser::Face::parse(font_data, 0)
+            .map_err(|e| RendererError::FontParsing(e.to_string()))?;
+        let units_per_em = face.units_per_em() as f32;
+        let scale = GLYPH_SIZE / units_per_em;
+
+        let mut characters = characters.chars().collect::<Vec<_>>();
+        characters.sort();
+        characters.dedup();
+
+        // Rasterise the signed distance field of each glyph
+        let mut bitmaps = Vec::new();
+        let mut glyphs = HashMap::new();
+        for c in characters.iter() {
+            let id = match face.glyph_index(*c) {
+                Some(id) => id,
+                None => continue,
+            };
+            let advance = face.glyph_hor_advance(id).unwrap_or(0) as f32 / units_per_em;
+            let mut outline = Outline::default();
+            match face.outline_glyph(id, &mut outline) {
+                Some(rect) if !outline.segments.is_empty() => {
+                    let width = ((rect.x_max - rect.x_min) as f32 * scale).ceil() as usize
+                        + 2 * SPREAD as usize;
+                    let height = ((rect.y_max - rect.y_min) as f32 * scale).ceil() as usize
+                        + 2 * SPREAD as usize;
+                    // Transform the outline to pixel coordinates with the top row first
+                    let segments = outline
+                        .segments
+                        .iter()
+                        .map(|(a, b)| {
+                            let to_pixels = |p: Vec2| {
+                                vec2(
+                                    (p.x - rect.x_min as f32) * scale + SPREAD,
+                                    (rect.y_max as f32 - p.y) * scale + SPREAD,
+                                )
+                            };
+                            (to_pixels(*a), to_pixels(*b))
+                        })
+                        .collect::<Vec<_>>();
+                    bitmaps.push((
+                        *c,
+                        width,
+                        height,
+                        signed_distance_field(&segments, width, height),
+                    ));
+                    let min = vec2(
+                        rect.x_min as f32 / units_per_em - SPREAD / GLYPH_SIZE,
+                        rect.y_max as f32 / units_per_em + SPREAD / GLYPH_SIZE
+                            - height as f32 / GLYPH_SIZE,
+                    );
+                    glyphs.insert(
+                        *c,
+                        Glyph {
+                            min,
+                            max: min + vec2(width as f32, height as f32) / GLYPH_SIZE,
+                            uv_min: vec2(0.0, 0.0),
+                            uv_max: vec2(0.0, 0.0),
+                            advance,
+                            visible: true,
+                        },
+                    );
+                }
+                _ => {
+                    glyphs.insert(
+                        *c,
+                        Glyph {
+                            min: vec2(0.0, 0.0),
+                            max: vec2(0.0, 0.0),
+                            uv_min: vec2(0.0, 0.0),
+                            uv_max: vec2(0.0, 0.0),
+                            advance,
+                            visible: false,
+                        },
+                    );
+                }
+            }
+        }
+
+        // Pack the glyphs into rows in the atlas
+        let mut placements = Vec::new();
+        let (mut x, mut y, mut row_height) = (0, 0, 0);
+        for (c, width, height, _) in bitmaps.iter() {
+            if x + width > ATLAS_WIDTH {
+                x = 0;
+                y += row_height;
+                row_height = 0;
+            }
+            placements.push((*c, x, y));
+            x += width;
+            row_height = row_height.max(*height);
+        }
+        let atlas_height = (y + row_height).max(1);
+        let mut data = vec![0u8; ATLAS_WIDTH * atlas_height];
+        for ((c, x, y), (_, width, height, bitmap)) in placements.into_iter().zip(bitmaps.iter()) {
+            for row in 0..*height {
+                data[(y + row) * ATLAS_WIDTH + x..(y + row) * ATLAS_WIDTH + x + width]
+                    .copy_from_slice(&bitmap[row * width..(row + 1) * width]);
+            }
+            // The texture is flipped when filled, so the first row ends up at the top
+            let glyph = glyphs.get_mut(&c).unwrap();
+            glyph.uv_min = vec2(
+                x as f32 / ATLAS_WIDTH as f32,
+                1.0 - (y + height) as f32 / atlas_height as f32,
+            );
+            glyph.uv_max = vec2(
+                (x + width) as f32 / ATLAS_WIDTH as f32,
+                1.0 - y as f32 / atlas_height as f32,
+            );
+        }
+        let mut atlas = Texture2D::new_empty::<u8>(
+            context,
+            ATLAS_WIDTH as u32,
+            atlas_height as u32,
+            Interpolation::Linear,
+            Interpolation::Linear,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        atlas.fill(&data);
+
+        // Find the kerning between each pair of characters
+        let mut kerning = HashMap::new();
+        if let Some(kern) = face.tables().kern {
+            for left in characters.iter() {
+                for right in characters.iter() {
+                    if let (Some(l), Some(r)) = (face.glyph_index(*left), face.glyph_index(*right))
+                    {
+                        let k = kern
+                            .subtables
+                            .into_iter()
+                            .filter(|s| s.horizontal && !s.variable)
+                            .find_map(|s| s.glyphs_kerning(l, r));
+                        if let Some(k) = k {
+                            if k != 0 {
+                                kerning.insert((*left, *right), k as f32 / units_per_em);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            atlas,
+            glyphs,
+            kerning,
+            line_height: (face.ascender() - face.descender() + face.line_gap()) as f32
+                / units_per_em,
+        })
+    }
+
+    ///
+    /// Returns the signed distance field atlas containing all the glyphs of this font.
+    ///
+    pub fn atlas(&self) -> &Texture2D {
+        &self.atlas
+    }
+
+    ///
+    /// Returns the distance between two lines of text in ems.
+    ///
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    ///
+    /// Returns the width of the given single line of text in ems.
+    ///
+    pub fn text_width(&self, text: &str) -> f32 {
+        let mut width = 0.0;
+        let mut previous = None;
+        for c in text.chars() {
+            if let Some(glyph) = self.glyphs.get(&c) {
+                width += self.kerning(previous, c) + glyph.advance;
+                previous = Some(c);
+            }
+        }
+        width
+    }
+
+    fn kerning(&self, previous: Option<char>, c: char) -> f32 {
+        previous
+            .and_then(|p| self.kerning.get(&(p, c)))
+            .cloned()
+            .unwrap_or(0.0)
+    }
+
+    ///
+    /// Splits the text into lines at line breaks and, if a maximum width is given, between words where a line exceeds the maximum width.
+    ///
+    fn lines(&self, text: &str, max_width: Option<f32>) -> Vec<String> {
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let max_width = match max_width {
+                Some(max_width) => max_width,
+                None => {
+                    lines.push(paragraph.to_owned());
+                    continue;
+                }
+            };
+            let mut line = String::new();
+            for word in paragraph.split(' ') {
+                let candidate = if line.is_empty() {
+                    word.to_owned()
+                } else {
+                    format!("{} {}", line, word)
+                };
+                if !line.is_empty() && self.text_width(&candidate) > max_width {
+                    lines.push(std::mem::replace(&mut line, word.to_owned()));
+                } else {
+                    line = candidate;
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
+}
+
+struct Outline {
+    segments: Vec<(Vec2, Vec2)>,
+    start: Vec2,
+    current: Vec2,
+}
+
+impl Default for Outline {
+    fn default() -> Self {
+        Self {
+            segments: Vec::new(),
+            start: vec2(0.0, 0.0),
+            current: vec2(0.0, 0.0),
+        }
+    }
+}
+
+impl ttf_parser::OutlineBuilder for Outline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.start = vec2(x, y);
+        self.current = self.start;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let p = vec2(x, y);
+        self.segments.push((self.current, p));
+        self.current = p;
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let p0 = self.current;
+        for i in 1..=CURVE_SEGMENTS {
+            let t = i as f32 / CURVE_SEGMENTS as f32;
+            let p = (1.0 - t) * (1.0 - t) * p0
+                + 2.0 * (1.0 - t) * t * vec2(x1, y1)
+                + t * t * vec2(x, y);
+            self.line_to(p.x, p.y);
+        }
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let p0 = self.current;
+        for i in 1..=CURVE_SEGMENTS {
+            let t = i as f32 / CURVE_SEGMENTS as f32;
+            let s = 1.0 - t;
+            let p = s * s * s * p0
+                + 3.0 * s * s * t * vec2(x1, y1)
+                + 3.0 * s * t * t * vec2(x2, y2)
+                + t * t * t * vec2(x, y);
+            self.line_to(p.x, p.y);
+        }
+    }
+
+    fn close(&mut self) {
+        if self.current != self.start {
+            self.line_to(self.start.x, self.start.y);
+        }
+    }
+}
+
+///
+/// Computes the signed distance field of the closed outline given by the line segments, in pixel coordinates, in a bitmap of the given size.
+/// The value 128 is at the outline and increases inside the outline.
+///
+fn signed_distance_field(segments: &[(Vec2, Vec2)], width: usize, height: usize) -> Vec<u8> {
+    let mut data = vec![0u8; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let p = vec2(x as f32 + 0.5, y as f32 + 0.5);
+            let mut distance = f32::MAX;
+            let mut winding = 0;
+            for (a, b) in segments {
+                let ab = b - a;
+                let t = ((p - a).dot(ab) / ab.magnitude2().max(f32::EPSILON)).clamp(0.0, 1.0);
+                distance = distance.min((a + t * ab - p).magnitude());
+                let cross = ab.x * (p.y - a.y) - ab.y * (p.x - a.x);
+                if a.y <= p.y && b.y > p.y && cross > 0.0 {
+                    winding += 1;
+                } else if b.y <= p.y && a.y > p.y && cross < 0.0 {
+                    winding -= 1;
+                }
+            }
+            let signed_distance = if winding != 0 { distance } else { -distance };
+            data[y * width + x] =
+                ((0.5 + 0.5 * signed_distance / SPREAD).clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+    }
+    data
+}
+
+///
+/// Defines how [Text] is placed in the scene.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum TextPlacement {
+    /// The text is placed in the xy-plane of the local coordinate system defined by the transformation and the text size is in world units per em.
+    /// Use this with the [camera2d] camera to render text in screen space with the text size in pixels per em.
+    #[default]
+    World,
+    /// The text is a billboard at the given position, ie. it always faces the camera, and the text size is in world units per em.
+    Billboard(Vec3),
+    /// The text is a label at the given position which always faces the camera and the text size is in pixels per em, independent of the distance to the camera.
+    Label(Vec3),
+}
+
+///
+/// The horizontal alignment of the lines of a [Text].
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TextAlignment {
+    /// The lines start at the origin.
+    #[default]
+    Left,
+    /// The lines are centered around the origin.
+    Center,
+    /// The lines end at the origin.
+    Right,
+}
+
+///
+/// A text [Geometry] where the glyphs are taken from a signed distance field [Font] atlas and laid out with kerning and line breaks.
+/// The origin of the text is on the baseline of the first line and the lines are placed below each other.
+/// Use a [TextMaterial] with the same font to render the text.
+///
+pub struct Text {
+    context: Context,
+    font: Arc<Font>,
+    positions: VertexBuffer,
+    uvs: VertexBuffer,
+    text: String,
+    size: f32,
+    max_width: Option<f32>,
+    alignment: TextAlignment,
+    placement: TextPlacement,
+    transformation: Mat4,
+    aabb_local: AxisAlignedBoundingBox,
+}
+
+impl Text {
+    ///
+    /// Creates a new text geometry with the given text laid out using the glyphs of the given font with the given size per em, see [TextPlacement] for the unit.
+    ///
+    pub fn new(context: &Context, font: Arc<Font>, text: &str, size: f32) -> Self {
+        let mut t = Self {
+            context: context.clone(),
+            font,
+            positions: VertexBuffer::new(context),
+            uvs: VertexBuffer::new(context),
+            text: String::new(),
+            size,
+            max_width: None,
+            alignment: TextAlignment::default(),
+            placement: TextPlacement::default(),
+            transformation: Mat4::identity(),
+            aabb_local: AxisAlignedBoundingBox::EMPTY,
+        };
+        t.set_text(text);
+        t
+    }
+
+    ///
+    /// Returns the text.
+    ///
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    ///
+    /// Sets the text. A line break starts a new line.
+    ///
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_owned();
+        self.update_buffers();
+    }
+
+    ///
+    /// Returns the size of the text per em, see [TextPlacement] for the unit.
+    ///
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    ///
+    /// Sets the size of the text per em, see [TextPlacement] for the unit.
+    ///
+    pub fn set_size(&mut self, size: f32) {
+        self.size = size;
+    }
+
+    ///
+    /// Sets the maximum width of a line in ems before the line is broken between two words or `None` if lines should only be broken at line breaks.
+    ///
+    pub fn set_max_width(&mut self, max_width: Option<f32>) {
+        self.max_width = max_width;
+        self.update_buffers();
+    }
+
+    ///
+    /// Sets the horizontal alignment of the lines.
+    ///
+    pub fn set_alignment(&mut self, alignment: TextAlignment) {
+        self.alignment = alignment;
+        self.update_buffers();
+    }
+
+    ///
+    /// Returns how the text is placed in the scene.
+    ///
+    pub fn placement(&self) -> TextPlacement {
+        self.placement
+    }
+
+    ///
+    /// Sets how the text is placed in the scene.
+    ///
+    pub fn set_placement(&mut self, placement: TextPlacement) {
+        self.placement = placement;
+    }
+
+    ///
+    /// Returns the local to world transformation applied to the text.
+    ///
+    pub fn transformation(&self) -> Mat4 {
+        self.transformation
+    }
+
+    ///
+    /// Set the local to world transformation applied to the text.
+    /// For billboards and labels, the transformation is applied to the position of the text.
+    ///
+    pub fn set_transformation(&mut self, transformation: Mat4) {
+        self.transformation = transformation;
+    }
+
+    fn update_buffers(&mut self) {
+        let mut positions = Vec::new();
+        let mut uvs = Vec::new();
+        for (i, line) in self
+            .font
+            .lines(&self.text, self.max_width)
+            .iter()
+            .enumerate()
+        {
+            let mut pen = vec2(
+                match self.alignment {
+                    TextAlignment::Left => 0.0,
+                    TextAlignment::Center => -0.5 * self.font.text_width(line),
+                    TextAlignment::Right => -self.font.text_width(line),
+                },
+                -(i as f32) * self.font.line_height,
+            );
+            let mut previous = None;
+            for c in line.chars() {
+                let glyph = match self.font.glyphs.get(&c) {
+                    Some(glyph) => glyph,
+                    None => continue,
+                };
+                pen.x += self.font.kerning(previous, c);
+                if glyph.visible {
+                    let (min, max) = (pen + glyph.min, pen + glyph.max);
+                    positions.extend_from_slice(&[
+                        min,
+                        vec2(max.x, min.y),
+                        max,
+                        max,
+                        vec2(min.x, max.y),
+                        min,
+                    ]);
+                    let (uv_min, uv_max) = (glyph.uv_min, glyph.uv_max);
+                    uvs.extend_from_slice(&[
+                        uv_min,
+                        vec2(uv_max.x, uv_min.y),
+                        uv_max,
+                        uv_max,
+                        vec2(uv_min.x, uv_max.y),
+                        uv_min,
+                    ]);
+                }
+                pen.x += glyph.advance;
+                previous = Some(c);
+            }
+        }
+        self.aabb_local = AxisAlignedBoundingBox::new_with_positions(
+            &positions
+                .iter()
+                .map(|p| vec3(p.x, p.y, 0.0))
+                .collect::<Vec<_>>(),
+        );
+        self.positions.fill(&positions);
+        self.uvs.fill(&uvs);
+    }
+
+    fn vertex_shader_source(&self) -> String {
+        format!(
+            "{}{}",
+            match self.placement {
+                TextPlacement::World => "",
+                TextPlacement::Billboard(_) => "#define USE_BILLBOARD\n",
+                TextPlacement::Label(_) => "#define USE_LABEL\n",
+            },
+            include_str!("shaders/text.vert")
+        )
+    }
+
+    fn draw(&self, program: &Program, render_states: RenderStates, camera: &Camera) {
+        let viewport = camera.viewport();
+        program.use_camera_block(camera);
+        program.use_uniform("modelMatrix", self.transformation);
+        program.use_uniform_if_required("eye", camera.position());
+        program.use_uniform("textSize", self.size);
+        match self.placement {
+            TextPlacement::World => {}
+            TextPlacement::Billboard(anchor) => {
+                let view = camera.view();
+                program.use_uniform("anchor", anchor);
+                program.use_uniform("cameraRight", vec3(view.x.x, view.y.x, view.z.x));
+                program.use_uniform("cameraUp", vec3(view.x.y, view.y.y, view.z.y));
+            }
+            TextPlacement::Label(anchor) => {
+                program.use_uniform("anchor", anchor);
+                program.use_uniform(
+                    "viewportSize",
+                    vec2(viewport.width as f32, viewport.height as f32),
+                );
+            }
+        }
+        program.use_vertex_attribute("position", &self.positions);
+        program.use_vertex_attribute("uv_coordinates", &self.uvs);
+        program.draw_arrays(
+            render_states,
+            viewport,
+            PrimitiveTopology::Triangles,
+            self.positions.vertex_count(),
+        );
+    }
+}
+
+impl<'a> IntoIterator for &'a Text {
+    type Item = &'a dyn Geometry;
+    type IntoIter = std::iter::Once<&'a dyn Geometry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        std::iter::once(self)
+    }
+}
+
+impl Geometry for Text {
+    fn render_with_material(
+        &self,
+        material: &dyn Material,
+        camera: &Camera,
+        lights: &[&dyn Light],
+    ) {
+        if self.positions.vertex_count() == 0 {
+            return;
+        }
+        let fragment_shader = material.fragment_shader(lights);
+        if fragment_shader.attributes.tangents {
+            panic!("a Text cannot be rendered with a material that requires tangents");
+        }
+        self.context
+            .program(
+                self.vertex_shader_source(),
+                fragment_shader.source,
+                |program| {
+                    material.use_uniforms(program, camera, lights);
+                    self.draw(program, material.render_states(), camera);
+                },
+            )
+            .expect("Failed compiling shader")
+    }
+
+    fn render_with_post_material(
+        &self,
+        material: &dyn PostMaterial,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        if self.positions.vertex_count() == 0 {
+            return;
+        }
+        let fragment_shader = material.fragment_shader(lights, color_texture, depth_texture);
+        if fragment_shader.attributes.tangents {
+            panic!("a Text cannot be rendered with a material that requires tangents");
+        }
+        self.context
+            .program(
+                self.vertex_shader_source(),
+                fragment_shader.source,
+                |program| {
+                    material.use_uniforms(program, camera, lights, color_texture, depth_texture);
+                    self.draw(program, material.render_states(), camera);
+                },
+            )
+            .expect("Failed compiling shader")
+    }
+
+    fn aabb(&self) -> AxisAlignedBoundingBox {
+        if self.aabb_local.is_empty() {
+            return AxisAlignedBoundingBox::EMPTY;
+        }
+        let extent = self.size
+            * self
+                .aabb_local
+                .min()
+                .magnitude()
+                .max(self.aabb_local.max().magnitude());
+        match self.placement {
+            TextPlacement::World => {
+                let mut aabb = self.aabb_local;
+                aabb.transform(&(self.transformation * Mat4::from_scale(self.size)));
+                aabb
+            }
+            TextPlacement::Billboard(anchor) => {
+                let center = (self.transformation * anchor.extend(1.0)).truncate();
+                let extent = vec3(extent, extent, extent);
+                AxisAlignedBoundingBox::new_with_positions(&[center - extent, center + extent])
+            }
+            TextPlacement::Label(_) => AxisAlignedBoundingBox::INFINITE,
+        }
+    }
+}
+
+///
+/// A material for rendering [Text] which uses the signed distance field atlas of a [Font] to render sharp glyphs at any size.
+/// The font should be the same as the font used by the text.
+/// This material is not affected by lights.
+///
+#[derive(Clone)]
+pub struct TextMaterial {
+    /// The font which contains the signed distance field atlas.
+    pub font: Arc<Font>,
+    /// The color of the text. Assumed to be in linear color space.
+    pub color: Color,
+    /// An optional outline around the glyphs given by the color and the width of the outline in ems.
+    /// The width is at most 0.125 em.
+    pub outline: Option<(Color, f32)>,
+    /// Render states.
+    pub render_states: RenderStates,
+}
+
+impl TextMaterial {
+    ///
+    /// Creates a new text material with the given font and color.
+    ///
+    pub fn new(font: Arc<Font>, color: Color) -> Self {
+        Self {
+            font,
+            color,
+            outline: None,
+            render_states: RenderStates {
+                write_mask: WriteMask::COLOR,
+                blend: Blend::TRANSPARENCY,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Material for TextMaterial {
+    fn fragment_shader(&self, _lights: &[&dyn Light]) -> FragmentShader {
+        FragmentShader {
+            source: format!(
+                "{}#include \"shared\"\n{}",
+                if self.outline.is_some() {
+                    "#define USE_OUTLINE\n"
+                } else {
+                    ""
+                },
+                include_str!("shaders/text.frag")
+            ),
+            attributes: FragmentAttributes {
+                uv: true,
+                color: true,
+                ..FragmentAttributes::NONE
+            },
+        }
+    }
+
+    fn use_uniforms(&self, program: &Program, _camera: &Camera, _lights: &[&dyn Light]) {
+        program.use_uniform("surfaceColor", self.color);
+        program.use_texture("sdfAtlas", self.font.atlas());
+        if let Some((color, width)) = self.outline {
+            program.use_uniform("outlineColor", color);
+            program.use_uniform("outlineWidth", (0.5 * width * GLYPH_SIZE / SPREAD).min(0.5));
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Transparent
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn signed_distance_field_of_square() {
+        let corners = [
+            vec2(2.0, 2.0),
+            vec2(6.0, 2.0),
+            vec2(6.0, 6.0),
+            vec2(2.0, 6.0),
+        ];
+        let segments = (0..4)
+            .map(|i| (corners[i], corners[(i + 1) % 4]))
+            .collect::<Vec<_>>();
+        let sdf = signed_distance_field(&segments, 8, 8);
+        assert!(sdf[3 * 8 + 3] > 128);
+        assert!(sdf[0] < 128);
+        assert!(sdf[3 * 8 + 3] > sdf[3 * 8 + 2]);
+    }
+}
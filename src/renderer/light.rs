@@ -60,6 +60,11 @@ pub trait Light {
     fn shader_source(&self, i: u32) -> String;
     /// Should bind the uniforms that is needed for calculating this lights contribution to the color in [Light::shader_source].
     fn use_uniforms(&self, program: &Program, i: u32);
+    /// Returns whether this light represents indirect lighting, like the [AmbientLight], which can be replaced by baked lighting, see [LightmapMaterial].
+    /// The default is false.
+    fn is_indirect(&self) -> bool {
+        false
+    }
 }
 
 impl<T: Light + ?Sized> Light for &T {
@@ -69,6 +74,9 @@ impl<T: Light + ?Sized> Light for &T {
     fn use_uniforms(&self, program: &Program, i: u32) {
         (*self).use_uniforms(program, i)
     }
+    fn is_indirect(&self) -> bool {
+        (*self).is_indirect()
+    }
 }
 
 impl<T: Light + ?Sized> Light for &mut T {
@@ -78,6 +86,9 @@ impl<T: Light + ?Sized> Light for &mut T {
     fn use_uniforms(&self, program: &Program, i: u32) {
         (**self).use_uniforms(program, i)
     }
+    fn is_indirect(&self) -> bool {
+        (**self).is_indirect()
+    }
 }
 
 impl<T: Light> Light for Box<T> {
@@ -87,6 +98,9 @@ impl<T: Light> Light for Box<T> {
     fn use_uniforms(&self, program: &Program, i: u32) {
         self.as_ref().use_uniforms(program, i)
     }
+    fn is_indirect(&self) -> bool {
+        self.as_ref().is_indirect()
+    }
 }
 
 impl<T: Light> Light for std::sync::Arc<T> {
@@ -96,6 +110,9 @@ impl<T: Light> Light for std::sync::Arc<T> {
     fn use_uniforms(&self, program: &Program, i: u32) {
         self.as_ref().use_uniforms(program, i)
     }
+    fn is_indirect(&self) -> bool {
+        self.as_ref().is_indirect()
+    }
 }
 
 impl<T: Light> Light for std::sync::Arc<std::sync::RwLock<T>> {
@@ -105,11 +122,16 @@ impl<T: Light> Light for std::sync::Arc<std::sync::RwLock<T>> {
     fn use_uniforms(&self, program: &Program, i: u32) {
         self.read().unwrap().use_uniforms(program, i)
     }
+    fn is_indirect(&self) -> bool {
+        self.read().unwrap().is_indirect()
+    }
 }
 
 ///
 /// Returns shader source code with the function `calculate_lighting` which calculate the lighting contribution for the given lights and the given [LightingModel].
 /// Use this if you want to implement a custom [Material](crate::renderer::Material) but use the default lighting calculations.
+/// The returned source includes the `shared` and `light_shared` shader chunks (see [Context::set_shader_chunk]),
+/// so these can also be included in the rest of the material source without being defined twice.
 ///
 /// The shader function has the following signature:
 /// ```no_rust
@@ -118,8 +140,7 @@ impl<T: Light> Light for std::sync::Arc<std::sync::RwLock<T>> {
 ///
 pub fn lights_shader_source(lights: &[&dyn Light], lighting_model: LightingModel) -> String {
     let mut shader_source = lighting_model_shader(lighting_model).to_string();
-    shader_source.push_str(include_str!("../core/shared.frag"));
-    shader_source.push_str(include_str!("light/shaders/light_shared.frag"));
+    shader_source.push_str("\n#include \"light_shared\"\n");
     let mut dir_fun = String::new();
     for (i, light) in lights.iter().enumerate() {
         shader_source.push_str(&light.shader_source(i as u32));
@@ -96,6 +96,9 @@ impl Light for AmbientLight {
         }
         program.use_uniform("ambientColor", self.color.to_vec3() * self.intensity);
     }
+    fn is_indirect(&self) -> bool {
+        true
+    }
 }
 
 impl Default for AmbientLight {
@@ -56,6 +56,7 @@ impl DirectionalLight {
         texture_size: u32,
         geometries: impl IntoIterator<Item = impl Geometry> + Clone,
     ) {
+        let _scope = self.context.profile_scope("directional light shadow map");
         let up = compute_up_direction(self.direction);
 
         let viewport = Viewport::new_at_origo(texture_size, texture_size);
@@ -94,17 +95,19 @@ impl DirectionalLight {
             },
             ..Default::default()
         };
-        shadow_texture
-            .as_depth_target()
-            .clear(ClearState::default())
-            .write(|| {
-                for geometry in geometries
-                    .into_iter()
-                    .filter(|g| shadow_camera.in_frustum(&g.aabb()))
-                {
-                    geometry.render_with_material(&depth_material, &shadow_camera, &[]);
-                }
-            });
+        self.context.with_blocking_shader_compile(|| {
+            shadow_texture
+                .as_depth_target()
+                .clear(ClearState::default())
+                .write(|| {
+                    for geometry in geometries
+                        .into_iter()
+                        .filter(|g| shadow_camera.in_frustum(&g.aabb()))
+                    {
+                        geometry.render_with_material(&depth_material, &shadow_camera, &[]);
+                    }
+                });
+        });
         self.shadow_texture = Some(shadow_texture);
         self.shadow_matrix = shadow_matrix(&shadow_camera);
     }
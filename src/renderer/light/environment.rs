@@ -54,8 +54,7 @@ impl Environment {
         );
         {
             let fragment_shader_source = format!(
-                "{}{}",
-                include_str!("../../core/shared.frag"),
+                "#include \"shared\"\n{}",
                 include_str!("shaders/irradiance.frag")
             );
             let viewport = Viewport::new_at_origo(irradiance_size, irradiance_size);
@@ -64,16 +63,18 @@ impl Environment {
                     .as_color_target(&[side], None)
                     .clear(ClearState::default())
                     .write(|| {
-                        apply_cube_effect(
-                            context,
-                            side,
-                            &fragment_shader_source,
-                            RenderStates::default(),
-                            viewport,
-                            |program| {
-                                program.use_texture_cube("environmentMap", environment_map);
-                            },
-                        )
+                        context.with_blocking_shader_compile(|| {
+                            apply_cube_effect(
+                                context,
+                                side,
+                                &fragment_shader_source,
+                                RenderStates::default(),
+                                viewport,
+                                |program| {
+                                    program.use_texture_cube("environmentMap", environment_map);
+                                },
+                            )
+                        })
                     });
             }
         }
@@ -93,10 +94,8 @@ impl Environment {
         );
         {
             let fragment_shader_source = format!(
-                "{}{}{}{}",
+                "{}\n#include \"light_shared\"\n{}",
                 super::lighting_model_shader(lighting_model),
-                include_str!("../../core/shared.frag"),
-                include_str!("shaders/light_shared.frag"),
                 include_str!("shaders/prefilter.frag")
             );
             let max_mip_levels = 5;
@@ -107,21 +106,24 @@ impl Environment {
                     let viewport =
                         Viewport::new_at_origo(color_target.width(), color_target.height());
                     color_target.clear(ClearState::default()).write(|| {
-                        apply_cube_effect(
-                            context,
-                            side,
-                            &fragment_shader_source,
-                            RenderStates::default(),
-                            viewport,
-                            |program| {
-                                program.use_texture_cube("environmentMap", environment_map);
-                                program.use_uniform(
-                                    "roughness",
-                                    mip as f32 / (max_mip_levels as f32 - 1.0),
-                                );
-                                program.use_uniform("resolution", environment_map.width() as f32);
-                            },
-                        )
+                        context.with_blocking_shader_compile(|| {
+                            apply_cube_effect(
+                                context,
+                                side,
+                                &fragment_shader_source,
+                                RenderStates::default(),
+                                viewport,
+                                |program| {
+                                    program.use_texture_cube("environmentMap", environment_map);
+                                    program.use_uniform(
+                                        "roughness",
+                                        mip as f32 / (max_mip_levels as f32 - 1.0),
+                                    );
+                                    program
+                                        .use_uniform("resolution", environment_map.width() as f32);
+                                },
+                            )
+                        })
                     });
                 }
             }
@@ -143,19 +145,19 @@ impl Environment {
             .as_color_target(None)
             .clear(ClearState::default())
             .write(|| {
-                apply_effect(
-                    context,
-                    &format!(
-                        "{}{}{}{}",
-                        super::lighting_model_shader(lighting_model),
-                        include_str!("../../core/shared.frag"),
-                        include_str!("shaders/light_shared.frag"),
-                        include_str!("shaders/brdf.frag")
-                    ),
-                    RenderStates::default(),
-                    viewport,
-                    |_| {},
-                )
+                context.with_blocking_shader_compile(|| {
+                    apply_effect(
+                        context,
+                        &format!(
+                            "{}\n#include \"light_shared\"\n{}",
+                            super::lighting_model_shader(lighting_model),
+                            include_str!("shaders/brdf.frag")
+                        ),
+                        RenderStates::default(),
+                        viewport,
+                        |_| {},
+                    )
+                })
             });
 
         Self {
@@ -67,6 +67,7 @@ impl SpotLight {
         texture_size: u32,
         geometries: impl IntoIterator<Item = impl Geometry> + Clone,
     ) {
+        let _scope = self.context.profile_scope("spot light shadow map");
         let position = self.position;
         let direction = self.direction;
         let up = compute_up_direction(self.direction);
@@ -108,17 +109,19 @@ impl SpotLight {
             },
             ..Default::default()
         };
-        shadow_texture
-            .as_depth_target()
-            .clear(ClearState::default())
-            .write(|| {
-                for geometry in geometries
-                    .into_iter()
-                    .filter(|g| shadow_camera.in_frustum(&g.aabb()))
-                {
-                    geometry.render_with_material(&depth_material, &shadow_camera, &[]);
-                }
-            });
+        self.context.with_blocking_shader_compile(|| {
+            shadow_texture
+                .as_depth_target()
+                .clear(ClearState::default())
+                .write(|| {
+                    for geometry in geometries
+                        .into_iter()
+                        .filter(|g| shadow_camera.in_frustum(&g.aabb()))
+                    {
+                        geometry.render_with_material(&depth_material, &shadow_camera, &[]);
+                    }
+                });
+        });
         self.shadow_texture = Some(shadow_texture);
     }
 
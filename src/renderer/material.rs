@@ -43,6 +43,10 @@ mod physical_material;
 #[doc(inline)]
 pub use physical_material::*;
 
+mod lightmap_material;
+#[doc(inline)]
+pub use lightmap_material::*;
+
 mod deferred_physical_material;
 #[doc(inline)]
 pub use deferred_physical_material::*;
@@ -55,6 +59,14 @@ mod isosurface_material;
 #[doc(inline)]
 pub use isosurface_material::*;
 
+mod material_graph;
+#[doc(inline)]
+pub use material_graph::*;
+
+mod subsurface_material;
+#[doc(inline)]
+pub use subsurface_material::*;
+
 use std::sync::Arc;
 
 ///
@@ -97,6 +109,8 @@ pub enum MaterialType {
     Transparent,
     /// Deferred opaque
     Deferred,
+    /// Deferred opaque with screen-space subsurface scattering, see [SubsurfaceMaterial]
+    DeferredSubsurface,
 }
 
 ///
@@ -115,6 +129,8 @@ pub struct FragmentAttributes {
     pub uv: bool,
     /// Color: `in vec4 col;`
     pub color: bool,
+    /// A second set of UV coordinates, for example used for lightmaps: `in vec2 lightmap_uvs;`
+    pub lightmap_uv: bool,
 }
 
 impl FragmentAttributes {
@@ -125,6 +141,7 @@ impl FragmentAttributes {
         tangents: true,
         uv: true,
         color: true,
+        lightmap_uv: true,
     };
     /// No attributes
     pub const NONE: Self = Self {
@@ -133,6 +150,7 @@ impl FragmentAttributes {
         tangents: false,
         uv: false,
         color: false,
+        lightmap_uv: false,
     };
 }
 
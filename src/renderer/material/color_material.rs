@@ -92,8 +92,7 @@ impl Material for ColorMaterial {
             attributes.uv = true;
             shader.push_str("#define USE_TEXTURE\nin vec2 uvs;\n");
         }
-        shader.push_str(include_str!("../../core/shared.frag"));
-        shader.push_str(include_str!("shaders/color_material.frag"));
+        shader.push_str("#include \"shared\"\n#include \"color_material\"\n");
         FragmentShader {
             source: shader,
             attributes,
@@ -10,6 +10,7 @@ use std::sync::Arc;
 /// The [RenderTarget::render], [ColorTarget::render] or [DepthTarget::render] methods all support the two stages required by this material, so just pass the [Object] with this material applied into one of these methods.
 /// However, it is not possible to use the [Object::render] method to render a [Geometry] with this material directly to the screen.
 /// Instead render the object into a [RenderTarget] consisting of a [Texture2DArray] with three RGBA u8 layers as color target and a [DepthTexture2D] as depth target.
+/// A fourth layer is needed if any of the objects is rendered with a deferred [SubsurfaceMaterial].
 /// Then call the [DeferredPhysicalMaterial::lighting_pass] method with these textures to render to the screen.
 ///
 #[derive(Clone)]
@@ -143,16 +144,43 @@ impl DeferredPhysicalMaterial {
         geometry_pass_depth_texture: DepthTexture,
         lights: &[&dyn Light],
     ) {
-        let mut fragment_shader = lights_shader_source(
+        Self::lighting_pass_with_output(
+            context,
+            camera,
+            geometry_pass_color_texture,
+            geometry_pass_depth_texture,
+            lights,
+            false,
+        )
+    }
+
+    ///
+    /// Same as [DeferredPhysicalMaterial::lighting_pass], except that the output is linear radiance without tone mapping if `linear_output` is true,
+    /// which is needed when the lighting is processed further before it is tone mapped, for example by the subsurface scattering passes.
+    ///
+    pub(in crate::renderer) fn lighting_pass_with_output(
+        context: &Context,
+        camera: &Camera,
+        geometry_pass_color_texture: ColorTexture,
+        geometry_pass_depth_texture: DepthTexture,
+        lights: &[&dyn Light],
+        linear_output: bool,
+    ) {
+        let mut fragment_shader = if linear_output {
+            "#define LINEAR_OUTPUT\n".to_owned()
+        } else {
+            String::new()
+        };
+        fragment_shader.push_str(&lights_shader_source(
             lights,
             LightingModel::Cook(
                 NormalDistributionFunction::TrowbridgeReitzGGX,
                 GeometryFunction::SmithSchlickGGX,
             ),
-        );
+        ));
         fragment_shader.push_str(&geometry_pass_color_texture.fragment_shader_source());
         fragment_shader.push_str(&geometry_pass_depth_texture.fragment_shader_source());
-        fragment_shader.push_str(include_str!("shaders/deferred_lighting.frag"));
+        fragment_shader.push_str("\n#include \"deferred_lighting\"\n");
         apply_effect(
             context,
             &fragment_shader,
@@ -189,7 +217,7 @@ impl Material for DeferredPhysicalMaterial {
             color: true,
             ..FragmentAttributes::NONE
         };
-        let mut output = include_str!("../../core/shared.frag").to_string();
+        let mut output = "#include \"shared\"\n".to_string();
         if self.albedo_texture.is_some()
             || self.metallic_roughness_texture.is_some()
             || self.normal_texture.is_some()
@@ -225,7 +253,7 @@ impl Material for DeferredPhysicalMaterial {
                 );
             }
         }
-        output.push_str(include_str!("shaders/deferred_physical_material.frag"));
+        output.push_str("\n#include \"deferred_physical_material\"\n");
         FragmentShader {
             source: output,
             attributes,
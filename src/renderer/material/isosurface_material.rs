@@ -27,7 +27,7 @@ pub struct IsosurfaceMaterial {
 impl Material for IsosurfaceMaterial {
     fn fragment_shader(&self, lights: &[&dyn Light]) -> FragmentShader {
         let mut source = lights_shader_source(lights, self.lighting_model);
-        source.push_str(include_str!("shaders/isosurface_material.frag"));
+        source.push_str("\n#include \"isosurface_material\"\n");
         FragmentShader {
             source,
             attributes: FragmentAttributes {
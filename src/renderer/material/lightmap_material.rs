@@ -0,0 +1,69 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// Specifies how a lightmap, ie. a texture with baked lighting, is applied by a [LightmapMaterial].
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum LightmapMode {
+    /// The lightmap contains all of the indirect lighting, so the indirect lights, for example the [AmbientLight], are ignored
+    /// and the indirect lighting is the albedo multiplied by the lightmap instead.
+    /// The direct lights, for example the [DirectionalLight], are applied as usual.
+    #[default]
+    Replace,
+    /// The indirect lighting from the indirect lights, for example the [AmbientLight], is multiplied by the lightmap, for example to apply baked ambient occlusion.
+    /// The direct lights, for example the [DirectionalLight], are applied as usual.
+    Multiply,
+}
+
+///
+/// A [PhysicalMaterial] where the indirect lighting is replaced or multiplied by baked lighting from a lightmap, see [LightmapMode].
+/// The lightmap is sampled using the lightmap uv coordinates of the geometry, see for example [Mesh::set_lightmap_uvs].
+/// This material is affected by lights.
+///
+#[derive(Clone)]
+pub struct LightmapMaterial {
+    /// The physical material which defines the surface.
+    pub material: PhysicalMaterial,
+    /// A texture with baked lighting. Assumed to be in sRGB.
+    pub lightmap: Texture2DRef,
+    /// Specifies how the [Self::lightmap] is applied.
+    pub mode: LightmapMode,
+}
+
+impl LightmapMaterial {
+    ///
+    /// Constructs a new lightmap material from the given physical material and lightmap using the default [LightmapMode].
+    ///
+    pub fn new(material: PhysicalMaterial, lightmap: Texture2DRef) -> Self {
+        Self {
+            material,
+            lightmap,
+            mode: LightmapMode::default(),
+        }
+    }
+}
+
+impl Material for LightmapMaterial {
+    fn fragment_shader(&self, lights: &[&dyn Light]) -> FragmentShader {
+        self.material
+            .fragment_shader_with_lightmap(lights, Some(self.mode))
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        self.material.use_uniforms_with_lightmap(
+            program,
+            camera,
+            lights,
+            Some((&self.lightmap, self.mode)),
+        )
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.material.render_states()
+    }
+
+    fn material_type(&self) -> MaterialType {
+        self.material.material_type()
+    }
+}
@@ -0,0 +1,368 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// Identifies a node in a [MaterialGraph]. Returned when adding a node using [MaterialGraph::add].
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+///
+/// A node in a [MaterialGraph]. Each node evaluates to a `vec4` in the generated fragment shader.
+/// Nodes that represent a scalar or a vector with less than four components fill the remaining components with zero,
+/// except [SurfaceNode::Constant] which fills all components with the given value when created from a single float.
+///
+#[derive(Clone)]
+pub enum SurfaceNode {
+    /// A constant value. Changing the value does not require recompiling the shader.
+    Constant(Vec4),
+    /// The color of the vertex (multiplied by the instance color if the geometry is instanced).
+    VertexColor,
+    /// The uv coordinates in the `x` and `y` components.
+    Uv,
+    /// The position in world space in the `x`, `y` and `z` components.
+    Position,
+    /// The normal in world space in the `x`, `y` and `z` components.
+    Normal,
+    /// The value of the texture at the uv coordinates.
+    Texture {
+        /// The texture.
+        texture: Texture2DRef,
+        /// Whether the color channels of the texture are in sRGB color space and should be converted to linear color space.
+        srgb: bool,
+    },
+    /// The normal in world space in the `x`, `y` and `z` components after applying the tangent space normal map in the given texture.
+    NormalMap {
+        /// The tangent space normal map.
+        texture: Texture2DRef,
+        /// A scalar multiplier applied to each normal vector of the normal map.
+        scale: f32,
+    },
+    /// The sum of the two nodes.
+    Add(NodeId, NodeId),
+    /// The component-wise product of the two nodes.
+    Multiply(NodeId, NodeId),
+    /// A linear interpolation between the first and second node using the `x` component of the third node as weight.
+    Mix(NodeId, NodeId, NodeId),
+}
+
+impl From<f32> for SurfaceNode {
+    fn from(value: f32) -> Self {
+        Self::Constant(vec4(value, value, value, value))
+    }
+}
+
+impl From<Color> for SurfaceNode {
+    fn from(color: Color) -> Self {
+        Self::Constant(color.to_vec4())
+    }
+}
+
+///
+/// A material which fragment shader is generated from a graph of [SurfaceNode]s.
+/// The outputs of the graph, for example [MaterialGraph::albedo], are used as input to the same lighting calculations as in [PhysicalMaterial],
+/// so the result is affected by lights.
+///
+/// ```no_run
+/// # use three_d::*;
+/// # let texture: Texture2DRef = unimplemented!();
+/// let mut graph = MaterialGraph::default();
+/// let tint = graph.add(Color::new(255, 200, 200, 255));
+/// let albedo_texture = graph.add(SurfaceNode::Texture { texture, srgb: true });
+/// graph.albedo = Some(graph.add(SurfaceNode::Multiply(tint, albedo_texture)));
+/// graph.roughness = Some(graph.add(0.4));
+/// ```
+///
+#[derive(Clone)]
+pub struct MaterialGraph {
+    nodes: Vec<SurfaceNode>,
+    /// The node defining the albedo base color in linear color space and alpha. Defaults to white.
+    pub albedo: Option<NodeId>,
+    /// The node defining how metallic the surface is in the `x` component. Defaults to `0.0`.
+    pub metallic: Option<NodeId>,
+    /// The node defining how rough the surface is in the `x` component. Defaults to `1.0`.
+    pub roughness: Option<NodeId>,
+    /// The node defining the amount of indirect lighting the surface receives in the `x` component. Defaults to `1.0`.
+    pub occlusion: Option<NodeId>,
+    /// The node defining the normal in world space in the `x`, `y` and `z` components. Defaults to the normal of the geometry.
+    pub normal: Option<NodeId>,
+    /// The node defining the color of light shining from the surface in linear color space. Defaults to black.
+    pub emissive: Option<NodeId>,
+    /// Render states.
+    pub render_states: RenderStates,
+    /// Whether this material should be treated as a transparent material (An object needs to be rendered differently depending on whether it is transparent or opaque).
+    pub is_transparent: bool,
+    /// The lighting model used when rendering this material
+    pub lighting_model: LightingModel,
+}
+
+impl MaterialGraph {
+    ///
+    /// Adds the node to the graph and returns the id which can be used as input to other nodes or as an output of the graph.
+    ///
+    /// # Panic
+    /// Will panic if the node refers to a node which is not in this graph.
+    ///
+    pub fn add(&mut self, node: impl Into<SurfaceNode>) -> NodeId {
+        let node = node.into();
+        Self::check_inputs(&node, self.nodes.len());
+        self.nodes.push(node);
+        NodeId(self.nodes.len() - 1)
+    }
+
+    ///
+    /// Returns the node with the given id.
+    ///
+    pub fn node(&self, id: NodeId) -> &SurfaceNode {
+        &self.nodes[id.0]
+    }
+
+    ///
+    /// Replaces the node with the given id, for example to change a constant value.
+    ///
+    /// # Panic
+    /// Will panic if the node refers to a node which was not added before the node with the given id, since that would create a cycle.
+    ///
+    pub fn set(&mut self, id: NodeId, node: impl Into<SurfaceNode>) {
+        let node = node.into();
+        Self::check_inputs(&node, id.0);
+        self.nodes[id.0] = node;
+    }
+
+    fn check_inputs(node: &SurfaceNode, index: usize) {
+        let inputs = match node {
+            SurfaceNode::Add(a, b) | SurfaceNode::Multiply(a, b) => vec![*a, *b],
+            SurfaceNode::Mix(a, b, t) => vec![*a, *b, *t],
+            _ => Vec::new(),
+        };
+        for input in inputs {
+            if input.0 >= index {
+                panic!(
+                    "the node {} is not in the material graph before the node {}",
+                    input.0, index
+                );
+            }
+        }
+    }
+
+    fn node_source(&self, i: usize) -> String {
+        match &self.nodes[i] {
+            SurfaceNode::Constant(_) => format!("node{}", i),
+            SurfaceNode::VertexColor => "col".to_owned(),
+            SurfaceNode::Uv => "vec4(uvs, 0.0, 0.0)".to_owned(),
+            SurfaceNode::Position => "vec4(pos, 0.0)".to_owned(),
+            SurfaceNode::Normal => "vec4(normal, 0.0)".to_owned(),
+            SurfaceNode::Texture { srgb, .. } => {
+                let sample = format!(
+                    "texture(nodeTexture{}, (nodeTexTransform{} * vec3(uvs, 1.0)).xy)",
+                    i, i
+                );
+                if *srgb {
+                    format!("srgb_to_linear({})", sample)
+                } else {
+                    sample
+                }
+            }
+            SurfaceNode::NormalMap { .. } => format!(
+                "vec4(perturb_normal(normal, tangent, bitangent, texture(nodeTexture{}, (nodeTexTransform{} * vec3(uvs, 1.0)).xy).xyz, nodeScale{}), 0.0)",
+                i, i, i
+            ),
+            SurfaceNode::Add(a, b) => format!("n{} + n{}", a.0, b.0),
+            SurfaceNode::Multiply(a, b) => format!("n{} * n{}", a.0, b.0),
+            SurfaceNode::Mix(a, b, t) => format!("mix(n{}, n{}, n{}.x)", a.0, b.0, t.0),
+        }
+    }
+}
+
+impl Material for MaterialGraph {
+    fn fragment_shader(&self, lights: &[&dyn Light]) -> FragmentShader {
+        let mut attributes = FragmentAttributes {
+            position: true,
+            normal: true,
+            color: true,
+            ..FragmentAttributes::NONE
+        };
+        for node in self.nodes.iter() {
+            match node {
+                SurfaceNode::Uv | SurfaceNode::Texture { .. } => attributes.uv = true,
+                SurfaceNode::NormalMap { .. } => {
+                    attributes.uv = true;
+                    attributes.tangents = true;
+                }
+                _ => {}
+            }
+        }
+
+        let mut output = lights_shader_source(lights, self.lighting_model);
+        output.push_str("#include \"normal_mapping\"\n");
+        output.push_str(
+            "
+            uniform vec3 cameraPosition;
+            in vec3 pos;
+            in vec3 nor;
+            in vec4 col;
+            layout (location = 0) out vec4 outColor;
+            vec4 srgb_to_linear(vec4 c)
+            {
+                return vec4(rgb_from_srgb(c.rgb), c.a);
+            }
+            ",
+        );
+        if attributes.uv {
+            output.push_str("in vec2 uvs;\n");
+        }
+        if attributes.tangents {
+            output.push_str("in vec3 tang;\nin vec3 bitang;\n");
+        }
+        let mut body = String::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            match node {
+                SurfaceNode::Constant(_) => {
+                    output.push_str(&format!("uniform vec4 node{};\n", i));
+                }
+                SurfaceNode::Texture { .. } => {
+                    output.push_str(&format!(
+                        "uniform sampler2D nodeTexture{};\nuniform mat3 nodeTexTransform{};\n",
+                        i, i
+                    ));
+                }
+                SurfaceNode::NormalMap { .. } => {
+                    output.push_str(&format!(
+                        "uniform sampler2D nodeTexture{};\nuniform mat3 nodeTexTransform{};\nuniform float nodeScale{};\n",
+                        i, i, i
+                    ));
+                }
+                _ => {}
+            }
+            body.push_str(&format!("    vec4 n{} = {};\n", i, self.node_source(i)));
+        }
+
+        let output_source = |id: Option<NodeId>, swizzle: &str, default: &str| {
+            id.map(|id| format!("n{}{}", id.0, swizzle))
+                .unwrap_or_else(|| default.to_owned())
+        };
+        output.push_str("void main()\n{\n");
+        output.push_str("    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);\n");
+        if attributes.tangents {
+            output.push_str("    vec3 tangent = normalize(gl_FrontFacing ? tang : -tang);\n");
+            output.push_str("    vec3 bitangent = normalize(gl_FrontFacing ? bitang : -bitang);\n");
+        }
+        output.push_str(&body);
+        output.push_str(&format!(
+            "
+    vec4 surface_color = {};
+    float metallic_factor = {};
+    float roughness_factor = {};
+    float occlusion = {};
+    normal = normalize({});
+    vec3 total_emissive = {};
+
+    outColor.rgb = total_emissive + calculate_lighting(cameraPosition, surface_color.rgb, pos, normal, metallic_factor, roughness_factor, occlusion);
+    outColor.rgb = reinhard_tone_mapping(outColor.rgb);
+    outColor.rgb = srgb_from_rgb(outColor.rgb);
+    outColor.a = surface_color.a;
+}}
+",
+            output_source(self.albedo, "", "vec4(1.0)"),
+            output_source(self.metallic, ".x", "0.0"),
+            output_source(self.roughness, ".x", "1.0"),
+            output_source(self.occlusion, ".x", "1.0"),
+            output_source(self.normal, ".xyz", "normal"),
+            output_source(self.emissive, ".rgb", "vec3(0.0)"),
+        ));
+        FragmentShader {
+            source: output,
+            attributes,
+        }
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        program.use_uniform_if_required("cameraPosition", camera.position());
+        for (i, light) in lights.iter().enumerate() {
+            light.use_uniforms(program, i as u32);
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            match node {
+                SurfaceNode::Constant(value) => {
+                    program.use_uniform_if_required(&format!("node{}", i), *value);
+                }
+                SurfaceNode::Texture { texture, .. } => {
+                    let name = format!("nodeTexture{}", i);
+                    if program.requires_uniform(&name) {
+                        program
+                            .use_uniform(&format!("nodeTexTransform{}", i), texture.transformation);
+                        program.use_texture(&name, texture);
+                    }
+                }
+                SurfaceNode::NormalMap { texture, scale } => {
+                    let name = format!("nodeTexture{}", i);
+                    if program.requires_uniform(&name) {
+                        program
+                            .use_uniform(&format!("nodeTexTransform{}", i), texture.transformation);
+                        program.use_uniform(&format!("nodeScale{}", i), *scale);
+                        program.use_texture(&name, texture);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        if self.is_transparent {
+            MaterialType::Transparent
+        } else {
+            MaterialType::Opaque
+        }
+    }
+}
+
+impl Default for MaterialGraph {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            albedo: None,
+            metallic: None,
+            roughness: None,
+            occlusion: None,
+            normal: None,
+            emissive: None,
+            render_states: RenderStates::default(),
+            is_transparent: false,
+            lighting_model: LightingModel::Blinn,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn generate_fragment_shader() {
+        let mut graph = MaterialGraph::default();
+        let tint = graph.add(0.5);
+        let uv = graph.add(SurfaceNode::Uv);
+        graph.albedo = Some(graph.add(SurfaceNode::Multiply(tint, uv)));
+        graph.set(tint, Color::WHITE);
+        let shader = graph.fragment_shader(&[]);
+        assert!(shader.attributes.uv);
+        assert!(!shader.attributes.tangents);
+        assert!(shader.source.contains("uniform vec4 node0;"));
+        assert!(shader.source.contains("vec4 n2 = n0 * n1;"));
+        assert!(shader.source.contains("vec4 surface_color = n2;"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_node_referring_to_later_node() {
+        let mut graph = MaterialGraph::default();
+        let a = graph.add(0.5);
+        let b = graph.add(1.0);
+        graph.set(a, SurfaceNode::Add(b, b));
+    }
+}
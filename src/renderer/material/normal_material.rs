@@ -63,7 +63,7 @@ impl Material for NormalMaterial {
             attributes.tangents = true;
             source.push_str("#define USE_TEXTURE\nin vec2 uvs;\nin vec3 tang;\nin vec3 bitang;\n");
         }
-        source.push_str(include_str!("shaders/normal_material.frag"));
+        source.push_str("#include \"normal_material\"\n");
         FragmentShader { source, attributes }
     }
 
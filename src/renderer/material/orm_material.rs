@@ -91,7 +91,7 @@ impl Material for ORMMaterial {
                 source.push_str("#define USE_OCCLUSION_TEXTURE;\n");
             }
         }
-        source.push_str(include_str!("shaders/orm_material.frag"));
+        source.push_str("#include \"orm_material\"\n");
         FragmentShader { source, attributes }
     }
 
@@ -132,15 +132,46 @@ impl FromCpuMaterial for PhysicalMaterial {
     }
 }
 
-impl Material for PhysicalMaterial {
-    fn fragment_shader(&self, lights: &[&dyn Light]) -> FragmentShader {
+impl PhysicalMaterial {
+    ///
+    /// Returns the fragment shader of this material, where the indirect lighting is replaced or multiplied by the baked lighting in a lightmap if a [LightmapMode] is given.
+    ///
+    pub(super) fn fragment_shader_with_lightmap(
+        &self,
+        lights: &[&dyn Light],
+        lightmap_mode: Option<LightmapMode>,
+    ) -> FragmentShader {
         let mut attributes = FragmentAttributes {
             position: true,
             normal: true,
             color: true,
             ..FragmentAttributes::NONE
         };
-        let mut output = lights_shader_source(lights, self.lighting_model);
+        let mut output = if let Some(lightmap_mode) = lightmap_mode {
+            let (direct_lights, indirect_lights) = partition_lights(lights, lightmap_mode);
+            let mut output = lights_shader_source(&direct_lights, self.lighting_model);
+            let mut indirect_lighting = String::new();
+            for (i, light) in indirect_lights.iter().enumerate() {
+                let i = (direct_lights.len() + i) as u32;
+                output.push_str(&light.shader_source(i));
+                indirect_lighting.push_str(&format!("color += calculate_lighting{}(surface_color, position, normal, view_direction, metallic, roughness, occlusion);\n", i));
+            }
+            output.push_str(&format!(
+                "
+                vec3 calculate_indirect_lighting(vec3 camera_position, vec3 surface_color, vec3 position, vec3 normal, float metallic, float roughness, float occlusion)
+                {{
+                    vec3 color = vec3(0.0, 0.0, 0.0);
+                    vec3 view_direction = normalize(camera_position - position);
+                    {}
+                    return color;
+                }}
+                ",
+                indirect_lighting
+            ));
+            output
+        } else {
+            lights_shader_source(lights, self.lighting_model)
+        };
         if self.albedo_texture.is_some()
             || self.metallic_roughness_texture.is_some()
             || self.normal_texture.is_some()
@@ -166,40 +197,50 @@ impl Material for PhysicalMaterial {
                 output.push_str("#define USE_EMISSIVE_TEXTURE;\n");
             }
         }
-        output.push_str(include_str!("shaders/physical_material.frag"));
+        if let Some(lightmap_mode) = lightmap_mode {
+            attributes.lightmap_uv = true;
+            output.push_str("#define USE_LIGHTMAP;\nin vec2 lightmap_uvs;\n");
+            if lightmap_mode == LightmapMode::Replace {
+                output.push_str("#define USE_LIGHTMAP_REPLACE;\n");
+            }
+        }
+        output.push_str("\n#include \"physical_material\"\n");
         FragmentShader {
             source: output,
             attributes,
         }
     }
 
-    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
-        if !lights.is_empty() {
+    ///
+    /// Sends the uniforms of this material to the program returned from [PhysicalMaterial::fragment_shader_with_lightmap] with the same lightmap mode.
+    ///
+    pub(super) fn use_uniforms_with_lightmap(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        lightmap: Option<(&Texture2DRef, LightmapMode)>,
+    ) {
+        let lights = if let Some((_, lightmap_mode)) = lightmap {
+            let (mut direct_lights, indirect_lights) = partition_lights(lights, lightmap_mode);
+            direct_lights.extend(indirect_lights);
+            direct_lights
+        } else {
+            lights.to_vec()
+        };
+        if !lights.is_empty() || lightmap.is_some() {
             program.use_uniform_if_required("cameraPosition", camera.position());
             for (i, light) in lights.iter().enumerate() {
                 light.use_uniforms(program, i as u32);
             }
             program.use_uniform("metallic", self.metallic);
             program.use_uniform_if_required("roughness", self.roughness);
-            if program.requires_uniform("albedoTexture") {
-                if let Some(ref texture) = self.albedo_texture {
-                    program.use_uniform("albedoTexTransform", texture.transformation);
-                    program.use_texture("albedoTexture", texture);
-                }
-            }
             if program.requires_uniform("metallicRoughnessTexture") {
                 if let Some(ref texture) = self.metallic_roughness_texture {
                     program.use_uniform("metallicRoughnessTexTransform", texture.transformation);
                     program.use_texture("metallicRoughnessTexture", texture);
                 }
             }
-            if program.requires_uniform("occlusionTexture") {
-                if let Some(ref texture) = self.occlusion_texture {
-                    program.use_uniform("occlusionTexTransform", texture.transformation);
-                    program.use_uniform("occlusionStrength", self.occlusion_strength);
-                    program.use_texture("occlusionTexture", texture);
-                }
-            }
             if program.requires_uniform("normalTexture") {
                 if let Some(ref texture) = self.normal_texture {
                     program.use_uniform("normalTexTransform", texture.transformation);
@@ -208,6 +249,23 @@ impl Material for PhysicalMaterial {
                 }
             }
         }
+        if program.requires_uniform("albedoTexture") {
+            if let Some(ref texture) = self.albedo_texture {
+                program.use_uniform("albedoTexTransform", texture.transformation);
+                program.use_texture("albedoTexture", texture);
+            }
+        }
+        if program.requires_uniform("occlusionTexture") {
+            if let Some(ref texture) = self.occlusion_texture {
+                program.use_uniform("occlusionTexTransform", texture.transformation);
+                program.use_uniform("occlusionStrength", self.occlusion_strength);
+                program.use_texture("occlusionTexture", texture);
+            }
+        }
+        if let Some((texture, _)) = lightmap {
+            program.use_uniform("lightmapTexTransform", texture.transformation);
+            program.use_texture("lightmap", texture);
+        }
         program.use_uniform("albedo", self.albedo);
         program.use_uniform("emissive", self.emissive);
         if program.requires_uniform("emissiveTexture") {
@@ -217,6 +275,34 @@ impl Material for PhysicalMaterial {
             }
         }
     }
+}
+
+///
+/// Splits the lights into the direct lights and the indirect lights that are multiplied by the lightmap.
+/// The indirect lights are left out if they are replaced by the lightmap.
+///
+fn partition_lights<'a>(
+    lights: &[&'a dyn Light],
+    lightmap_mode: LightmapMode,
+) -> (Vec<&'a dyn Light>, Vec<&'a dyn Light>) {
+    let (direct_lights, indirect_lights): (Vec<_>, Vec<_>) = lights
+        .iter()
+        .copied()
+        .partition(|light| !light.is_indirect());
+    match lightmap_mode {
+        LightmapMode::Replace => (direct_lights, Vec::new()),
+        LightmapMode::Multiply => (direct_lights, indirect_lights),
+    }
+}
+
+impl Material for PhysicalMaterial {
+    fn fragment_shader(&self, lights: &[&dyn Light]) -> FragmentShader {
+        self.fragment_shader_with_lightmap(lights, None)
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        self.use_uniforms_with_lightmap(program, camera, lights, None)
+    }
 
     fn render_states(&self) -> RenderStates {
         self.render_states
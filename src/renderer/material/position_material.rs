@@ -20,7 +20,7 @@ impl FromCpuMaterial for PositionMaterial {
 impl Material for PositionMaterial {
     fn fragment_shader(&self, _lights: &[&dyn Light]) -> FragmentShader {
         FragmentShader {
-            source: include_str!("shaders/position_material.frag").to_string(),
+            source: "#include \"position_material\"\n".to_string(),
             attributes: FragmentAttributes {
                 position: true,
                 ..FragmentAttributes::NONE
@@ -33,7 +33,9 @@ void main()
     vec3 normal = normalize(vec3(n2.x, n2.y, (int(floor(n.z * 255.0)) & 128) == 128 ? z: -z));
     float roughness_factor = n.w;
     float occlusion = float(int(floor(n.z * 255.0)) & 127) / 127.0;
-    vec3 total_emissive = sample_layer(uvs, 2).rgb;
+    vec4 e = sample_layer(uvs, 2);
+    vec3 total_emissive = e.rgb;
+    float transmittance = e.a;
 
     if(debug_type == 0) // Position
     {
@@ -62,8 +64,16 @@ void main()
     }
     else { // None
         outColor.rgb = total_emissive + calculate_lighting(cameraPosition, surface_color.rgb, position, normal, metallic_factor, roughness_factor, occlusion);
+        if(transmittance > 0.0)
+        {
+            // Light shining onto the back of a thin subsurface scattering surface is transmitted through it
+            vec3 scatter_color = sample_layer(uvs, 3).rgb;
+            outColor.rgb += transmittance * calculate_lighting(cameraPosition, scatter_color, position, -normal, 0.0, 1.0, occlusion);
+        }
+#ifndef LINEAR_OUTPUT
         outColor.rgb = reinhard_tone_mapping(outColor.rgb);
         outColor.rgb = srgb_from_rgb(outColor.rgb);
+#endif
         outColor.a = surface_color.a;
     }
 }
\ No newline at end of file
@@ -31,6 +31,16 @@ uniform mat3 normalTexTransform;
 uniform float normalScale;
 #endif
 
+#ifdef USE_SUBSURFACE
+uniform vec4 scatterColor;
+uniform float scatterRadius;
+uniform float thickness;
+#ifdef USE_THICKNESS_TEXTURE
+uniform sampler2D thicknessTexture;
+uniform mat3 thicknessTexTransform;
+#endif
+#endif
+
 in vec3 pos;
 in vec3 nor;
 in vec4 col;
@@ -38,6 +48,7 @@ in vec4 col;
 layout (location = 0) out vec4 outColor;
 layout (location = 1) out vec4 outNormal;
 layout (location = 2) out vec4 outEmissive;
+layout (location = 3) out vec4 outSubsurface;
 
 void main()
 {
@@ -85,4 +96,14 @@ void main()
     }
     outNormal = vec4(0.5 * normal.xy + 0.5, float(o | nz << 7)/255.0, roughness_factor);
     outEmissive = vec4(total_emissive, 0.0);
+    outSubsurface = vec4(0.0);
+
+#ifdef USE_SUBSURFACE
+    float thickness_factor = thickness;
+#ifdef USE_THICKNESS_TEXTURE
+    thickness_factor *= texture(thicknessTexture, (thicknessTexTransform * vec3(uvs, 1.0)).xy).r;
+#endif
+    outEmissive.a = 1.0 - thickness_factor;
+    outSubsurface = vec4(scatterColor.rgb, scatterRadius / (1.0 + scatterRadius));
+#endif
 }
\ No newline at end of file
@@ -26,6 +26,13 @@ uniform mat3 occlusionTexTransform;
 uniform float occlusionStrength;
 #endif
 
+#ifdef USE_LIGHTMAP
+uniform sampler2D lightmap;
+uniform mat3 lightmapTexTransform;
+#endif
+
+#include "normal_mapping"
+
 #ifdef USE_NORMAL_TEXTURE
 uniform sampler2D normalTexture;
 uniform mat3 normalTexTransform;
@@ -66,8 +73,7 @@ void main()
 #ifdef USE_NORMAL_TEXTURE
     vec3 tangent = normalize(gl_FrontFacing ? tang : -tang);
     vec3 bitangent = normalize(gl_FrontFacing ? bitang : -bitang);
-    mat3 tbn = mat3(tangent, bitangent, normal);
-    normal = tbn * ((2.0 * texture(normalTexture, (normalTexTransform * vec3(uvs, 1.0)).xy).xyz - 1.0) * vec3(normalScale, normalScale, 1.0));
+    normal = perturb_normal(normal, tangent, bitangent, texture(normalTexture, (normalTexTransform * vec3(uvs, 1.0)).xy).xyz, normalScale);
 #endif
 
     vec3 total_emissive = emissive.rgb;
@@ -77,6 +83,14 @@ void main()
 #endif
 
     outColor.rgb = total_emissive + calculate_lighting(cameraPosition, surface_color.rgb, pos, normal, metallic_factor, roughness_factor, occlusion);
+#ifdef USE_LIGHTMAP
+    vec3 baked_lighting = rgb_from_srgb(texture(lightmap, (lightmapTexTransform * vec3(lightmap_uvs, 1.0)).xy).rgb);
+#ifdef USE_LIGHTMAP_REPLACE
+    outColor.rgb += occlusion * baked_lighting * mix(surface_color.rgb, vec3(0.0), metallic_factor);
+#else
+    outColor.rgb += baked_lighting * calculate_indirect_lighting(cameraPosition, surface_color.rgb, pos, normal, metallic_factor, roughness_factor, occlusion);
+#endif
+#endif
     outColor.rgb = reinhard_tone_mapping(outColor.rgb);
     outColor.rgb = srgb_from_rgb(outColor.rgb);
     outColor.a = surface_color.a;
@@ -0,0 +1,58 @@
+
+uniform float roughness;
+uniform vec3 cameraPosition;
+
+uniform vec4 albedo;
+#ifdef USE_ALBEDO_TEXTURE
+uniform sampler2D albedoTexture;
+uniform mat3 albedoTexTransform;
+#endif
+
+#include "normal_mapping"
+
+#ifdef USE_NORMAL_TEXTURE
+uniform sampler2D normalTexture;
+uniform mat3 normalTexTransform;
+uniform float normalScale;
+#endif
+
+uniform vec4 scatterColor;
+uniform float thickness;
+#ifdef USE_THICKNESS_TEXTURE
+uniform sampler2D thicknessTexture;
+uniform mat3 thicknessTexTransform;
+#endif
+
+in vec3 pos;
+in vec3 nor;
+in vec4 col;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec4 surface_color = albedo * col;
+#ifdef USE_ALBEDO_TEXTURE
+    vec4 c = texture(albedoTexture, (albedoTexTransform * vec3(uvs, 1.0)).xy);
+    surface_color *= vec4(rgb_from_srgb(c.rgb), c.a);
+#endif
+
+    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
+#ifdef USE_NORMAL_TEXTURE
+    vec3 tangent = normalize(gl_FrontFacing ? tang : -tang);
+    vec3 bitangent = normalize(gl_FrontFacing ? bitang : -bitang);
+    normal = perturb_normal(normal, tangent, bitangent, texture(normalTexture, (normalTexTransform * vec3(uvs, 1.0)).xy).xyz, normalScale);
+#endif
+
+    float thickness_factor = thickness;
+#ifdef USE_THICKNESS_TEXTURE
+    thickness_factor *= texture(thicknessTexture, (thicknessTexTransform * vec3(uvs, 1.0)).xy).r;
+#endif
+
+    // Wrap lighting: The light shining onto the back of the surface wraps around and is transmitted through the thin parts of the surface
+    outColor.rgb = calculate_lighting(cameraPosition, surface_color.rgb, pos, normal, 0.0, roughness, 1.0);
+    outColor.rgb += (1.0 - thickness_factor) * calculate_lighting(cameraPosition, scatterColor.rgb, pos, -normal, 0.0, 1.0, 1.0);
+    outColor.rgb = reinhard_tone_mapping(outColor.rgb);
+    outColor.rgb = srgb_from_rgb(outColor.rgb);
+    outColor.a = surface_color.a;
+}
@@ -0,0 +1,74 @@
+#include "shared"
+
+uniform sampler2DArray subsurfaceMap;
+uniform int subsurfaceLayer;
+uniform mat4 viewProjection;
+uniform mat4 viewProjectionInverse;
+uniform vec3 direction;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+const int SAMPLES = 7;
+
+// The color texture contains linear radiance, which is tone mapped after the last blur pass
+vec4 output_color(vec3 radiance, float alpha)
+{
+#ifdef TONE_MAPPING
+    radiance = srgb_from_rgb(reinhard_tone_mapping(radiance));
+#endif
+    return vec4(radiance, alpha);
+}
+
+vec4 sample_subsurface(vec2 uv)
+{
+    return texture(subsurfaceMap, vec3(uv, float(subsurfaceLayer)));
+}
+
+void main()
+{
+    float depth = sample_depth(uvs);
+    if(depth > 0.99999)
+    {
+        discard;
+    }
+    gl_FragDepth = depth;
+
+    vec4 center_color = sample_color(uvs);
+    vec4 subsurface = sample_subsurface(uvs);
+    if(subsurface.a < 0.001)
+    {
+        outColor = output_color(center_color.rgb, center_color.a);
+        return;
+    }
+
+    // Find the scatter radius in uv coordinates along the blur direction
+    float radius = subsurface.a / max(1.0 - subsurface.a, 0.001);
+    vec3 position = world_pos_from_depth(viewProjectionInverse, depth, uvs);
+    vec4 p = viewProjection * vec4(position + radius * direction, 1.0);
+    vec2 uv_radius = 0.5 * p.xy / p.w + 0.5 - uvs;
+
+    // Each color channel scatters a distance proportional to the scatter color
+    vec3 sigma = max(subsurface.rgb, vec3(0.05)) * 0.5;
+    vec3 center = center_color.rgb;
+    vec3 sum = vec3(0.0);
+    vec3 weight_sum = vec3(0.0);
+    for(int i = 0; i < SAMPLES; i++)
+    {
+        float x = 2.0 * float(i) / float(SAMPLES - 1) - 1.0;
+        vec2 uv = uvs + x * uv_radius;
+        vec3 c = sample_color(uv).rgb;
+
+        // Do not scatter light from surfaces that are too far away or without subsurface scattering
+        vec3 sample_position = world_pos_from_depth(viewProjectionInverse, sample_depth(uv), uv);
+        float deviation = abs(distance(sample_position, position) - abs(x) * radius);
+        float follow_surface = sample_subsurface(uv).a < 0.001 ? 1.0 : min(deviation / radius, 1.0);
+        c = mix(c, center, follow_surface);
+
+        vec3 weight = exp(-x * x / (2.0 * sigma * sigma));
+        sum += weight * c;
+        weight_sum += weight;
+    }
+    outColor = output_color(sum / weight_sum, center_color.a);
+}
@@ -1,4 +1,4 @@
-uniform mat4 viewProjection;
+#include "camera"
 uniform mat4 viewProjectionInverse;
 uniform vec3 cameraPosition;
 uniform vec2 screenSize;
@@ -9,11 +9,7 @@ pub struct SkyboxMaterial {
 impl Material for SkyboxMaterial {
     fn fragment_shader(&self, _lights: &[&dyn Light]) -> FragmentShader {
         FragmentShader {
-            source: format!(
-                "{}{}",
-                include_str!("../../core/shared.frag"),
-                include_str!("shaders/skybox_material.frag")
-            ),
+            source: "#include \"shared\"\n#include \"skybox_material\"\n".to_owned(),
             attributes: FragmentAttributes::NONE,
         }
     }
@@ -0,0 +1,218 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// A material for surfaces where light penetrates the surface and is scattered beneath it before exiting, for example skin, wax, marble and organic tissue.
+/// This material is affected by lights.
+///
+/// If [SubsurfaceMaterial::deferred] is true, the material is rendered in two stages similar to [DeferredPhysicalMaterial]
+/// and the light is scattered using a screen-space separable blur, with a distance given by [SubsurfaceMaterial::scatter_radius], after the lighting has been applied.
+/// The blur requires half float render targets (see [Context::is_half_float_render_target_supported]), if they are not supported, only the light transmitted through thin surfaces is applied.
+/// Otherwise it is rendered directly using a cheap approximation where light shining onto the back of the surface wraps around and is transmitted through the thin parts of the surface.
+/// In both cases, the amount of transmitted light is controlled by [SubsurfaceMaterial::thickness] and [SubsurfaceMaterial::thickness_texture].
+///
+#[derive(Clone)]
+pub struct SubsurfaceMaterial {
+    /// Name.
+    pub name: String,
+    /// Albedo base color, also called diffuse color. Assumed to be in linear color space.
+    pub albedo: Color,
+    /// Texture with albedo base colors, also called diffuse color. Assumed to be in sRGB with or without an alpha channel.
+    pub albedo_texture: Option<Texture2DRef>,
+    /// A value in the range `[0..1]` specifying how rough the surface is.
+    pub roughness: f32,
+    /// A scalar multiplier applied to each normal vector of the [Self::normal_texture].
+    pub normal_scale: f32,
+    /// A tangent space normal map, also known as bump map.
+    pub normal_texture: Option<Texture2DRef>,
+    /// The color of the light that is scattered beneath the surface. Assumed to be in linear color space.
+    /// A color channel with a high value scatters further than a color channel with a low value, for skin the red channel should for example scatter the most.
+    pub scatter_color: Color,
+    /// The distance in world space that light travels beneath the surface before exiting. Only used when [Self::deferred] is true.
+    pub scatter_radius: f32,
+    /// A value in the range `[0..1]` specifying how thick the surface is, where a thick surface does not transmit any light from the back of the surface.
+    pub thickness: f32,
+    /// A texture with thickness values which are multiplied with the [Self::thickness] value in the shader.
+    /// The thickness values are sampled from the red channel.
+    pub thickness_texture: Option<Texture2DRef>,
+    /// Render states.
+    pub render_states: RenderStates,
+    /// Whether this material is rendered in two stages with screen-space subsurface scattering (see [DeferredPhysicalMaterial]) or directly with wrap lighting.
+    pub deferred: bool,
+}
+
+impl SubsurfaceMaterial {
+    ///
+    /// Constructs a subsurface material with the base color and textures from the given physical material.
+    ///
+    pub fn from_physical_material(
+        physical_material: &PhysicalMaterial,
+        scatter_color: Color,
+        scatter_radius: f32,
+    ) -> Self {
+        Self {
+            name: physical_material.name.clone(),
+            albedo: physical_material.albedo,
+            albedo_texture: physical_material.albedo_texture.clone(),
+            roughness: physical_material.roughness,
+            normal_scale: physical_material.normal_scale,
+            normal_texture: physical_material.normal_texture.clone(),
+            scatter_color,
+            scatter_radius,
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// Blurs the color texture, which is the linear result of the lighting pass of a deferred render call, in the given direction for all pixels with subsurface scattering.
+    /// The subsurface scattering data is read from the fourth layer of the geometry pass texture.
+    /// If `tone_mapping` is true, the blurred radiance is tone mapped and converted to sRGB, which should be done in the last pass.
+    ///
+    pub(in crate::renderer) fn scattering_pass(
+        context: &Context,
+        camera: &Camera,
+        color_texture: &Texture2D,
+        depth_texture: &DepthTexture2D,
+        geometry_pass_texture: &Texture2DArray,
+        direction: Vec3,
+        tone_mapping: bool,
+    ) {
+        let color_texture = ColorTexture::Single(color_texture);
+        let depth_texture = DepthTexture::Single(depth_texture);
+        let fragment_shader = format!(
+            "{}{}{}\n#include \"subsurface_scattering\"\n",
+            if tone_mapping {
+                "#define TONE_MAPPING\n"
+            } else {
+                ""
+            },
+            color_texture.fragment_shader_source(),
+            depth_texture.fragment_shader_source(),
+        );
+        let view_projection = camera.projection() * camera.view();
+        apply_effect(
+            context,
+            &fragment_shader,
+            RenderStates::default(),
+            camera.viewport(),
+            |program| {
+                color_texture.use_uniforms(program);
+                depth_texture.use_uniforms(program);
+                program.use_uniform("subsurfaceLayer", 3);
+                program.use_texture_array("subsurfaceMap", geometry_pass_texture);
+                program.use_uniform("viewProjection", view_projection);
+                program.use_uniform("viewProjectionInverse", view_projection.invert().unwrap());
+                program.use_uniform("direction", direction);
+            },
+        )
+    }
+}
+
+impl Material for SubsurfaceMaterial {
+    fn fragment_shader(&self, lights: &[&dyn Light]) -> FragmentShader {
+        let mut attributes = FragmentAttributes {
+            position: true,
+            normal: true,
+            color: true,
+            ..FragmentAttributes::NONE
+        };
+        let mut output = if self.deferred {
+            "#include \"shared\"\n#define USE_SUBSURFACE\n".to_owned()
+        } else {
+            lights_shader_source(lights, LightingModel::Blinn)
+        };
+        if self.albedo_texture.is_some()
+            || self.normal_texture.is_some()
+            || self.thickness_texture.is_some()
+        {
+            attributes.uv = true;
+            output.push_str("in vec2 uvs;\n");
+            if self.albedo_texture.is_some() {
+                output.push_str("#define USE_ALBEDO_TEXTURE;\n");
+            }
+            if self.normal_texture.is_some() {
+                attributes.tangents = true;
+                output.push_str("#define USE_NORMAL_TEXTURE;\nin vec3 tang;\nin vec3 bitang;\n");
+            }
+            if self.thickness_texture.is_some() {
+                output.push_str("#define USE_THICKNESS_TEXTURE;\n");
+            }
+        }
+        if self.deferred {
+            output.push_str("\n#include \"deferred_physical_material\"\n");
+        } else {
+            output.push_str("\n#include \"subsurface_material\"\n");
+        }
+        FragmentShader {
+            source: output,
+            attributes,
+        }
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        if self.deferred {
+            program.use_uniform("metallic", 0.0f32);
+            program.use_uniform("emissive", Color::BLACK);
+            program.use_uniform("scatterRadius", self.scatter_radius);
+        } else {
+            program.use_uniform_if_required("cameraPosition", camera.position());
+            for (i, light) in lights.iter().enumerate() {
+                light.use_uniforms(program, i as u32);
+            }
+        }
+        program.use_uniform_if_required("roughness", self.roughness);
+        program.use_uniform("albedo", self.albedo);
+        program.use_uniform_if_required("scatterColor", self.scatter_color);
+        program.use_uniform_if_required("thickness", self.thickness);
+        if program.requires_uniform("albedoTexture") {
+            if let Some(ref texture) = self.albedo_texture {
+                program.use_uniform("albedoTexTransform", texture.transformation);
+                program.use_texture("albedoTexture", texture);
+            }
+        }
+        if program.requires_uniform("normalTexture") {
+            if let Some(ref texture) = self.normal_texture {
+                program.use_uniform("normalTexTransform", texture.transformation);
+                program.use_uniform("normalScale", self.normal_scale);
+                program.use_texture("normalTexture", texture);
+            }
+        }
+        if program.requires_uniform("thicknessTexture") {
+            if let Some(ref texture) = self.thickness_texture {
+                program.use_uniform("thicknessTexTransform", texture.transformation);
+                program.use_texture("thicknessTexture", texture);
+            }
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        if self.deferred {
+            MaterialType::DeferredSubsurface
+        } else {
+            MaterialType::Opaque
+        }
+    }
+}
+
+impl Default for SubsurfaceMaterial {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            albedo: Color::WHITE,
+            albedo_texture: None,
+            roughness: 0.5,
+            normal_scale: 1.0,
+            normal_texture: None,
+            scatter_color: Color::new_opaque(255, 100, 70),
+            scatter_radius: 0.01,
+            thickness: 1.0,
+            thickness_texture: None,
+            render_states: RenderStates::default(),
+            deferred: true,
+        }
+    }
+}
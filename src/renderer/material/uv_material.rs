@@ -20,7 +20,7 @@ impl FromCpuMaterial for UVMaterial {
 impl Material for UVMaterial {
     fn fragment_shader(&self, _lights: &[&dyn Light]) -> FragmentShader {
         FragmentShader {
-            source: include_str!("shaders/uv_material.frag").to_string(),
+            source: "#include \"uv_material\"\n".to_string(),
             attributes: FragmentAttributes {
                 uv: true,
                 ..FragmentAttributes::NONE
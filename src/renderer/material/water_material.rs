@@ -43,7 +43,7 @@ impl PostMaterial for WaterMaterial {
     ) -> FragmentShader {
         FragmentShader {
             source: format!(
-                "{}\n{}\n{}\n{}\n{}",
+                "{}\n{}\n{}\n{}\n#include \"water_material\"\n",
                 match &self.background {
                     Background::Color(_) => "",
                     Background::Texture(_) => "#define USE_BACKGROUND_TEXTURE",
@@ -55,7 +55,6 @@ impl PostMaterial for WaterMaterial {
                     .expect("Must supply a depth texture to apply a water effect")
                     .fragment_shader_source(),
                 lights_shader_source(lights, self.lighting_model),
-                include_str!("shaders/water_material.frag")
             ),
             attributes: FragmentAttributes {
                 position: true,
@@ -90,7 +89,7 @@ impl PostMaterial for WaterMaterial {
         for (i, light) in lights.iter().enumerate() {
             light.use_uniforms(program, i as u32);
         }
-        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_camera_block(camera);
         program.use_uniform(
             "viewProjectionInverse",
             (camera.projection() * camera.view()).invert().unwrap(),
@@ -41,6 +41,14 @@ mod axes;
 #[doc(inline)]
 pub use axes::*;
 
+mod decal;
+#[doc(inline)]
+pub use decal::*;
+
+mod scene;
+#[doc(inline)]
+pub use scene::*;
+
 use crate::core::*;
 use crate::renderer::*;
 
@@ -0,0 +1,294 @@
+use crate::renderer::*;
+
+///
+/// Projects textures onto the surfaces inside an oriented box, for example bullet holes, road markings or labels.
+/// The box is the cube from `-1` to `1` in all axes transformed by the [Decal::transformation] and the textures are projected along the local negative z-axis,
+/// ie. a surface is covered by the decal if it is inside the box and faces the local positive z-axis.
+///
+/// A decal modifies the geometry information written by objects with a [DeferredPhysicalMaterial] before the lighting is applied,
+/// so the decal is lit in the same way as the surface underneath. Use [RenderTarget::render_with_decals] to render a scene with decals.
+///
+/// Decals are only supported for deferred objects, there is no forward path, so objects rendered with a forward material,
+/// for example [PhysicalMaterial] or a transparent material, are never affected by decals.
+///
+pub struct Decal {
+    mesh: Mesh,
+    /// Albedo base color which is multiplied with the [Self::albedo_texture]. Assumed to be in linear color space.
+    /// The alpha value specifies how much the decal covers the surface underneath.
+    pub albedo: Color,
+    /// Texture with albedo base colors. Assumed to be in sRGB with or without an alpha channel.
+    pub albedo_texture: Option<Texture2DRef>,
+    /// A scalar multiplier applied to each normal vector of the [Self::normal_texture].
+    pub normal_scale: f32,
+    /// A tangent space normal map, where the tangent space is defined by the local x- and y-axis of the decal.
+    pub normal_texture: Option<Texture2DRef>,
+    /// A texture with occlusion values in the red channel, roughness values in the green channel and metallic values in the blue channel
+    /// which replaces the values of the surface underneath.
+    pub orm_texture: Option<Texture2DRef>,
+    /// The decal is not applied to surfaces where the angle between the surface normal and the local positive z-axis of the decal is larger than this angle.
+    pub max_angle: Radians,
+}
+
+impl Decal {
+    ///
+    /// Constructs a new decal inside the box defined by the given transformation, see [Decal::set_transformation].
+    ///
+    pub fn new(context: &Context, transformation: Mat4) -> Self {
+        let mut mesh = Mesh::new(context, &CpuMesh::cube());
+        mesh.set_transformation(transformation);
+        Self {
+            mesh,
+            albedo: Color::WHITE,
+            albedo_texture: None,
+            normal_scale: 1.0,
+            normal_texture: None,
+            orm_texture: None,
+            max_angle: degrees(80.0).into(),
+        }
+    }
+
+    ///
+    /// Returns the transformation of the box that the decal is projected inside.
+    ///
+    pub fn transformation(&self) -> Mat4 {
+        self.mesh.transformation()
+    }
+
+    ///
+    /// Sets the transformation of the box that the decal is projected inside.
+    /// The transformation is applied to the cube from `-1` to `1` in all axes, so for example a scale of `0.1` results in a box with a side length of `0.2`.
+    ///
+    pub fn set_transformation(&mut self, transformation: Mat4) {
+        self.mesh.set_transformation(transformation);
+    }
+
+    ///
+    /// Returns the [AxisAlignedBoundingBox] of the box that the decal is projected inside.
+    ///
+    pub fn aabb(&self) -> AxisAlignedBoundingBox {
+        self.mesh.aabb()
+    }
+
+    ///
+    /// Applies the decals to the albedo and metallic layer and the normal, occlusion and roughness layer of the geometry pass texture.
+    ///
+    pub(in crate::renderer) fn apply_decals(
+        context: &Context,
+        camera: &Camera,
+        decals: &[&Decal],
+        geometry_pass_texture: &mut Texture2DArray,
+        geometry_pass_depth_texture: &DepthTexture2D,
+    ) {
+        // A decal with a degenerate box does not cover anything
+        let decals = decals
+            .iter()
+            .filter(|d| camera.in_frustum(&d.aabb()))
+            .filter_map(|d| d.transformation().invert().map(|inverse| (*d, inverse)))
+            .collect::<Vec<_>>();
+        if decals.is_empty() {
+            return;
+        }
+        let viewport = camera.viewport();
+        context.scratch_texture_array(
+            "decal copy",
+            viewport.width,
+            viewport.height,
+            2,
+            |copy_texture| {
+                for (decal, inverse_transformation) in decals {
+                    // The decal is blended with a copy of the geometry information since it is not possible to read from and write to the same texture
+                    let scissor_box = decal.scissor_box(camera);
+                    copy_texture.as_color_target(&[0, 1], None).write_partially(
+                        scissor_box,
+                        || {
+                            let color_texture = ColorTexture::Array {
+                                texture: geometry_pass_texture,
+                                layers: &[0, 1],
+                            };
+                            context.with_blocking_shader_compile(|| {
+                                apply_effect(
+                                    context,
+                                    &format!(
+                                        "{}\n{}",
+                                        color_texture.fragment_shader_source(),
+                                        "
+                                    in vec2 uvs;
+                                    layout (location = 0) out vec4 outColor;
+                                    layout (location = 1) out vec4 outNormal;
+                                    void main()
+                                    {
+                                        outColor = sample_layer(uvs, 0);
+                                        outNormal = sample_layer(uvs, 1);
+                                    }"
+                                    ),
+                                    RenderStates {
+                                        depth_test: DepthTest::Always,
+                                        ..Default::default()
+                                    },
+                                    viewport,
+                                    |program| color_texture.use_uniforms(program),
+                                )
+                            });
+                        },
+                    );
+                    geometry_pass_texture
+                        .as_color_target(&[0, 1], None)
+                        .write_partially(scissor_box, || {
+                            decal.mesh.render_with_post_material(
+                                &DecalMaterial {
+                                    decal,
+                                    inverse_transformation,
+                                },
+                                camera,
+                                &[],
+                                Some(ColorTexture::Array {
+                                    texture: copy_texture,
+                                    layers: &[0, 1],
+                                }),
+                                Some(DepthTexture::Single(geometry_pass_depth_texture)),
+                            );
+                        });
+                }
+            },
+        );
+    }
+
+    /// The part of the screen covered by the box, or the entire screen if the box intersects the near plane.
+    fn scissor_box(&self, camera: &Camera) -> ScissorBox {
+        let viewport = camera.viewport();
+        let view_projection = camera.projection() * camera.view() * self.transformation();
+        let mut min = vec2(f32::INFINITY, f32::INFINITY);
+        let mut max = vec2(f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for i in 0..8 {
+            let corner = vec4(
+                if i & 1 == 0 { -1.0 } else { 1.0 },
+                if i & 2 == 0 { -1.0 } else { 1.0 },
+                if i & 4 == 0 { -1.0 } else { 1.0 },
+                1.0,
+            );
+            let p = view_projection * corner;
+            if p.w <= 0.0 {
+                return viewport.into();
+            }
+            let screen_position = vec2(
+                (0.5 * p.x / p.w + 0.5) * viewport.width as f32,
+                (0.5 * p.y / p.w + 0.5) * viewport.height as f32,
+            );
+            min = vec2(min.x.min(screen_position.x), min.y.min(screen_position.y));
+            max = vec2(max.x.max(screen_position.x), max.y.max(screen_position.y));
+        }
+        ScissorBox {
+            x: viewport.x + min.x.floor() as i32,
+            y: viewport.y + min.y.floor() as i32,
+            width: (max.x.ceil() - min.x.floor()) as u32,
+            height: (max.y.ceil() - min.y.floor()) as u32,
+        }
+        .intersection(viewport)
+    }
+}
+
+struct DecalMaterial<'a> {
+    decal: &'a Decal,
+    inverse_transformation: Mat4,
+}
+
+impl PostMaterial for DecalMaterial<'_> {
+    fn fragment_shader(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> FragmentShader {
+        let decal = self.decal;
+        let mut source = String::new();
+        if decal.albedo_texture.is_some() {
+            source.push_str("#define USE_ALBEDO_TEXTURE\n");
+        }
+        if decal.normal_texture.is_some() {
+            source.push_str("#define USE_NORMAL_TEXTURE\n");
+        }
+        if decal.orm_texture.is_some() {
+            source.push_str("#define USE_ORM_TEXTURE\n");
+        }
+        source.push_str(
+            &color_texture
+                .expect("Must supply the geometry pass color texture to apply a decal")
+                .fragment_shader_source(),
+        );
+        source.push_str(
+            &depth_texture
+                .expect("Must supply the geometry pass depth texture to apply a decal")
+                .fragment_shader_source(),
+        );
+        source.push_str(include_str!("shaders/decal.frag"));
+        FragmentShader {
+            source,
+            attributes: FragmentAttributes::NONE,
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        let decal = self.decal;
+        color_texture
+            .expect("Must supply the geometry pass color texture to apply a decal")
+            .use_uniforms(program);
+        depth_texture
+            .expect("Must supply the geometry pass depth texture to apply a decal")
+            .use_uniforms(program);
+        let transformation = decal.transformation();
+        program.use_uniform(
+            "viewProjectionInverse",
+            (camera.projection() * camera.view()).invert().unwrap(),
+        );
+        program.use_uniform("decalInverseTransformation", self.inverse_transformation);
+        program.use_uniform(
+            "decalAxes",
+            Mat3::from_cols(
+                transformation.x.truncate().normalize(),
+                transformation.y.truncate().normalize(),
+                transformation.z.truncate().normalize(),
+            ),
+        );
+        program.use_uniform(
+            "screenSize",
+            vec2(
+                camera.viewport().width as f32,
+                camera.viewport().height as f32,
+            ),
+        );
+        program.use_uniform("cosMaxAngle", decal.max_angle.0.cos());
+        program.use_uniform("albedo", decal.albedo);
+        if let Some(ref texture) = decal.albedo_texture {
+            program.use_uniform("albedoTexTransform", texture.transformation);
+            program.use_texture("albedoTexture", texture);
+        }
+        if let Some(ref texture) = decal.normal_texture {
+            program.use_uniform("normalTexTransform", texture.transformation);
+            program.use_uniform("normalScale", decal.normal_scale);
+            program.use_texture("normalTexture", texture);
+        }
+        if let Some(ref texture) = decal.orm_texture {
+            program.use_uniform("ormTexTransform", texture.transformation);
+            program.use_texture("ormTexture", texture);
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            cull: Cull::Front,
+            ..Default::default()
+        }
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Opaque
+    }
+}
@@ -211,12 +211,15 @@ impl ImpostersMaterial {
                     center,
                     vec3(0.0, 1.0, 0.0),
                 );
-                RenderTarget::new(
+                let target = RenderTarget::new(
                     self.texture.as_color_target(&layers, None),
                     depth_texture.as_depth_target(),
-                )
-                .clear(ClearState::color_and_depth(0.0, 0.0, 0.0, 0.0, 1.0))
-                .render(&camera, objects.clone(), lights);
+                );
+                self.context.with_blocking_shader_compile(|| {
+                    target
+                        .clear(ClearState::color_and_depth(0.0, 0.0, 0.0, 0.0, 1.0))
+                        .render(&camera, objects.clone(), lights);
+                });
             }
         }
     }
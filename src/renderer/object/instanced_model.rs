@@ -1,3 +1,4 @@
+use crate::renderer::geometry::blend_transformations;
 use crate::renderer::*;
 
 ///
@@ -13,16 +14,30 @@ impl<M: Material> InstancedModelPart<M> {
     /// Returns a list of unique names for the animations for this model part. Use these names as input to [Self::choose_animation].
     ///
     pub fn animations(&self) -> Vec<Option<String>> {
-        self.animations
+        let mut names: Vec<Option<String>> = self
+            .animations
             .iter()
             .map(|animation| animation.name.clone())
-            .collect()
+            .collect();
+        let mut other_names = self.morph_animations();
+        if let Some(skeleton) = self.skeleton() {
+            other_names.extend(skeleton.animations());
+        }
+        for name in other_names {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
     }
 
     ///
     /// Specifies the animation to use when [Geometry::animate] is called. Use the [Self::animations] method to get a list of possible animations.
+    /// If the mesh is skinned (see [InstancedMesh::set_skin]) or has morph animations (see [InstancedMesh::set_morph_animations]), the animation with the same name is also chosen for the skeleton and morph weights.
     ///
     pub fn choose_animation(&mut self, animation_name: Option<&str>) {
+        self.choose_skeleton_animation(animation_name);
+        self.choose_morph_animation(animation_name);
         if let Some(animation) = self
             .animations
             .iter()
@@ -32,6 +47,28 @@ impl<M: Material> InstancedModelPart<M> {
             self.set_animation(move |time| animation.transformation(time));
         }
     }
+
+    ///
+    /// Animates this model part by blending the given animation samples, for example the samples returned by [AnimationController::samples].
+    /// This is an alternative to [Geometry::animate] which only evaluates the animation chosen by [Self::choose_animation].
+    ///
+    pub fn animate_blended(&mut self, samples: &[AnimationSample]) {
+        let transformations = samples
+            .iter()
+            .filter_map(|sample| {
+                self.animations
+                    .iter()
+                    .find(|a| sample.name == a.name)
+                    .map(|a| (a.transformation(sample.time), sample.weight))
+            })
+            .collect::<Vec<_>>();
+        if !transformations.is_empty() {
+            self.gm
+                .geometry
+                .set_animation_transformation(blend_transformations(&transformations));
+        }
+        self.gm.geometry.animate_blended(samples);
+    }
 }
 
 impl<M: Material> std::ops::Deref for InstancedModelPart<M> {
@@ -118,6 +155,10 @@ impl<M: Material + FromCpuMaterial + Clone + Default> InstancedModel<M> {
     /// Constructs an [InstancedModel] from a [CpuModel] and the given [Instances] attributes, ie. constructs a list of [Gm]s with a [InstancedMesh] as geometry (constructed from the [CpuMesh]es in the [CpuModel]) and
     /// a [material] type specified by the generic parameter which implement [FromCpuMaterial] (constructed from the [CpuMaterial]s in the [CpuModel]).
     ///
+    /// A [CpuModel] does not contain any joints or joint weights, so to render a rigged model in other poses than the bind pose,
+    /// set the [Skeleton] and the joint indices and weights on each skinned model part using [InstancedMesh::set_skin] and then call [Self::choose_animation].
+    /// Similarly, the morph weights of the animations in the [CpuModel] are applied when morph targets are set on each model part using [InstancedMesh::set_morph_targets].
+    ///
     pub fn new(
         context: &Context,
         instances: &Instances,
@@ -149,6 +190,14 @@ impl<M: Material + FromCpuMaterial + Clone + Default> InstancedModel<M> {
                     material,
                 };
                 gm.set_transformation(primitive.transformation);
+                gm.set_morph_animations(
+                    primitive
+                        .animations
+                        .iter()
+                        .filter(|a| a.key_frames.iter().any(|(_, k)| k.weights.is_some()))
+                        .cloned()
+                        .collect(),
+                );
                 gms.push(InstancedModelPart {
                     gm,
                     animations: primitive.animations.clone(),
@@ -188,6 +237,15 @@ impl<M: Material + FromCpuMaterial + Clone + Default> InstancedModel<M> {
     pub fn animate(&mut self, time: f32) {
         self.iter_mut().for_each(|m| m.animate(time));
     }
+
+    ///
+    /// Animates this model using the clips played by the given [AnimationController] instead of the animation chosen by [Self::choose_animation].
+    /// Call [AnimationController::update] before calling this method each frame.
+    ///
+    pub fn animate_with(&mut self, controller: &AnimationController) {
+        let samples = controller.samples();
+        self.iter_mut().for_each(|m| m.animate_blended(&samples));
+    }
 }
 
 impl<M: Material> std::ops::Deref for InstancedModel<M> {
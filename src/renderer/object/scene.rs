@@ -0,0 +1,536 @@
+use crate::renderer::*;
+pub use three_d_asset::{Node as CpuNode, Scene as CpuScene};
+
+///
+/// Represents an object, geometry or similar where the local to world transformation can be set.
+/// Used by a [Scene] to propagate the world transformation of a [Node] to the object attached to the node.
+///
+pub trait Transformable {
+    ///
+    /// Set the local to world transformation.
+    ///
+    fn set_transformation(&mut self, transformation: Mat4);
+}
+
+impl Transformable for Mesh {
+    fn set_transformation(&mut self, transformation: Mat4) {
+        Mesh::set_transformation(self, transformation)
+    }
+}
+
+impl Transformable for InstancedMesh {
+    fn set_transformation(&mut self, transformation: Mat4) {
+        InstancedMesh::set_transformation(self, transformation)
+    }
+}
+
+impl Transformable for Sprites {
+    fn set_transformation(&mut self, transformation: Mat4) {
+        Sprites::set_transformation(self, transformation)
+    }
+}
+
+impl Transformable for ParticleSystem {
+    fn set_transformation(&mut self, transformation: Mat4) {
+        ParticleSystem::set_transformation(self, transformation)
+    }
+}
+
+impl Transformable for Axes {
+    fn set_transformation(&mut self, transformation: Mat4) {
+        Axes::set_transformation(self, transformation)
+    }
+}
+
+impl Transformable for Polyline3D {
+    fn set_transformation(&mut self, transformation: Mat4) {
+        Polyline3D::set_transformation(self, transformation)
+    }
+}
+
+impl<G: Geometry + Transformable, M: Material> Transformable for Gm<G, M> {
+    fn set_transformation(&mut self, transformation: Mat4) {
+        self.geometry.set_transformation(transformation)
+    }
+}
+
+impl<M: Material> Transformable for ModelPart<M> {
+    fn set_transformation(&mut self, transformation: Mat4) {
+        self.geometry.set_transformation(transformation)
+    }
+}
+
+impl<M: Material> Transformable for InstancedModelPart<M> {
+    fn set_transformation(&mut self, transformation: Mat4) {
+        self.geometry.set_transformation(transformation)
+    }
+}
+
+///
+/// An [Object] which can be attached to a [Node] in a [Scene].
+///
+pub trait SceneObject: Object + Transformable {
+    ///
+    /// Returns this scene object as an [Object].
+    ///
+    fn as_object(&self) -> &dyn Object;
+}
+
+impl<T: Object + Transformable> SceneObject for T {
+    fn as_object(&self) -> &dyn Object {
+        self
+    }
+}
+
+///
+/// The id of a [Node] in a [Scene].
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SceneNodeId(usize);
+
+///
+/// A node in a [Scene] with a transformation relative to the parent node and optionally an attached object.
+///
+pub struct Node {
+    name: Option<String>,
+    transformation: Mat4,
+    world_transformation: Mat4,
+    visible: bool,
+    parent: Option<SceneNodeId>,
+    children: Vec<SceneNodeId>,
+    object: Option<Box<dyn SceneObject>>,
+    animation: Option<KeyFrames>,
+    animation_transformation: Mat4,
+}
+
+impl Node {
+    ///
+    /// Returns the name of this node.
+    ///
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    ///
+    /// Returns the transformation of this node relative to the parent node.
+    ///
+    pub fn transformation(&self) -> Mat4 {
+        self.transformation
+    }
+
+    ///
+    /// Returns the local to world transformation of this node, ie. the transformations of all the ancestors of this node and this node combined.
+    ///
+    pub fn world_transformation(&self) -> Mat4 {
+        self.world_transformation
+    }
+
+    ///
+    /// Returns whether this node is visible. Note that the node is not rendered if any of its ancestors are invisible.
+    ///
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    ///
+    /// Returns the parent of this node or `None` if it is a root node.
+    ///
+    pub fn parent(&self) -> Option<SceneNodeId> {
+        self.parent
+    }
+
+    ///
+    /// Returns the children of this node.
+    ///
+    pub fn children(&self) -> &[SceneNodeId] {
+        &self.children
+    }
+
+    ///
+    /// Returns the object attached to this node, if any.
+    ///
+    pub fn object(&self) -> Option<&dyn SceneObject> {
+        self.object.as_deref()
+    }
+
+    ///
+    /// Returns the object attached to this node mutably, if any.
+    /// Note that the transformation of the object is overwritten by the world transformation of the node when the transformation of the node or any of its ancestors change.
+    ///
+    pub fn object_mut(&mut self) -> Option<&mut (dyn SceneObject + 'static)> {
+        self.object.as_deref_mut()
+    }
+}
+
+///
+/// A scene graph consisting of a hierarchy of [Node]s, each with a transformation relative to its parent node and optionally an attached [SceneObject].
+/// The world transformation of a node is propagated to the attached object and all descendant nodes whenever the transformation of the node changes,
+/// so for example attaching a weapon to the node of a hand makes the weapon follow the hand.
+///
+#[derive(Default)]
+pub struct Scene {
+    nodes: Vec<Option<Node>>,
+}
+
+impl Scene {
+    ///
+    /// Creates a new empty scene.
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Adds a new node without an object as a child of the given parent node, or as a root node if the parent is `None`, and returns the id of the new node.
+    ///
+    pub fn add_node(
+        &mut self,
+        parent: Option<SceneNodeId>,
+        name: Option<&str>,
+        transformation: Mat4,
+    ) -> SceneNodeId {
+        self.insert(parent, name, transformation, None)
+    }
+
+    ///
+    /// Adds a new node with the given object attached as a child of the given parent node, or as a root node if the parent is `None`, and returns the id of the new node.
+    ///
+    pub fn add_object(
+        &mut self,
+        parent: Option<SceneNodeId>,
+        name: Option<&str>,
+        transformation: Mat4,
+        object: impl SceneObject + 'static,
+    ) -> SceneNodeId {
+        self.insert(parent, name, transformation, Some(Box::new(object)))
+    }
+
+    ///
+    /// Adds a node for the given [Model] as a child of the given parent node, or as a root node if the parent is `None`, and returns the id of the new node.
+    /// Each part of the model is added as a child node of the new node with the transformation of the part.
+    /// Since a [CpuModel] does not contain the node hierarchy, use [Self::add_cpu_scene] to keep the hierarchy of a loaded scene.
+    ///
+    pub fn add_model<M: Material + 'static>(
+        &mut self,
+        parent: Option<SceneNodeId>,
+        name: Option<&str>,
+        transformation: Mat4,
+        mut model: Model<M>,
+    ) -> SceneNodeId {
+        let id = self.add_node(parent, name, transformation);
+        for part in model.drain(..) {
+            let part_transformation = part.transformation();
+            self.add_object(Some(id), None, part_transformation, part);
+        }
+        id
+    }
+
+    ///
+    /// Adds the node hierarchy of the given [CpuScene] as a child of the given parent node, or as a root node if the parent is `None`, and returns the id of the new node.
+    /// Each [CpuNode] is added as a node with the same name and transformation and each triangle geometry is attached as a [Gm] with a [Mesh]
+    /// and a [material] type specified by the generic parameter. The first animation of each node is played when [Self::animate] is called.
+    ///
+    pub fn add_cpu_scene<M: Material + FromCpuMaterial + Clone + Default + 'static>(
+        &mut self,
+        context: &Context,
+        parent: Option<SceneNodeId>,
+        cpu_scene: &CpuScene,
+    ) -> Result<SceneNodeId, RendererError> {
+        let materials = cpu_scene
+            .materials
+            .iter()
+            .map(|m| M::from_cpu_material(context, m))
+            .collect::<Vec<_>>();
+        let id = self.add_node(parent, Some(&cpu_scene.name), Mat4::identity());
+        for child in cpu_scene.children.iter() {
+            self.add_cpu_node(context, id, child, &materials)?;
+        }
+        Ok(id)
+    }
+
+    fn add_cpu_node<M: Material + Clone + Default + 'static>(
+        &mut self,
+        context: &Context,
+        parent: SceneNodeId,
+        cpu_node: &CpuNode,
+        materials: &[M],
+    ) -> Result<(), RendererError> {
+        let object = if let Some(CpuGeometry::Triangles(geometry)) = &cpu_node.geometry {
+            let material = if let Some(material_index) = cpu_node.material_index {
+                materials
+                    .get(material_index)
+                    .ok_or_else(|| {
+                        RendererError::MissingMaterial(
+                            material_index.to_string(),
+                            cpu_node.name.clone(),
+                        )
+                    })?
+                    .clone()
+            } else {
+                M::default()
+            };
+            Some(Box::new(Gm::new(Mesh::new(context, geometry), material)) as Box<dyn SceneObject>)
+        } else {
+            None
+        };
+        let id = self.insert(
+            Some(parent),
+            Some(&cpu_node.name),
+            cpu_node.transformation,
+            object,
+        );
+        self.node_mut(id).animation = cpu_node
+            .animations
+            .first()
+            .map(|(_, key_frames)| key_frames.clone());
+        for child in cpu_node.children.iter() {
+            self.add_cpu_node(context, id, child, materials)?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Removes the given node and all of its descendants from the scene.
+    ///
+    pub fn remove(&mut self, id: SceneNodeId) {
+        if let Some(parent) = self.node(id).parent {
+            self.node_mut(parent).children.retain(|c| *c != id);
+        }
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            if let Some(node) = self.nodes[id.0].take() {
+                stack.extend(node.children);
+            }
+        }
+    }
+
+    ///
+    /// Returns the node with the given id.
+    ///
+    /// # Panic
+    /// Will panic if the node has been removed.
+    ///
+    pub fn node(&self, id: SceneNodeId) -> &Node {
+        self.nodes[id.0]
+            .as_ref()
+            .unwrap_or_else(|| panic!("the node {:?} has been removed", id))
+    }
+
+    ///
+    /// Returns the node with the given id mutably.
+    ///
+    /// # Panic
+    /// Will panic if the node has been removed.
+    ///
+    pub fn node_mut(&mut self, id: SceneNodeId) -> &mut Node {
+        self.nodes[id.0]
+            .as_mut()
+            .unwrap_or_else(|| panic!("the node {:?} has been removed", id))
+    }
+
+    ///
+    /// Returns the id of the first node with the given name, if any.
+    ///
+    pub fn find(&self, name: &str) -> Option<SceneNodeId> {
+        self.nodes
+            .iter()
+            .position(|n| n.as_ref().and_then(|n| n.name()) == Some(name))
+            .map(SceneNodeId)
+    }
+
+    ///
+    /// Sets the transformation of the given node relative to its parent and updates the world transformation of the node and all of its descendants.
+    ///
+    pub fn set_transformation(&mut self, id: SceneNodeId, transformation: Mat4) {
+        self.node_mut(id).transformation = transformation;
+        self.update_world_transformations(id);
+    }
+
+    ///
+    /// Moves the given node to the given parent, or makes it a root node if the parent is `None`, while keeping its transformation relative to the parent.
+    ///
+    /// # Panic
+    /// Will panic if the new parent is the node itself or one of its descendants.
+    ///
+    pub fn set_parent(&mut self, id: SceneNodeId, parent: Option<SceneNodeId>) {
+        let mut ancestor = parent;
+        while let Some(a) = ancestor {
+            if a == id {
+                panic!("cannot make the node {:?} a descendant of itself", id);
+            }
+            ancestor = self.node(a).parent;
+        }
+        if let Some(old_parent) = self.node(id).parent {
+            self.node_mut(old_parent).children.retain(|c| *c != id);
+        }
+        if let Some(parent) = parent {
+            self.node_mut(parent).children.push(id);
+        }
+        self.node_mut(id).parent = parent;
+        self.update_world_transformations(id);
+    }
+
+    ///
+    /// Sets whether the given node and its descendants are visible.
+    ///
+    pub fn set_visible(&mut self, id: SceneNodeId, visible: bool) {
+        self.node_mut(id).visible = visible;
+    }
+
+    ///
+    /// Returns the visible objects in the scene, ie. the objects attached to nodes where the node and all of its ancestors are visible.
+    ///
+    pub fn objects(&self) -> Vec<&dyn Object> {
+        let mut objects = Vec::new();
+        let mut stack = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.as_ref().map(|n| n.parent.is_none()).unwrap_or(false))
+            .map(|(i, _)| SceneNodeId(i))
+            .collect::<Vec<_>>();
+        while let Some(id) = stack.pop() {
+            let node = self.node(id);
+            if node.visible {
+                if let Some(object) = &node.object {
+                    objects.push(object.as_object());
+                }
+                stack.extend(node.children.iter().rev());
+            }
+        }
+        objects
+    }
+
+    ///
+    /// For updating the animation of all nodes and objects in the scene. The time parameter should be some continious time, for example the time since start.
+    ///
+    pub fn animate(&mut self, time: f32) {
+        let mut animated = Vec::new();
+        for (i, node) in self.nodes.iter_mut().enumerate() {
+            if let Some(node) = node {
+                if let Some(animation) = &node.animation {
+                    node.animation_transformation = animation.transformation(time);
+                    animated.push(SceneNodeId(i));
+                }
+                if let Some(object) = &mut node.object {
+                    object.animate(time);
+                }
+            }
+        }
+        for id in animated {
+            self.update_world_transformations(id);
+        }
+    }
+
+    fn insert(
+        &mut self,
+        parent: Option<SceneNodeId>,
+        name: Option<&str>,
+        transformation: Mat4,
+        object: Option<Box<dyn SceneObject>>,
+    ) -> SceneNodeId {
+        let id = SceneNodeId(self.nodes.len());
+        if let Some(parent) = parent {
+            self.node_mut(parent).children.push(id);
+        }
+        self.nodes.push(Some(Node {
+            name: name.map(|name| name.to_owned()),
+            transformation,
+            world_transformation: transformation,
+            visible: true,
+            parent,
+            children: Vec::new(),
+            object,
+            animation: None,
+            animation_transformation: Mat4::identity(),
+        }));
+        self.update_world_transformations(id);
+        id
+    }
+
+    fn update_world_transformations(&mut self, id: SceneNodeId) {
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let parent_transformation = self
+                .node(id)
+                .parent
+                .map(|parent| self.node(parent).world_transformation)
+                .unwrap_or(Mat4::identity());
+            let node = self.node_mut(id);
+            node.world_transformation =
+                parent_transformation * node.transformation * node.animation_transformation;
+            if let Some(object) = &mut node.object {
+                object.set_transformation(node.world_transformation);
+            }
+            stack.extend(node.children.iter().cloned());
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Scene {
+    type Item = &'a dyn Object;
+    type IntoIter = std::vec::IntoIter<&'a dyn Object>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.objects().into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn world_transformations() {
+        let mut scene = Scene::new();
+        let car = scene.add_node(
+            None,
+            Some("car"),
+            Mat4::from_translation(vec3(1.0, 0.0, 0.0)),
+        );
+        let wheel = scene.add_node(
+            Some(car),
+            Some("wheel"),
+            Mat4::from_translation(vec3(0.0, 2.0, 0.0)),
+        );
+        assert_eq!(scene.find("wheel"), Some(wheel));
+        assert_eq!(scene.node(wheel).parent(), Some(car));
+        assert_eq!(
+            scene.node(wheel).world_transformation(),
+            Mat4::from_translation(vec3(1.0, 2.0, 0.0))
+        );
+
+        scene.set_transformation(car, Mat4::from_translation(vec3(0.0, 0.0, 3.0)));
+        assert_eq!(
+            scene.node(wheel).world_transformation(),
+            Mat4::from_translation(vec3(0.0, 2.0, 3.0))
+        );
+
+        scene.set_parent(wheel, None);
+        assert!(scene.node(car).children().is_empty());
+        assert_eq!(
+            scene.node(wheel).world_transformation(),
+            Mat4::from_translation(vec3(0.0, 2.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn remove_descendants() {
+        let mut scene = Scene::new();
+        let root = scene.add_node(None, Some("root"), Mat4::identity());
+        let arm = scene.add_node(Some(root), Some("arm"), Mat4::identity());
+        scene.add_node(Some(arm), Some("hand"), Mat4::identity());
+        scene.remove(arm);
+        assert_eq!(scene.find("hand"), None);
+        assert!(scene.node(root).children().is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn parent_to_descendant() {
+        let mut scene = Scene::new();
+        let root = scene.add_node(None, None, Mat4::identity());
+        let child = scene.add_node(Some(root), None, Mat4::identity());
+        scene.set_parent(root, Some(child));
+    }
+}
@@ -0,0 +1,103 @@
+#include "shared"
+#include "normal_mapping"
+
+uniform mat4 viewProjectionInverse;
+uniform mat4 decalInverseTransformation;
+uniform mat3 decalAxes;
+uniform vec2 screenSize;
+uniform float cosMaxAngle;
+
+uniform vec4 albedo;
+#ifdef USE_ALBEDO_TEXTURE
+uniform sampler2D albedoTexture;
+uniform mat3 albedoTexTransform;
+#endif
+
+#ifdef USE_NORMAL_TEXTURE
+uniform sampler2D normalTexture;
+uniform mat3 normalTexTransform;
+uniform float normalScale;
+#endif
+
+#ifdef USE_ORM_TEXTURE
+uniform sampler2D ormTexture;
+uniform mat3 ormTexTransform;
+#endif
+
+layout (location = 0) out vec4 outColor;
+layout (location = 1) out vec4 outNormal;
+
+void main()
+{
+    vec2 screen_uv = gl_FragCoord.xy / screenSize;
+    float depth = sample_depth(screen_uv);
+    if(depth > 0.99999)
+    {
+        discard;
+    }
+
+    // Project the surface underneath into the decal box
+    vec3 position = world_pos_from_depth(viewProjectionInverse, depth, screen_uv);
+    vec3 local_position = (decalInverseTransformation * vec4(position, 1.0)).xyz;
+    if(any(greaterThan(abs(local_position), vec3(1.0))))
+    {
+        discard;
+    }
+
+    // Decode the geometry information
+    vec4 c = sample_layer(screen_uv, 0);
+    vec3 surface_color = c.rgb;
+    float metallic_factor = c.w;
+    vec4 n = sample_layer(screen_uv, 1);
+    vec2 n2 = n.xy*2.0 - 1.0;
+    float z = 1.0 - n2.x * n2.x - n2.y * n2.y;
+    if (z > 0.0001) {
+        z = sqrt(z);
+    }
+    vec3 normal = normalize(vec3(n2.x, n2.y, (int(floor(n.z * 255.0)) & 128) == 128 ? z: -z));
+    float roughness_factor = n.w;
+    float occlusion = float(int(floor(n.z * 255.0)) & 127) / 127.0;
+
+    // Avoid stretching the decal onto surfaces that are almost parallel to the projection direction
+    vec3 decal_normal = normalize(decalAxes[2]);
+    if(dot(normal, decal_normal) < cosMaxAngle)
+    {
+        discard;
+    }
+
+    vec2 uvs = vec2(0.5 * local_position.x + 0.5, 0.5 - 0.5 * local_position.y);
+    vec4 decal_color = albedo;
+#ifdef USE_ALBEDO_TEXTURE
+    vec4 t = texture(albedoTexture, (albedoTexTransform * vec3(uvs, 1.0)).xy);
+    decal_color *= vec4(rgb_from_srgb(t.rgb), t.a);
+#endif
+    float alpha = decal_color.a;
+    if(alpha < 0.001)
+    {
+        discard;
+    }
+    surface_color = mix(surface_color, decal_color.rgb, alpha);
+
+#ifdef USE_NORMAL_TEXTURE
+    vec3 tangent = normalize(decalAxes[0] - normal * dot(normal, decalAxes[0]));
+    vec3 bitangent = cross(normal, tangent);
+    vec3 decal_surface_normal = perturb_normal(normal, tangent, bitangent, texture(normalTexture, (normalTexTransform * vec3(uvs, 1.0)).xy).xyz, normalScale);
+    normal = normalize(mix(normal, decal_surface_normal, alpha));
+#endif
+
+#ifdef USE_ORM_TEXTURE
+    vec3 orm = texture(ormTexture, (ormTexTransform * vec3(uvs, 1.0)).xy).rgb;
+    occlusion = mix(occlusion, orm.r, alpha);
+    roughness_factor = mix(roughness_factor, orm.g, alpha);
+    metallic_factor = mix(metallic_factor, orm.b, alpha);
+#endif
+
+    // Encode the geometry information in the same way as the geometry pass
+    outColor = vec4(surface_color, metallic_factor);
+    int o = int(occlusion * 127.0);
+    int nz = 1;
+    if(normal.z < 0.0) {
+        nz = 0;
+    }
+    outNormal = vec4(0.5 * normal.xy + 0.5, float(o | nz << 7)/255.0, roughness_factor);
+}
@@ -1,5 +1,5 @@
 uniform vec3 offset;
-uniform mat4 viewProjection;
+#include "camera"
 uniform float time;
 
 uniform vec4 waveParameters[4];
@@ -148,7 +148,12 @@ impl Geometry for Skybox {
                     program.use_uniform("view", camera.view());
                     program.use_uniform("projection", camera.projection());
                     program.use_vertex_attribute("position", &self.vertex_buffer);
-                    program.draw_arrays(material.render_states(), camera.viewport(), 36);
+                    program.draw_arrays(
+                        material.render_states(),
+                        camera.viewport(),
+                        PrimitiveTopology::Triangles,
+                        36,
+                    );
                 },
             )
             .expect("Failed compiling shader");
@@ -172,7 +177,12 @@ impl Geometry for Skybox {
                     program.use_uniform("view", camera.view());
                     program.use_uniform("projection", camera.projection());
                     program.use_vertex_attribute("position", &self.vertex_buffer);
-                    program.draw_arrays(material.render_states(), camera.viewport(), 36);
+                    program.draw_arrays(
+                        material.render_states(),
+                        camera.viewport(),
+                        PrimitiveTopology::Triangles,
+                        36,
+                    );
                 },
             )
             .expect("Failed compiling shader");
@@ -358,7 +358,12 @@ impl TerrainPatch {
         if required_attributes.normal || required_attributes.tangents {
             program.use_vertex_attribute("normal", &self.normals_buffer);
         }
-        program.draw_elements(render_states, camera.viewport(), &self.index_buffer);
+        program.draw_elements(
+            render_states,
+            camera.viewport(),
+            PrimitiveTopology::Triangles,
+            &self.index_buffer,
+        );
     }
 }
 
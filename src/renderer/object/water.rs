@@ -215,7 +215,7 @@ impl WaterPatch {
             "offset",
             self.center + vec3(self.offset.x, 0.0, self.offset.y),
         );
-        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_camera_block(camera);
         program.use_uniform("time", self.time * 0.001);
         program.use_uniform_array(
             "waveParameters",
@@ -235,7 +235,12 @@ impl WaterPatch {
         );
 
         program.use_vertex_attribute("position", &self.position_buffer);
-        program.draw_elements(render_states, camera.viewport(), &self.index_buffer);
+        program.draw_elements(
+            render_states,
+            camera.viewport(),
+            PrimitiveTopology::Triangles,
+            &self.index_buffer,
+        );
     }
 }
 
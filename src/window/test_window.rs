@@ -140,6 +140,7 @@ impl<T> Window<T> {
                         depth_texture.as_depth_target(),
                     )),
                 });
+                self.context.end_profiler_frame();
                 frame_count += 1;
             }
         }
@@ -320,6 +320,7 @@ impl<T: 'static + Clone> Window<T> {
                     };
                     first_frame = false;
                     let frame_output = callback(frame_input);
+                    self.gl.end_profiler_frame();
                     if frame_output.exit {
                         *control_flow = ControlFlow::Exit;
                     } else {